@@ -7,7 +7,15 @@ pub enum Error {
     CommonError(String),
     NotExist(String),
     Exist(String),
+    // the stored object has moved on from the revision the caller expects
+    Conflict(String),
     SchedulerNoEnoughResource(String),
+    // subtracting want of resource from a pool which only has have left
+    ResourceUnderflow {
+        resource: String,
+        have: u64,
+        want: u64,
+    },
     SerdeJsonError(SerdeJsonError),
     StdIOErr(std::io::Error),
     ReqWestErr(reqwest::Error),
@@ -24,6 +24,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use futures::channel::mpsc;
 use serde::Deserialize;
 use serde::Serialize;
 use serde_json::Value;
@@ -34,14 +35,21 @@ use std::sync::Arc;
 use std::sync::Mutex;
 
 use crate::common::*;
+use crate::obj_mgr::func_mgr::*;
+use crate::obj_mgr::funcsnapshot_mgr::*;
+use crate::obj_mgr::namespace_mgr::*;
+use crate::obj_mgr::node_mgr::*;
+use crate::obj_mgr::pod_mgr::*;
+use crate::obj_mgr::tenant_mgr::*;
+use crate::validation::{ErrorList, FieldPath, IndexPath};
 
-use super::selector::Labels;
+use super::selector::{FieldSelector, Labels, Selector};
 
 pub trait DeepCopy {
     fn DeepCopy(&self) -> Self;
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum EventType {
     None,
     Added,
@@ -79,13 +87,25 @@ pub struct DeltaEvent {
     pub oldObj: Option<DataObject<Value>>,
 }
 
-#[derive(Debug)]
+// WatchEvent is what a watch stream sends to clients, one json object per line.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WatchEvent {
+    #[serde(rename = "type")]
     pub type_: EventType,
 
+    #[serde(rename = "object", default)]
     pub obj: DataObject<Value>,
 }
 
+impl WatchEvent {
+    pub fn FromDelta(event: &DeltaEvent) -> Self {
+        return Self {
+            type_: event.type_.clone(),
+            obj: event.obj.clone(),
+        };
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EdgeListGraph<T: Serialize> {
     nodes: T,
@@ -104,16 +124,22 @@ pub struct DataObject<SpecType: Serialize + Clone + core::fmt::Debug + Default>
     pub annotations: Labels,
 
     // Revision of the Channel
-    #[serde(skip_serializing, default)]
+    #[serde(default, skip_serializing_if = "IsZeroRevision")]
     pub channelRev: i64,
 
-    // revision number set by creator of object such as etcd
-    #[serde(skip_serializing, default)]
+    // revision number set by creator of object such as etcd. It's sent with the object
+    // read from the store, so that a client can guard its update with it, and left out
+    // while it's 0, e.g. in the object a client writes.
+    #[serde(default, skip_serializing_if = "IsZeroRevision")]
     pub revision: i64,
 
     pub object: SpecType,
 }
 
+fn IsZeroRevision(revision: &i64) -> bool {
+    return *revision == 0;
+}
+
 impl DataObject<Value> {
     pub fn To<T: Serialize + for<'a> Deserialize<'a> + Clone + core::fmt::Debug + Default>(
         &self,
@@ -134,6 +160,451 @@ impl DataObject<Value> {
     }
 }
 
+impl DataObject<Value> {
+    // Typed converts the object to the typed object of its kind.
+    pub fn Typed(&self) -> Result<TypedObject> {
+        let kind = LookupObjectKind(&self.objType)?;
+        return (kind.typed)(self);
+    }
+
+    // Normalize round trips the object through the typed object of its kind, so the
+    // defaulted fields are filled and the legacy forms are migrated.
+    pub fn Normalize(&self) -> Result<DataObject<Value>> {
+        let mut obj = self.Typed()?.DataObject();
+        obj.channelRev = self.channelRev;
+        obj.revision = self.revision;
+        return Ok(obj);
+    }
+
+    // Validate runs the validator registered for the object's kind.
+    pub fn Validate(&self) -> ErrorList {
+        match LookupObjectKind(&self.objType) {
+            Err(e) => {
+                let mut errs = ErrorList::default();
+                errs.Check("type", Err(e));
+                return errs;
+            }
+            Ok(kind) => return (kind.validator)(self),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatchType {
+    // RFC 7386 JSON merge patch
+    Merge,
+    // RFC 6902 JSON patch
+    Json,
+}
+
+impl PatchType {
+    pub fn Parse(s: &str) -> Result<Self> {
+        match s {
+            "merge" => return Ok(Self::Merge),
+            "json" => return Ok(Self::Json),
+            _ => {
+                return Err(Error::CommonError(format!(
+                    "unknown patch type {:?}, expect merge or json",
+                    s
+                )))
+            }
+        }
+    }
+}
+
+impl DataObject<Value> {
+    // Patch applies a patch document to the whole object, i.e. paths start at the
+    // object root such as /object/spec/resources. The identity fields (type, tenant,
+    // namespace, name) can't be changed by a patch. The revisions are kept.
+    pub fn Patch(&self, patchType: PatchType, patch: &Value) -> Result<DataObject<Value>> {
+        let mut doc = serde_json::to_value(self)?;
+        match patchType {
+            PatchType::Merge => MergePatch(&mut doc, patch),
+            PatchType::Json => JsonPatch(&mut doc, patch)?,
+        }
+
+        let mut obj: DataObject<Value> = serde_json::from_value(doc)?;
+        if obj.StoreKey() != self.StoreKey() {
+            return Err(Error::CommonError(format!(
+                "patch can't change object identity from {} to {}",
+                self.StoreKey(),
+                obj.StoreKey()
+            )));
+        }
+
+        obj.channelRev = self.channelRev;
+        obj.revision = self.revision;
+        return Ok(obj);
+    }
+
+    // Diff returns the differences of the user set fields from self to other: the
+    // labels, annotations and object. The object status and the spec fields set by the
+    // gateway are not compared, nor are the revisions. Both are normalized first, so a
+    // field left to its default doesn't differ from the default value.
+    pub fn Diff(&self, other: &DataObject<Value>) -> Result<Vec<ValueDiff>> {
+        let (this, other) = (self.Normalize()?, other.Normalize()?);
+        let mut diffs = Vec::new();
+        DiffValue(
+            "labels",
+            &serde_json::to_value(&this.labels)?,
+            &serde_json::to_value(&other.labels)?,
+            &mut diffs,
+        );
+        DiffValue(
+            "annotations",
+            &serde_json::to_value(&this.annotations)?,
+            &serde_json::to_value(&other.annotations)?,
+            &mut diffs,
+        );
+
+        let userObject = |o: &Value| -> Value {
+            let mut o = o.clone();
+            if let Some(map) = o.as_object_mut() {
+                map.remove("status");
+            }
+            return o;
+        };
+        DiffValue(
+            "object",
+            &userObject(&this.object),
+            &userObject(&other.object),
+            &mut diffs,
+        );
+        diffs.retain(|d| Classify(&self.objType, &d.path) != DiffClass::System);
+        return Ok(diffs);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DiffClass {
+    // a function spec change which makes the existing snapshots stale
+    Resnapshot,
+    Cosmetic,
+    // set by the gateway, not shown
+    System,
+}
+
+impl DiffClass {
+    pub fn Mark(&self) -> &'static str {
+        match self {
+            Self::Resnapshot => return "!",
+            Self::Cosmetic => return "~",
+            Self::System => return " ",
+        }
+    }
+}
+
+// Classify tells whether a change of the field at path of an object of objType forces
+// a new snapshot. Only the function spec is snapshotted.
+pub fn Classify(objType: &str, path: &str) -> DiffClass {
+    if objType != Function::KEY {
+        return DiffClass::Cosmetic;
+    }
+
+    let field = match path.strip_prefix("object.spec.") {
+        None => return DiffClass::Cosmetic,
+        Some(rest) => rest.split(['.', '[']).next().unwrap_or(rest),
+    };
+    if FuncSpec::SYSTEM_FIELDS.contains(&field) {
+        return DiffClass::System;
+    }
+    if FuncSpec::ForcesResnapshot(field) {
+        return DiffClass::Resnapshot;
+    }
+    return DiffClass::Cosmetic;
+}
+
+// ValueDiff is a changed leaf of a json document, old or new is None if the value is
+// added or removed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueDiff {
+    pub path: String,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+// DiffValue appends the differences from old to new down to the changed leaves.
+// Objects are compared by member and arrays by index, the paths are the ones of the
+// validation errors such as object.spec.commands[2].
+pub fn DiffValue(path: &str, old: &Value, new: &Value, diffs: &mut Vec<ValueDiff>) {
+    match (old, new) {
+        (Value::Object(o), Value::Object(n)) => {
+            for (k, ov) in o {
+                match n.get(k) {
+                    Some(nv) => DiffValue(&FieldPath(path, k), ov, nv, diffs),
+                    None => diffs.push(ValueDiff {
+                        path: FieldPath(path, k),
+                        old: Some(ov.clone()),
+                        new: None,
+                    }),
+                }
+            }
+            for (k, nv) in n {
+                if !o.contains_key(k) {
+                    diffs.push(ValueDiff {
+                        path: FieldPath(path, k),
+                        old: None,
+                        new: Some(nv.clone()),
+                    });
+                }
+            }
+        }
+        (Value::Array(o), Value::Array(n)) => {
+            for i in 0..o.len().max(n.len()) {
+                match (o.get(i), n.get(i)) {
+                    (Some(ov), Some(nv)) => DiffValue(&IndexPath(path, i), ov, nv, diffs),
+                    (ov, nv) => diffs.push(ValueDiff {
+                        path: IndexPath(path, i),
+                        old: ov.cloned(),
+                        new: nv.cloned(),
+                    }),
+                }
+            }
+        }
+        (o, n) if o != n => diffs.push(ValueDiff {
+            path: path.to_owned(),
+            old: Some(o.clone()),
+            new: Some(n.clone()),
+        }),
+        _ => (),
+    }
+}
+
+// MergePatch applies a RFC 7386 merge patch: objects are merged recursively, null
+// removes a member and any other value replaces the target.
+pub fn MergePatch(target: &mut Value, patch: &Value) {
+    let patch = match patch {
+        Value::Object(p) => p,
+        _ => {
+            *target = patch.clone();
+            return;
+        }
+    };
+
+    if !target.is_object() {
+        *target = Value::Object(serde_json::Map::new());
+    }
+
+    let map = target.as_object_mut().unwrap();
+    for (k, v) in patch {
+        if v.is_null() {
+            map.remove(k);
+        } else {
+            MergePatch(map.entry(k.clone()).or_insert(Value::Null), v);
+        }
+    }
+}
+
+// JsonPatch applies a RFC 6902 patch, an array of add/remove/replace/move/copy/test
+// operations. The operations are applied atomically: target is only changed if all
+// of them succeed.
+pub fn JsonPatch(target: &mut Value, patch: &Value) -> Result<()> {
+    let ops = match patch.as_array() {
+        None => {
+            return Err(Error::CommonError(
+                "json patch must be an array of operations".to_owned(),
+            ))
+        }
+        Some(ops) => ops,
+    };
+
+    let mut doc = target.clone();
+    for (idx, op) in ops.iter().enumerate() {
+        match JsonPatchOp(&mut doc, op) {
+            Err(Error::CommonError(e)) => {
+                return Err(Error::CommonError(format!("json patch op {}: {}", idx, e)))
+            }
+            Err(e) => return Err(e),
+            Ok(()) => (),
+        }
+    }
+
+    *target = doc;
+    return Ok(());
+}
+
+fn JsonPatchOp(doc: &mut Value, op: &Value) -> Result<()> {
+    let field = |name: &str| -> Result<&str> {
+        match op.get(name).and_then(|v| v.as_str()) {
+            None => return Err(Error::CommonError(format!("missing string field {:?}", name))),
+            Some(s) => return Ok(s),
+        }
+    };
+    let value = || -> Result<Value> {
+        match op.get("value") {
+            None => return Err(Error::CommonError("missing field \"value\"".to_owned())),
+            Some(v) => return Ok(v.clone()),
+        }
+    };
+
+    let path = field("path")?;
+    match field("op")? {
+        "add" => return PointerAdd(doc, path, value()?),
+        "remove" => {
+            PointerRemove(doc, path)?;
+            return Ok(());
+        }
+        "replace" => {
+            let v = value()?;
+            match doc.pointer_mut(path) {
+                None => return Err(Error::CommonError(format!("path {:?} doesn't exist", path))),
+                Some(t) => *t = v,
+            }
+            return Ok(());
+        }
+        "move" => {
+            let from = field("from")?;
+            if path.starts_with(from) && path[from.len()..].starts_with('/') {
+                return Err(Error::CommonError(format!(
+                    "can't move {:?} into its child {:?}",
+                    from, path
+                )));
+            }
+            let v = PointerRemove(doc, from)?;
+            return PointerAdd(doc, path, v);
+        }
+        "copy" => {
+            let from = field("from")?;
+            let v = match doc.pointer(from) {
+                None => return Err(Error::CommonError(format!("path {:?} doesn't exist", from))),
+                Some(v) => v.clone(),
+            };
+            return PointerAdd(doc, path, v);
+        }
+        "test" => {
+            let v = value()?;
+            if doc.pointer(path) != Some(&v) {
+                return Err(Error::CommonError(format!(
+                    "test fail at {:?}, expect {}",
+                    path, v
+                )));
+            }
+            return Ok(());
+        }
+        o => return Err(Error::CommonError(format!("unknown op {:?}", o))),
+    }
+}
+
+// SplitPointer splits a JSON pointer into the parent pointer and the unescaped last token.
+fn SplitPointer(path: &str) -> Result<(&str, String)> {
+    match path.rfind('/') {
+        None => {
+            return Err(Error::CommonError(format!(
+                "invalid json pointer {:?}",
+                path
+            )))
+        }
+        Some(idx) => {
+            let token = path[idx + 1..].replace("~1", "/").replace("~0", "~");
+            return Ok((&path[..idx], token));
+        }
+    }
+}
+
+fn ArrayIndex(token: &str, len: usize, allowEnd: bool) -> Result<usize> {
+    if allowEnd && token == "-" {
+        return Ok(len);
+    }
+
+    let valid = !token.is_empty()
+        && token.bytes().all(|b| b.is_ascii_digit())
+        && (token == "0" || !token.starts_with('0'));
+    match token.parse::<usize>() {
+        Ok(idx) if valid && (idx < len || (allowEnd && idx == len)) => return Ok(idx),
+        _ => {
+            return Err(Error::CommonError(format!(
+                "invalid array index {:?} for array of length {}",
+                token, len
+            )))
+        }
+    }
+}
+
+fn PointerAdd(doc: &mut Value, path: &str, value: Value) -> Result<()> {
+    if path.is_empty() {
+        *doc = value;
+        return Ok(());
+    }
+
+    let (parent, token) = SplitPointer(path)?;
+    match doc.pointer_mut(parent) {
+        Some(Value::Object(map)) => {
+            map.insert(token, value);
+        }
+        Some(Value::Array(arr)) => {
+            let idx = ArrayIndex(&token, arr.len(), true)?;
+            arr.insert(idx, value);
+        }
+        _ => {
+            return Err(Error::CommonError(format!(
+                "parent of path {:?} isn't an object or array",
+                path
+            )))
+        }
+    }
+
+    return Ok(());
+}
+
+fn PointerRemove(doc: &mut Value, path: &str) -> Result<Value> {
+    let (parent, token) = SplitPointer(path)?;
+    let removed = match doc.pointer_mut(parent) {
+        Some(Value::Object(map)) => map.remove(&token),
+        Some(Value::Array(arr)) => {
+            let idx = ArrayIndex(&token, arr.len(), false)?;
+            Some(arr.remove(idx))
+        }
+        _ => None,
+    };
+
+    match removed {
+        None => return Err(Error::CommonError(format!("path {:?} doesn't exist", path))),
+        Some(v) => return Ok(v),
+    }
+}
+
+// ObjectFormat is the format of an object file. Both formats can hold several objects:
+// Json files are a sequence of json objects, which includes JSON Lines, and Yaml files
+// are a stream of documents separated by "---".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectFormat {
+    Json,
+    Yaml,
+}
+
+impl ObjectFormat {
+    // FromFilename picks the format by file extension, None if it isn't an object file.
+    pub fn FromFilename(filename: &str) -> Option<Self> {
+        let ext = match filename.rsplit_once('.') {
+            None => return None,
+            Some((_, ext)) => ext,
+        };
+
+        match ext {
+            "json" | "jsonl" | "ndjson" => return Some(Self::Json),
+            "yaml" | "yml" => return Some(Self::Yaml),
+            _ => return None,
+        }
+    }
+
+    // Detect guesses the format of content without a file name, e.g. read from stdin.
+    // A json object or array of objects is json, anything else, including json after a
+    // yaml comment, is read as yaml.
+    pub fn Detect(content: &str) -> Self {
+        let content = StripBom(content).trim_start();
+        if content.starts_with('{') || content.starts_with('[') {
+            return Self::Json;
+        }
+
+        return Self::Yaml;
+    }
+}
+
+// StripBom removes the byte order mark some editors write at the start of a file.
+fn StripBom(content: &str) -> &str {
+    return content.strip_prefix('\u{feff}').unwrap_or(content);
+}
+
 impl<SpecType: Serialize + for<'a> Deserialize<'a> + Clone + core::fmt::Debug + Default>
     DataObject<SpecType>
 {
@@ -179,6 +650,43 @@ impl<SpecType: Serialize + for<'a> Deserialize<'a> + Clone + core::fmt::Debug +
         let inner: DataObject<SpecType> = serde_json::from_str::<DataObject<SpecType>>(s)?;
         return Ok(inner);
     }
+
+    // NewListFromString parses all the objects in s. Parse errors report the line and
+    // column in s; empty yaml documents are skipped. Json is a stream of objects or a
+    // single array of them.
+    pub fn NewListFromString(s: &str, format: ObjectFormat) -> Result<Vec<DataObject<SpecType>>> {
+        let s = StripBom(s);
+        let mut objs = Vec::new();
+        match format {
+            ObjectFormat::Json if s.trim_start().starts_with('[') => {
+                match serde_json::from_str::<Vec<Self>>(s) {
+                    Err(e) => return Err(Error::CommonError(format!("{}", e))),
+                    Ok(list) => objs = list,
+                }
+            }
+            ObjectFormat::Json => {
+                let stream = serde_json::Deserializer::from_str(s).into_iter::<Self>();
+                for obj in stream {
+                    match obj {
+                        Err(e) => return Err(Error::CommonError(format!("{}", e))),
+                        Ok(obj) => objs.push(obj),
+                    }
+                }
+            }
+            ObjectFormat::Yaml => {
+                // the document iterator keeps returning a syntax error, stop at the first error
+                for doc in serde_yaml::Deserializer::from_str(s) {
+                    match Option::<Self>::deserialize(doc) {
+                        Err(e) => return Err(Error::CommonError(format!("{}", e))),
+                        Ok(None) => (),
+                        Ok(Some(obj)) => objs.push(obj),
+                    }
+                }
+            }
+        }
+
+        return Ok(objs);
+    }
     pub fn Tenant(&self) -> String {
         return self.tenant.clone();
     }
@@ -213,6 +721,19 @@ impl<SpecType: Serialize + for<'a> Deserialize<'a> + Clone + core::fmt::Debug +
         return self.revision;
     }
 
+    // ExpectRevision is the revision to guard an update of this stored object with. A
+    // stored object always has one, 0 means the store didn't send it and the update
+    // can't be made conditional.
+    pub fn ExpectRevision(&self) -> Result<i64> {
+        if self.revision == 0 {
+            return Err(Error::CommonError(format!(
+                "{} has no revision, can't guard its update against concurrent changes",
+                self.StoreKey()
+            )));
+        }
+        return Ok(self.revision);
+    }
+
     pub fn Labels(&self) -> Labels {
         let lables = self.labels.clone();
         return lables;
@@ -235,9 +756,214 @@ impl DeepCopy for DataObject<Value> {
     }
 }
 
+// ObjectScope is where the objects of a kind are stored in the tenant/namespace tree.
+// The order is also the dependency order: an object's tenant and namespace have to
+// exist before it can be created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ObjectScope {
+    // stored under the system tenant and namespace, e.g. tenants and nodes
+    System,
+    // stored under the system namespace of its tenant, e.g. namespaces
+    Tenant,
+    // stored in a namespace of a tenant, e.g. functions and pods
+    Namespace,
+}
+
+// ObjectKind binds the objType string of a DataObject to its spec type.
+pub trait ObjectKind:
+    Serialize + for<'a> Deserialize<'a> + Clone + core::fmt::Debug + Default
+{
+    const KIND: &'static str;
+    const SCOPE: ObjectScope;
+
+    fn Validate(obj: &DataObject<Value>) -> ErrorList;
+    fn Typed(obj: DataObject<Self>) -> TypedObject;
+}
+
+#[derive(Debug, Clone)]
+pub enum TypedObject {
+    Tenant(Tenant),
+    Namespace(Namespace),
+    Node(Node),
+    Function(Function),
+    Pod(FuncPod),
+    Snapshot(FuncSnapshot),
+}
+
+impl TypedObject {
+    pub fn Kind(&self) -> &'static str {
+        match self {
+            Self::Tenant(_) => return TenantObject::KIND,
+            Self::Namespace(_) => return NamespaceObject::KIND,
+            Self::Node(_) => return NodeSpec::KIND,
+            Self::Function(_) => return FuncObject::KIND,
+            Self::Pod(_) => return FuncPodObject::KIND,
+            Self::Snapshot(_) => return ContainerSnapshot::KIND,
+        }
+    }
+
+    pub fn DataObject(&self) -> DataObject<Value> {
+        match self {
+            Self::Tenant(o) => return o.DataObject(),
+            Self::Namespace(o) => return o.DataObject(),
+            Self::Node(o) => return o.DataObject(),
+            Self::Function(o) => return o.DataObject(),
+            Self::Pod(o) => return o.DataObject(),
+            Self::Snapshot(o) => return o.DataObject(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ObjectKindInfo {
+    pub kind: &'static str,
+    pub scope: ObjectScope,
+    pub validator: fn(&DataObject<Value>) -> ErrorList,
+    pub typed: fn(&DataObject<Value>) -> Result<TypedObject>,
+}
+
+impl ObjectKindInfo {
+    pub fn Of<T: ObjectKind>() -> Self {
+        return Self {
+            kind: T::KIND,
+            scope: T::SCOPE,
+            validator: T::Validate,
+            typed: |obj| Ok(T::Typed(obj.To::<T>()?)),
+        };
+    }
+}
+
+lazy_static::lazy_static! {
+    pub static ref OBJECT_KINDS: BTreeMap<&'static str, ObjectKindInfo> = [
+        ObjectKindInfo::Of::<TenantObject>(),
+        ObjectKindInfo::Of::<NamespaceObject>(),
+        ObjectKindInfo::Of::<NodeSpec>(),
+        ObjectKindInfo::Of::<FuncObject>(),
+        ObjectKindInfo::Of::<FuncPodObject>(),
+        ObjectKindInfo::Of::<ContainerSnapshot>(),
+    ].iter().map(|k| (k.kind, *k)).collect();
+}
+
+pub fn LookupObjectKind(kind: &str) -> Result<&'static ObjectKindInfo> {
+    match OBJECT_KINDS.get(kind) {
+        None => {
+            return Err(Error::CommonError(format!(
+                "unknown object type {}, expect one of {:?}",
+                kind,
+                OBJECT_KINDS.keys().collect::<Vec<_>>()
+            )))
+        }
+        Some(k) => return Ok(k),
+    }
+}
+
+// DeltaEventStream is the receiving end of a DataObjectMgr watch. The watch is
+// cancelled when the stream is dropped.
+pub type DeltaEventStream = mpsc::UnboundedReceiver<DeltaEvent>;
+
+#[derive(Debug)]
+pub struct ObjectWatcher {
+    // "<tenant>/<namespace>/" prefix of the watched object keys, empty for all objects
+    pub prefix: String,
+    pub selector: Selector,
+    pub sender: mpsc::UnboundedSender<DeltaEvent>,
+}
+
+impl ObjectWatcher {
+    pub fn Match<SpecType: Serialize + Clone + core::fmt::Debug + Default>(
+        &self,
+        obj: &DataObject<SpecType>,
+    ) -> bool {
+        let key = format!("{}/{}/{}", &obj.tenant, &obj.namespace, &obj.name);
+        return key.starts_with(&self.prefix) && self.selector.Match(&obj.labels);
+    }
+
+    // Send returns false when the receiver has been dropped
+    pub fn Send(&self, event: DeltaEvent) -> bool {
+        return self.sender.unbounded_send(event).is_ok();
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct DataObjectMgrInner<SpecType: Serialize + Clone + core::fmt::Debug + Default> {
     pub objs: BTreeMap<String, DataObject<SpecType>>,
+    pub watchers: Vec<ObjectWatcher>,
+    // the replaced versions of each object, oldest first, at most HISTORY_LIMIT. They
+    // are only kept in memory: after a restart an object's history starts over from
+    // its stored version.
+    pub history: BTreeMap<String, Vec<DataObject<SpecType>>>,
+    // HistoryFilter of the managers created by NewWithHistory, None if the replaced
+    // versions are not kept.
+    pub historyFilter: Option<HistoryFilter<SpecType>>,
+}
+
+// HistoryFilter tells whether the update of old to new is worth a history entry.
+pub type HistoryFilter<SpecType> = fn(&DataObject<SpecType>, &DataObject<SpecType>) -> bool;
+
+impl<SpecType: Serialize + for<'a> Deserialize<'a> + Clone + core::fmt::Debug + Default>
+    DataObjectMgrInner<SpecType>
+{
+    pub const HISTORY_LIMIT: usize = 10;
+
+    fn AddHistory(&mut self, key: &str, oldObj: DataObject<SpecType>, obj: &DataObject<SpecType>) {
+        match self.historyFilter {
+            Some(filter) if filter(&oldObj, obj) => (),
+            _ => return,
+        }
+
+        let history = self.history.entry(key.to_owned()).or_default();
+        history.push(oldObj);
+        if history.len() > Self::HISTORY_LIMIT {
+            history.remove(0);
+        }
+    }
+
+    // Notify sends the change of obj to all the matching watchers and drops the
+    // watchers whose stream is gone. For a Modified event whose new object doesn't
+    // match a watcher any more but the old object did, the watcher gets a Deleted
+    // event, so that its view stays consistent with its filter.
+    pub fn Notify(
+        &mut self,
+        type_: EventType,
+        obj: &DataObject<SpecType>,
+        oldObj: Option<&DataObject<SpecType>>,
+    ) {
+        if self.watchers.len() == 0 {
+            return;
+        }
+
+        let valueObj = obj.DataObject();
+        let oldValueObj = oldObj.map(|o| o.DataObject());
+        self.watchers.retain(|w| {
+            let newMatch = w.Match(obj);
+            let oldMatch = match oldObj {
+                None => false,
+                Some(o) => w.Match(o),
+            };
+
+            let type_ = match (&type_, oldMatch, newMatch) {
+                (EventType::Modified, false, true) => EventType::Added,
+                (EventType::Modified, true, false) => EventType::Deleted,
+                (_, false, false) => return true,
+                (t, _, _) => t.DeepCopy(),
+            };
+
+            let (obj, oldObj) = if type_ == EventType::Deleted && oldMatch && !newMatch {
+                (oldValueObj.clone().unwrap(), None)
+            } else if type_ == EventType::Modified {
+                (valueObj.clone(), oldValueObj.clone())
+            } else {
+                (valueObj.clone(), None)
+            };
+
+            return w.Send(DeltaEvent {
+                type_: type_,
+                inInitialList: false,
+                obj: obj,
+                oldObj: oldObj,
+            });
+        });
+    }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -256,6 +982,14 @@ impl<SpecType: Serialize + Clone + core::fmt::Debug + Default> Deref for DataObj
 impl<SpecType: Serialize + for<'a> Deserialize<'a> + Clone + core::fmt::Debug + Default>
     DataObjectMgr<SpecType>
 {
+    // NewWithHistory returns a manager which keeps the versions replaced by the updates
+    // that filter accepts, see History. The default manager keeps none.
+    pub fn NewWithHistory(filter: HistoryFilter<SpecType>) -> Self {
+        let mgr = Self::default();
+        mgr.lock().unwrap().historyFilter = Some(filter);
+        return mgr;
+    }
+
     pub fn Contains(&self, tenant: &str, namespace: &str, name: &str) -> bool {
         let key = format!("{}/{}/{}", tenant, namespace, name);
         return self.lock().unwrap().objs.contains_key(&key);
@@ -271,20 +1005,15 @@ impl<SpecType: Serialize + for<'a> Deserialize<'a> + Clone + core::fmt::Debug +
         } else {
             "".to_owned()
         };
-        for (key, _) in self
-            .lock()
-            .unwrap()
+        let inner = self.lock().unwrap();
+        let first = inner
             .objs
             .range::<String, _>((Included(start.clone()), Unbounded))
-        {
-            if key.starts_with(&start) {
-                return false;
-            } else {
-                break;
-            }
+            .next();
+        match first {
+            Some((key, _)) if key.starts_with(&start) => return false,
+            _ => return true,
         }
-
-        return true;
     }
 
     pub fn GetObjectKeys(&self, tenant: &str, namespace: &str) -> Result<Vec<String>> {
@@ -372,6 +1101,45 @@ impl<SpecType: Serialize + for<'a> Deserialize<'a> + Clone + core::fmt::Debug +
         return Ok(vec);
     }
 
+    // GetObjectsBySelector returns the objects under tenant/namespace whose labels
+    // match selector, with the same tenant/namespace scoping as GetObjects.
+    pub fn GetObjectsBySelector(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        selector: &Selector,
+    ) -> Result<Vec<DataObject<SpecType>>> {
+        let mut objs = self.GetObjects(tenant, namespace)?;
+        objs.retain(|o| selector.Match(&o.labels));
+        return Ok(objs);
+    }
+
+    // GetObjectsByPrefixAndSelector is GetObjectsByPrefix filtered by the label selector.
+    pub fn GetObjectsByPrefixAndSelector(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        nameprefix: &str,
+        selector: &Selector,
+    ) -> Result<Vec<DataObject<SpecType>>> {
+        let mut objs = self.GetObjectsByPrefix(tenant, namespace, nameprefix)?;
+        objs.retain(|o| selector.Match(&o.labels));
+        return Ok(objs);
+    }
+
+    // GetObjectsByFieldSelector returns the objects under tenant/namespace whose
+    // fields match selector, e.g. "object.status.state=Ready".
+    pub fn GetObjectsByFieldSelector(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        selector: &FieldSelector,
+    ) -> Result<Vec<DataObject<SpecType>>> {
+        let mut objs = self.GetObjects(tenant, namespace)?;
+        objs.retain(|o| selector.MatchObject(o));
+        return Ok(objs);
+    }
+
     pub fn GetByObjectkey(&self, key: &str) -> Result<DataObject<SpecType>> {
         match self.lock().unwrap().objs.get(key) {
             None => {
@@ -397,6 +1165,82 @@ impl<SpecType: Serialize + for<'a> Deserialize<'a> + Clone + core::fmt::Debug +
         }
     }
 
+    // History returns the replaced versions of an object, oldest first, followed by
+    // the current one. A removed object, or one of a manager without history, has
+    // only the current one. The gateway serves it as a
+    // json array at GET /history/<type>/<tenant>/<namespace>/<name>/.
+    pub fn History(
+        &self,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+    ) -> Result<Vec<DataObject<SpecType>>> {
+        let key = format!("{}/{}/{}", tenant, namespace, name);
+        let inner = self.lock().unwrap();
+        let current = match inner.objs.get(&key) {
+            None => return Err(Error::NotExist(format!("DataObjectMgr::History {}", key))),
+            Some(o) => o.clone(),
+        };
+
+        let mut history = inner.history.get(&key).cloned().unwrap_or_default();
+        history.push(current);
+        return Ok(history);
+    }
+
+    // Watch subscribes to the changes of the objects under tenant/namespace whose
+    // labels match selector. An empty tenant watches all the objects and an empty
+    // namespace watches the whole tenant. The stream starts with an Added event with
+    // inInitialList set for each existing object, followed by an InitDone event.
+    pub fn Watch(&self, tenant: &str, namespace: &str, selector: Selector) -> DeltaEventStream {
+        let prefix = if tenant.len() > 0 {
+            if namespace.len() > 0 {
+                format!("{}/{}/", tenant, namespace)
+            } else {
+                format!("{}/", tenant)
+            }
+        } else {
+            "".to_owned()
+        };
+
+        let (tx, rx) = mpsc::unbounded();
+        let watcher = ObjectWatcher {
+            prefix: prefix,
+            selector: selector,
+            sender: tx,
+        };
+
+        let mut inner = self.lock().unwrap();
+        for (_, o) in inner
+            .objs
+            .range::<String, _>((Included(watcher.prefix.clone()), Unbounded))
+        {
+            if !o.Key().starts_with(&watcher.prefix) {
+                break;
+            }
+
+            if !watcher.selector.Match(&o.labels) {
+                continue;
+            }
+
+            watcher.Send(DeltaEvent {
+                type_: EventType::Added,
+                inInitialList: true,
+                obj: o.DataObject(),
+                oldObj: None,
+            });
+        }
+
+        watcher.Send(DeltaEvent {
+            type_: EventType::InitDone,
+            inInitialList: true,
+            obj: DataObject::default(),
+            oldObj: None,
+        });
+
+        inner.watchers.push(watcher);
+        return rx;
+    }
+
     pub fn Add(&self, obj: DataObject<SpecType>) -> Result<()> {
         let mut inner = self.lock().unwrap();
 
@@ -406,37 +1250,105 @@ impl<SpecType: Serialize + for<'a> Deserialize<'a> + Clone + core::fmt::Debug +
             return Err(Error::Exist(format!("DataObjectMgr::Add {}", &key)));
         };
 
+        inner.Notify(EventType::Added, &obj, None);
         inner.objs.insert(key, obj);
 
         return Ok(());
     }
 
+    // Update replaces the stored object and moves it to the next revision. The revision
+    // of obj is the one of the copy it is based on: if the stored object is no longer
+    // at it, the update is rejected with Error::Conflict. Objects without revision (0)
+    // are not checked.
     pub fn Update(&self, obj: DataObject<SpecType>) -> Result<()> {
+        let expectRevision = obj.revision;
+        return self.UpdateInner(obj, None, |stored| {
+            expectRevision == 0 || stored.revision == expectRevision
+        });
+    }
+
+    // CompareAndUpdate replaces the stored object with obj, at the revision of obj, only
+    // if the stored revision is still expectRevision, otherwise Error::Conflict is
+    // returned.
+    pub fn CompareAndUpdate(&self, obj: DataObject<SpecType>, expectRevision: i64) -> Result<()> {
+        let revision = obj.revision;
+        return self.UpdateInner(obj, Some(revision), |stored| {
+            stored.revision == expectRevision
+        });
+    }
+
+    // UpdateInner stores obj at revision, or at the next one of the stored object if
+    // revision is None.
+    fn UpdateInner<F: Fn(&DataObject<SpecType>) -> bool>(
+        &self,
+        mut obj: DataObject<SpecType>,
+        revision: Option<i64>,
+        check: F,
+    ) -> Result<()> {
         let mut inner = self.lock().unwrap();
 
         let key = obj.Key();
 
-        if !inner.objs.contains_key(&key) {
-            return Err(Error::NotExist(format!("DataObjectMgr::Update {}", &key)));
+        let oldObj = match inner.objs.get(&key) {
+            None => {
+                return Err(Error::NotExist(format!("DataObjectMgr::Update {}", &key)));
+            }
+            Some(o) => o,
         };
 
+        if !check(oldObj) {
+            return Err(Error::Conflict(format!(
+                "DataObjectMgr::Update {} stored revision {} update revision {}",
+                &key, oldObj.revision, obj.revision
+            )));
+        }
+
+        obj.revision = revision.unwrap_or(oldObj.revision + 1);
+        let oldObj = inner.objs.remove(&key).unwrap();
+        inner.Notify(EventType::Modified, &obj, Some(&oldObj));
+        inner.AddHistory(&key, oldObj, &obj);
         inner.objs.insert(key, obj);
 
         return Ok(());
     }
 
     pub fn Remove(&self, obj: DataObject<SpecType>) -> Result<()> {
+        return self.RemoveInner(obj, None);
+    }
+
+    // CompareAndRemove removes the stored object only if its revision is still
+    // expectRevision, otherwise Error::Conflict is returned.
+    pub fn CompareAndRemove(&self, obj: DataObject<SpecType>, expectRevision: i64) -> Result<()> {
+        return self.RemoveInner(obj, Some(expectRevision));
+    }
+
+    fn RemoveInner(&self, obj: DataObject<SpecType>, expectRevision: Option<i64>) -> Result<()> {
         let key = obj.Key();
         let mut inner = self.lock().unwrap();
-        if !inner.objs.contains_key(&key) {
-            return Err(Error::NotExist(format!(
-                "DataObjectMgr::Remove {}/{:?}",
-                key,
-                inner.objs.keys()
-            )));
+        let storedRevision = match inner.objs.get(&key) {
+            None => {
+                return Err(Error::NotExist(format!(
+                    "DataObjectMgr::Remove {}/{:?}",
+                    key,
+                    inner.objs.keys()
+                )));
+            }
+            Some(o) => o.revision,
+        };
+
+        match expectRevision {
+            Some(rev) if rev != storedRevision => {
+                return Err(Error::Conflict(format!(
+                    "DataObjectMgr::Remove {} stored revision {} expect revision {}",
+                    &key, storedRevision, rev
+                )));
+            }
+            _ => (),
         }
 
-        inner.objs.remove(&key);
+        let obj = inner.objs.remove(&key).unwrap();
+        inner.history.remove(&key);
+        inner.Notify(EventType::Deleted, &obj, None);
 
         return Ok(());
     }
@@ -0,0 +1,387 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// The request and response shapes of the function calls of each ApiType, the body of
+// POST /funccall/{tenant}/{namespace}/{funcname}/{path}.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::common::*;
+use crate::obj_mgr::func_mgr::{ApiType, SampleCall};
+
+// CompletionRequest is the openai /v1/completions request. The fields not known here,
+// e.g. the vllm sampling parameters, are passed through in extra.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CompletionRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    pub prompt: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+    #[serde(default)]
+    pub stream: bool,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChatMessage {
+    pub role: String,
+    pub content: String,
+}
+
+// ChatCompletionRequest is the openai /v1/chat/completions request.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ChatCompletionRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    pub messages: Vec<ChatMessage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+    #[serde(default)]
+    pub stream: bool,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+// LlavaRequest is a completion request with the url of the image to ask about.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LlavaRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    pub prompt: String,
+    pub image: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    #[serde(default)]
+    pub stream: bool,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+// PromptRequest is the request of the standard and text2img functions, a prompt plus
+// whatever the function takes.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PromptRequest {
+    pub prompt: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Usage {
+    #[serde(default)]
+    pub prompt_tokens: u64,
+    #[serde(default)]
+    pub completion_tokens: u64,
+    #[serde(default)]
+    pub total_tokens: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CompletionChoice {
+    #[serde(default)]
+    pub index: u32,
+    #[serde(default)]
+    pub text: String,
+    #[serde(default)]
+    pub finish_reason: Option<String>,
+}
+
+// CompletionResponse is both the whole response and a streamed chunk of a completion.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CompletionResponse {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub object: String,
+    #[serde(default)]
+    pub created: u64,
+    #[serde(default)]
+    pub model: String,
+    pub choices: Vec<CompletionChoice>,
+    #[serde(default)]
+    pub usage: Option<Usage>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ChatChoice {
+    #[serde(default)]
+    pub index: u32,
+    pub message: ChatMessage,
+    #[serde(default)]
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ChatCompletionResponse {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub object: String,
+    #[serde(default)]
+    pub created: u64,
+    #[serde(default)]
+    pub model: String,
+    pub choices: Vec<ChatChoice>,
+    #[serde(default)]
+    pub usage: Option<Usage>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ChatDelta {
+    #[serde(default)]
+    pub role: Option<String>,
+    #[serde(default)]
+    pub content: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ChatChunkChoice {
+    #[serde(default)]
+    pub index: u32,
+    pub delta: ChatDelta,
+    #[serde(default)]
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ChatCompletionChunk {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub model: String,
+    pub choices: Vec<ChatChunkChoice>,
+}
+
+#[derive(Debug, Clone)]
+pub enum FuncRequest {
+    Completion(CompletionRequest),
+    ChatCompletion(ChatCompletionRequest),
+    Llava(LlavaRequest),
+    Standard(PromptRequest),
+    Text2Image(PromptRequest),
+}
+
+#[derive(Debug, Clone)]
+pub enum FuncResponse {
+    Completion(CompletionResponse),
+    ChatCompletion(ChatCompletionResponse),
+    // the standard functions answer any json
+    Json(Value),
+    Image(Vec<u8>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamChunk {
+    Text(String),
+    // the [DONE] event closing an openai stream
+    Done,
+}
+
+impl FuncRequest {
+    // New types the json body of a call. An openai call to a chat/completions path is a
+    // chat completion, its prompt becomes the user message.
+    pub fn New(apiType: &ApiType, path: &str, body: Map<String, Value>) -> Result<Self> {
+        let mut body = body;
+        let req = match apiType {
+            ApiType::OpenAI if path.trim_end_matches('/').ends_with("chat/completions") => {
+                if !body.contains_key("messages") {
+                    let prompt = body.remove("prompt").unwrap_or_default();
+                    body.insert(
+                        "messages".to_owned(),
+                        serde_json::json!([{"role": "user", "content": prompt}]),
+                    );
+                }
+                body.remove("prompt");
+                Self::ChatCompletion(Self::Parse(apiType, body)?)
+            }
+            ApiType::OpenAI => Self::Completion(Self::Parse(apiType, body)?),
+            ApiType::Llava => Self::Llava(Self::Parse(apiType, body)?),
+            ApiType::Standard => Self::Standard(Self::Parse(apiType, body)?),
+            ApiType::Text2Image => Self::Text2Image(Self::Parse(apiType, body)?),
+        };
+        return Ok(req);
+    }
+
+    pub fn FromSampleCall(sample: &SampleCall) -> Result<Self> {
+        return Self::New(&sample.apiType, &sample.path, sample.RequestBody());
+    }
+
+    fn Parse<T: for<'de> Deserialize<'de>>(apiType: &ApiType, body: Map<String, Value>) -> Result<T> {
+        match serde_json::from_value(Value::Object(body)) {
+            Err(e) => {
+                return Err(Error::CommonError(format!(
+                    "invalid {:?} request: {}",
+                    apiType, e
+                )))
+            }
+            Ok(r) => return Ok(r),
+        }
+    }
+
+    pub fn IsStream(&self) -> bool {
+        match self {
+            Self::Completion(r) => return r.stream,
+            Self::ChatCompletion(r) => return r.stream,
+            Self::Llava(r) => return r.stream,
+            Self::Standard(_) | Self::Text2Image(_) => return false,
+        }
+    }
+
+    pub fn ToValue(&self) -> Result<Value> {
+        let v = match self {
+            Self::Completion(r) => serde_json::to_value(r)?,
+            Self::ChatCompletion(r) => serde_json::to_value(r)?,
+            Self::Llava(r) => serde_json::to_value(r)?,
+            Self::Standard(r) | Self::Text2Image(r) => serde_json::to_value(r)?,
+        };
+        return Ok(v);
+    }
+
+    // DecodeResponse decodes the whole body of a non streaming response.
+    pub fn DecodeResponse(&self, body: &[u8]) -> Result<FuncResponse> {
+        let resp = match self {
+            Self::Completion(_) | Self::Llava(_) => {
+                FuncResponse::Completion(serde_json::from_slice(body)?)
+            }
+            Self::ChatCompletion(_) => FuncResponse::ChatCompletion(serde_json::from_slice(body)?),
+            Self::Standard(_) => FuncResponse::Json(serde_json::from_slice(body)?),
+            Self::Text2Image(_) => FuncResponse::Image(body.to_vec()),
+        };
+        return Ok(resp);
+    }
+
+    // DecodeChunk decodes the data of one server-sent event of a streaming response.
+    pub fn DecodeChunk(&self, data: &str) -> Result<StreamChunk> {
+        if data.trim() == "[DONE]" {
+            return Ok(StreamChunk::Done);
+        }
+
+        let text = match self {
+            Self::Completion(_) | Self::Llava(_) => {
+                let chunk: CompletionResponse = Self::ParseChunk(data)?;
+                chunk.choices.into_iter().map(|c| c.text).collect()
+            }
+            Self::ChatCompletion(_) => {
+                let chunk: ChatCompletionChunk = Self::ParseChunk(data)?;
+                chunk
+                    .choices
+                    .into_iter()
+                    .filter_map(|c| c.delta.content)
+                    .collect()
+            }
+            Self::Standard(_) | Self::Text2Image(_) => data.to_owned(),
+        };
+        return Ok(StreamChunk::Text(text));
+    }
+
+    fn ParseChunk<T: for<'de> Deserialize<'de>>(data: &str) -> Result<T> {
+        match serde_json::from_str(data) {
+            Err(e) => {
+                return Err(Error::CommonError(format!(
+                    "invalid stream chunk {:?}: {}",
+                    data, e
+                )))
+            }
+            Ok(c) => return Ok(c),
+        }
+    }
+}
+
+impl FuncResponse {
+    // Text returns the generated text, None for the json and image responses.
+    pub fn Text(&self) -> Option<String> {
+        match self {
+            Self::Completion(r) => return Some(r.choices.iter().map(|c| c.text.as_str()).collect()),
+            Self::ChatCompletion(r) => {
+                return Some(
+                    r.choices
+                        .iter()
+                        .map(|c| c.message.content.as_str())
+                        .collect(),
+                )
+            }
+            Self::Json(_) | Self::Image(_) => return None,
+        }
+    }
+}
+
+// SseDecoder splits a server-sent events response into the data of its events.
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    buf: Vec<u8>,
+    data: Vec<String>,
+}
+
+impl SseDecoder {
+    pub fn New() -> Self {
+        return Self::default();
+    }
+
+    // Feed takes the next chunk of the response and returns the completed events.
+    pub fn Feed(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buf.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        while let Some(pos) = self.buf.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buf.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+            if let Some(event) = self.Line(line) {
+                events.push(event);
+            }
+        }
+        return events;
+    }
+
+    // Finish returns the last event if the response doesn't end with a blank line.
+    pub fn Finish(&mut self) -> Option<String> {
+        let rest = std::mem::take(&mut self.buf);
+        let rest = String::from_utf8_lossy(&rest).to_string();
+        if let Some(event) = self.Line(&rest) {
+            return Some(event);
+        }
+        return self.Line("");
+    }
+
+    fn Line(&mut self, line: &str) -> Option<String> {
+        // a blank line ends the event
+        if line.is_empty() {
+            if self.data.is_empty() {
+                return None;
+            }
+            let event = self.data.join("\n");
+            self.data.clear();
+            return Some(event);
+        }
+
+        // comments and the event/id/retry fields are not used
+        if let Some(data) = line.strip_prefix("data:") {
+            self.data
+                .push(data.strip_prefix(' ').unwrap_or(data).to_owned());
+        }
+        return None;
+    }
+}
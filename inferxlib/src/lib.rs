@@ -10,8 +10,11 @@ extern crate log;
 
 pub mod common;
 pub mod data_obj;
+pub mod func_api;
 pub mod node;
 pub mod obj_mgr;
+pub mod placement;
 pub mod resource;
+pub mod sched_sim;
 pub mod selector;
 pub mod validation;
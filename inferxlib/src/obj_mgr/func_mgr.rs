@@ -12,12 +12,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::{BTreeMap, HashMap};
+use std::collections::BTreeMap;
 
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
+use crate::common::*;
 use crate::data_obj::*;
 use crate::resource::*;
+use crate::validation::*;
 
 pub const FUNCPOD_TYPE: &str = "funcpod_type.qservice.io";
 pub const FUNCPOD_FUNCNAME: &str = "fun_name.qservice.io";
@@ -56,6 +59,13 @@ pub struct HttpEndpoint {
     pub probe: String,
 }
 
+impl HttpEndpoint {
+    pub fn Validate(&self, path: &str, errs: &mut ErrorList) {
+        errs.Check(&FieldPath(path, "port"), IsValidPortNum(self.port as i32));
+        errs.Check(&FieldPath(path, "probe"), IsHttpPath(&self.probe));
+    }
+}
+
 impl Default for HttpEndpoint {
     fn default() -> Self {
         return Self {
@@ -76,16 +86,37 @@ pub struct SampleCall {
     // tempature
     // stream
     // image
-    pub body: BTreeMap<String, String>,
+    #[serde(deserialize_with = "DeserializeBody")]
+    pub body: BTreeMap<String, Value>,
+}
+
+// DeserializeBody reads the body values as json. The old body form stores every value
+// as a string, e.g. "max_tokens": "1000", those of the typed fields are migrated by
+// BodyValue.
+fn DeserializeBody<'de, D>(deserializer: D) -> core::result::Result<BTreeMap<String, Value>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let map = BTreeMap::<String, Value>::deserialize(deserializer)?;
+    return Ok(map
+        .into_iter()
+        .map(|(k, v)| match v {
+            Value::String(s) => {
+                let v = BodyValue(&k, &s);
+                (k, v)
+            }
+            v => (k, v),
+        })
+        .collect());
 }
 
 impl Default for SampleCall {
     fn default() -> Self {
         let mut map = BTreeMap::new();
-        map.insert("name".to_owned(), "Unknown".to_owned());
-        map.insert("max_tokens".to_owned(), "1000".to_owned());
-        map.insert("temperature".to_owned(), "0".to_owned());
-        map.insert("stream".to_owned(), "true".to_owned());
+        map.insert("name".to_owned(), Value::from("Unknown"));
+        map.insert("max_tokens".to_owned(), Value::from(1000));
+        map.insert("temperature".to_owned(), Value::from(0));
+        map.insert("stream".to_owned(), Value::from(true));
 
         return Self {
             apiType: ApiType::OpenAI,
@@ -96,6 +127,49 @@ impl Default for SampleCall {
     }
 }
 
+impl SampleCall {
+    // RequestBody is the json body of the sample call, the prompt plus the body fields.
+    pub fn RequestBody(&self) -> serde_json::Map<String, Value> {
+        let mut body = serde_json::Map::new();
+        body.insert("prompt".to_owned(), Value::String(self.prompt.clone()));
+        for (k, v) in &self.body {
+            body.insert(k.clone(), v.clone());
+        }
+        return body;
+    }
+}
+
+// the sample call body fields which are numbers
+pub const NUMBER_BODY_FIELDS: &[&str] = &[
+    "max_tokens",
+    "temperature",
+    "top_p",
+    "top_k",
+    "n",
+    "seed",
+    "presence_penalty",
+    "frequency_penalty",
+];
+
+// the sample call body fields which are booleans
+pub const BOOL_BODY_FIELDS: &[&str] = &["stream"];
+
+// BodyValue converts the value of body field key given as a string. The value of a
+// number or boolean field such as "1000" or "true" becomes the json value, any other
+// value stays a string, so a model named "123" isn't turned into a number.
+pub fn BodyValue(key: &str, s: &str) -> Value {
+    if NUMBER_BODY_FIELDS.contains(&key) {
+        if let Ok(Value::Number(n)) = serde_json::from_str::<Value>(s.trim()) {
+            return Value::Number(n);
+        }
+    } else if BOOL_BODY_FIELDS.contains(&key) {
+        if let Ok(b) = s.trim().parse::<bool>() {
+            return Value::Bool(b);
+        }
+    }
+    return Value::String(s.to_owned());
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum ApiType {
     #[serde(rename = "openai")]
@@ -148,6 +222,16 @@ fn PromptDefault() -> String {
 impl FuncSpec {
     pub const HIBERNATE_CONTAINER_MEM_OVERHEAD: u64 = 500; // 500 * 1024 * 1024; 500 MB
 
+    // the serialized spec fields which the container doesn't see, a change of any other
+    // field makes the existing snapshots stale
+    pub const COSMETIC_FIELDS: &'static [&'static str] = &["sample_query", "probe"];
+    // the serialized spec fields set by the gateway
+    pub const SYSTEM_FIELDS: &'static [&'static str] = &["version"];
+
+    pub fn ForcesResnapshot(field: &str) -> bool {
+        return !Self::COSMETIC_FIELDS.contains(&field) && !Self::SYSTEM_FIELDS.contains(&field);
+    }
+
     pub fn RestoreResource(&self, blobStoreEnable: bool) -> Resources {
         match self.standby.GpuMemKeepalive(blobStoreEnable) {
             StandbyType::Mem => {
@@ -169,14 +253,84 @@ impl FuncSpec {
         return self.resources.clone();
     }
 
-    pub fn ResumeResource(&self, blobStoreEnable: bool) -> Resources {
+    pub fn ResumeResource(&self, blobStoreEnable: bool) -> Result<Resources> {
         let restoreResource = self.RestoreResource(blobStoreEnable);
         let mut req = self.resources.clone();
-        req.Sub(&restoreResource);
-        return req;
+        req.Sub(&restoreResource)?;
+        return Ok(req);
+    }
+
+    pub fn Validate(&self, path: &str, errs: &mut ErrorList) {
+        errs.Check(&FieldPath(path, "image"), IsImageReference(&self.image));
+
+        let envsPath = FieldPath(path, "envs");
+        for (i, (name, _)) in self.envs.iter().enumerate() {
+            errs.Check(
+                &IndexPath(&IndexPath(&envsPath, i), 0),
+                IsEnvVarName(name),
+            );
+        }
+
+        let mountsPath = FieldPath(path, "mounts");
+        for (i, mount) in self.mounts.iter().enumerate() {
+            let mountPath = IndexPath(&mountsPath, i);
+            errs.Check(&FieldPath(&mountPath, "hostpath"), IsAbsPath(&mount.hostpath));
+            errs.Check(
+                &FieldPath(&mountPath, "mountpath"),
+                IsAbsPath(&mount.mountpath),
+            );
+        }
+
+        self.endpoint.Validate(&FieldPath(path, "endpoint"), errs);
+        self.probe.Validate(&FieldPath(path, "probe"), errs);
+
+        let resourcesPath = FieldPath(path, "resources");
+        if self.resources.memory == 0 {
+            errs.Add(&FieldPath(&resourcesPath, "Mem"), "memory must be non zero");
+        }
+
+        if self.resources.gpu.gpuCount > MAX_GPU_COUNT as u64 {
+            errs.Add(
+                &FieldPath(&FieldPath(&resourcesPath, "GPU"), "Count"),
+                &format!(
+                    "must be no more than {}, actual {}",
+                    MAX_GPU_COUNT, self.resources.gpu.gpuCount
+                ),
+            );
+        }
+    }
+}
+
+impl ObjectKind for FuncObject {
+    const KIND: &'static str = Function::KEY;
+    const SCOPE: ObjectScope = ObjectScope::Namespace;
+
+    fn Validate(obj: &DataObject<Value>) -> ErrorList {
+        return ValidateFunction(obj);
+    }
+
+    fn Typed(obj: Function) -> TypedObject {
+        return TypedObject::Function(obj);
     }
 }
 
+// ValidateFunction decodes obj as a Function and checks all of its fields. Every
+// problem found is returned with the JSON path of the field, so ixctl can reject a
+// bad function file before it is sent to the gateway.
+pub fn ValidateFunction(obj: &DataObject<Value>) -> ErrorList {
+    let mut errs = ErrorList::default();
+    ValidateObjectMeta(obj, Function::KEY, &mut errs);
+    ValidateScope(obj, FuncObject::SCOPE, &mut errs);
+
+    let func: FuncObject = match DecodeObject(&obj.object, &mut errs) {
+        None => return errs,
+        Some(f) => f,
+    };
+
+    func.spec.Validate("object.spec", &mut errs);
+    return errs;
+}
+
 fn port_default() -> u16 {
     return 80;
 }
@@ -233,6 +387,16 @@ pub struct FuncObject {
 pub type Function = DataObject<FuncObject>;
 pub type FuncMgr = DataObjectMgr<FuncObject>;
 
+impl FuncMgr {
+    // New returns the function manager, it keeps the history of the spec changes for
+    // ixctl rollout.
+    pub fn New() -> Self {
+        return Self::NewWithHistory(|old, new| {
+            return !ChangedSpecFields(&old.DataObject(), &new.DataObject()).is_empty();
+        });
+    }
+}
+
 impl Function {
     pub const KEY: &'static str = "function";
 
@@ -250,35 +414,82 @@ impl Function {
         return self.object.spec.version;
     }
 
+    // SampleRestCall is the curl command of the sample call. The body is json built by
+    // serde_json and single quoted for the shell.
     pub fn SampleRestCall(&self) -> String {
-        let mut map = HashMap::new();
-
         let sample = &self.object.spec.sampleCall;
-        let remainpath = sample.path.clone();
+        let body = Value::Object(sample.RequestBody());
+        let body = serde_json::to_string_pretty(&body).unwrap_or_default();
 
-        map.insert("prompt".to_owned(), sample.prompt.clone());
-        for (k, v) in &sample.body {
-            map.insert(k.clone(), v.clone());
-        }
+        return format!(
+            "curl http://localhost:4000/funccall/{}/{}/{}/{} -H \"Content-Type: application/json\" -d {}",
+            &self.tenant,
+            &self.namespace,
+            &self.name,
+            sample.path.trim_start_matches('/'),
+            ShellQuote(&body)
+        );
+    }
+}
 
-        let mut jstr = String::new();
-        let mut count = map.len();
-        for (k, v) in map {
-            count -= 1;
-            if count == 0 {
-                jstr += &format!("  \"{}\": \"{}\" \n", k, v);
-            } else {
-                jstr += &format!("  \"{}\": \"{}\", \n", k, v);
-            }
+// SpecVersion is the spec version of a function object, 0 if it has none.
+pub fn SpecVersion(obj: &DataObject<Value>) -> i64 {
+    return obj
+        .object
+        .pointer("/spec/version")
+        .and_then(|v| v.as_i64())
+        .unwrap_or(0);
+}
+
+// Rollback is the current object with the spec of target. The spec version stays the
+// current one, the gateway moves it on as for any update.
+pub fn Rollback(
+    current: &DataObject<Value>,
+    target: &DataObject<Value>,
+) -> Result<DataObject<Value>> {
+    let mut obj = current.clone();
+    let mut spec = match target.object.get("spec") {
+        Some(spec) => spec.clone(),
+        None => {
+            return Err(Error::CommonError(format!(
+                "revision {} of {} has no spec",
+                SpecVersion(target),
+                target.Key()
+            )))
         }
+    };
+    spec["version"] = Value::from(SpecVersion(current));
+    obj.object["spec"] = spec;
+    return Ok(obj);
+}
 
-        let tenant = self.tenant.clone();
-        let namespace = self.namespace.clone();
-        let funcname = self.name.clone();
+// ChangedSpecFields lists the top level spec fields which differ, version excluded.
+pub fn ChangedSpecFields(a: &DataObject<Value>, b: &DataObject<Value>) -> Vec<String> {
+    let empty = serde_json::Map::new();
+    let spec = |o: &DataObject<Value>| -> serde_json::Map<String, Value> {
+        return o
+            .object
+            .get("spec")
+            .and_then(|s| s.as_object())
+            .unwrap_or(&empty)
+            .clone();
+    };
+    let (a, b) = (spec(a), spec(b));
+
+    let mut fields: Vec<String> = a
+        .keys()
+        .chain(b.keys())
+        .filter(|k| *k != "version" && a.get(*k) != b.get(*k))
+        .cloned()
+        .collect();
+    fields.sort();
+    fields.dedup();
+    return fields;
+}
 
-        let script = format!("curl http://localhost:4000/funccall/{tenant}/{namespace}/{funcname}/{remainpath} -H \"Content-Type: application/json\" -d '{{\n{jstr}}}'");
-        return script;
-    }
+// ShellQuote single quotes s for a posix shell, a ' inside becomes '\''
+pub fn ShellQuote(s: &str) -> String {
+    return format!("'{}'", s.replace('\'', "'\\''"));
 }
 
 //pub type Function = Function;
@@ -1,9 +1,11 @@
 use std::collections::BTreeMap;
 
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::common::*;
-use crate::data_obj::{DataObject, DataObjectMgr};
+use crate::validation::*;
+use crate::data_obj::{DataObject, DataObjectMgr, ObjectKind, ObjectScope, TypedObject};
 use crate::resource::Standby;
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -82,6 +84,50 @@ impl Default for SnapshotState {
 
 impl ContainerSnapshot {
     pub const KEY: &'static str = "snapshot";
+
+    pub fn Validate(&self, path: &str, errs: &mut ErrorList) {
+        // funckey is <tenant>/<namespace>/<funcname>/<revision>
+        let parts: Vec<&str> = self.funckey.split("/").collect();
+        if parts.len() != 4 || parts[3].parse::<i64>().is_err() {
+            errs.Add(
+                &FieldPath(path, "funckey"),
+                &format!(
+                    "must be <tenant>/<namespace>/<funcname>/<revision>, actual: {}",
+                    &self.funckey
+                ),
+            );
+        }
+
+        errs.Check(&FieldPath(path, "nodename"), IsQualifiedName(&self.nodename));
+    }
+}
+
+impl ObjectKind for ContainerSnapshot {
+    const KIND: &'static str = ContainerSnapshot::KEY;
+    const SCOPE: ObjectScope = ObjectScope::Namespace;
+
+    fn Validate(obj: &DataObject<Value>) -> ErrorList {
+        return ValidateSnapshot(obj);
+    }
+
+    fn Typed(obj: FuncSnapshot) -> TypedObject {
+        return TypedObject::Snapshot(obj);
+    }
+}
+
+// ValidateSnapshot checks a snapshot object.
+pub fn ValidateSnapshot(obj: &DataObject<Value>) -> ErrorList {
+    let mut errs = ErrorList::default();
+    ValidateObjectMeta(obj, ContainerSnapshot::KEY, &mut errs);
+    ValidateScope(obj, ContainerSnapshot::SCOPE, &mut errs);
+
+    let snapshot: ContainerSnapshot = match DecodeObject(&obj.object, &mut errs) {
+        None => return errs,
+        Some(s) => s,
+    };
+
+    snapshot.Validate("object", &mut errs);
+    return errs;
 }
 
 pub type FuncSnapshot = DataObject<ContainerSnapshot>;
@@ -13,8 +13,10 @@
 // limitations under the License.
 
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::data_obj::*;
+use crate::validation::*;
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct NamespaceObject {
@@ -36,3 +38,27 @@ pub type NamespaceMgr = DataObjectMgr<NamespaceObject>;
 impl Namespace {
     pub const KEY: &'static str = "namespace";
 }
+
+impl ObjectKind for NamespaceObject {
+    const KIND: &'static str = Namespace::KEY;
+    const SCOPE: ObjectScope = ObjectScope::Tenant;
+
+    fn Validate(obj: &DataObject<Value>) -> ErrorList {
+        return ValidateNamespace(obj);
+    }
+
+    fn Typed(obj: Namespace) -> TypedObject {
+        return TypedObject::Namespace(obj);
+    }
+}
+
+// ValidateNamespace checks a namespace object. The namespace object of
+// <tenant>/<name> is stored under namespace "system" of its tenant.
+pub fn ValidateNamespace(obj: &DataObject<Value>) -> ErrorList {
+    let mut errs = ErrorList::default();
+    ValidateObjectMeta(obj, Namespace::KEY, &mut errs);
+    ValidateScope(obj, NamespaceObject::SCOPE, &mut errs);
+
+    DecodeObject::<NamespaceObject>(&obj.object, &mut errs);
+    return errs;
+}
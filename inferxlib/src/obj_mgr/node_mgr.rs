@@ -1,8 +1,10 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::resource::NodeResources;
 
 use crate::data_obj::*;
+use crate::validation::*;
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct NodeSpec {
@@ -27,3 +29,73 @@ impl Node {
         return format!("http://{}:{}", self.object.nodeIp, self.object.podMgrPort);
     }
 }
+
+impl NodeSpec {
+    pub fn Validate(&self, path: &str, errs: &mut ErrorList) {
+        errs.Check(&FieldPath(path, "nodeIp"), IsValidIP(&self.nodeIp));
+        errs.Check(
+            &FieldPath(path, "podMgrPort"),
+            IsValidPortNum(self.podMgrPort as i32),
+        );
+        errs.Check(
+            &FieldPath(path, "tsotSvcPort"),
+            IsValidPortNum(self.tsotSvcPort as i32),
+        );
+        errs.Check(
+            &FieldPath(path, "stateSvcPort"),
+            IsValidPortNum(self.stateSvcPort as i32),
+        );
+        errs.Check(
+            &FieldPath(&FieldPath(path, "resources"), "Topology"),
+            self.resources.topology.Validate(),
+        );
+
+        let cidrPath = FieldPath(path, "cidr");
+        match self.cidr.split_once("/") {
+            None => errs.Add(&cidrPath, "must be a CIDR such as 10.1.0.0/16"),
+            Some((ip, prefix)) => {
+                // the prefix length is bounded by the address family, 32 bits for IPv4
+                let maxLen = match ip.parse::<std::net::IpAddr>() {
+                    Err(_) => {
+                        errs.Check(&cidrPath, IsValidIP(ip));
+                        128
+                    }
+                    Ok(addr) if addr.is_ipv4() => 32,
+                    Ok(_) => 128,
+                };
+                match prefix.parse::<i32>() {
+                    Err(_) => errs.Add(&cidrPath, "prefix length must be a number"),
+                    Ok(len) => errs.Check(&cidrPath, IsInRange(len, 0, maxLen)),
+                }
+            }
+        }
+    }
+}
+
+impl ObjectKind for NodeSpec {
+    const KIND: &'static str = Node::KEY;
+    const SCOPE: ObjectScope = ObjectScope::System;
+
+    fn Validate(obj: &DataObject<Value>) -> ErrorList {
+        return ValidateNode(obj);
+    }
+
+    fn Typed(obj: Node) -> TypedObject {
+        return TypedObject::Node(obj);
+    }
+}
+
+// ValidateNode checks a node_info object, which is stored under the system tenant.
+pub fn ValidateNode(obj: &DataObject<Value>) -> ErrorList {
+    let mut errs = ErrorList::default();
+    ValidateObjectMeta(obj, Node::KEY, &mut errs);
+    ValidateScope(obj, NodeSpec::SCOPE, &mut errs);
+
+    let spec: NodeSpec = match DecodeObject(&obj.object, &mut errs) {
+        None => return errs,
+        Some(s) => s,
+    };
+
+    spec.Validate("object", &mut errs);
+    return errs;
+}
@@ -13,18 +13,22 @@
 // limitations under the License.
 
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::time::SystemTime;
 
 use super::funcsnapshot_mgr::*;
 use crate::common::*;
 use crate::data_obj::DataObject;
 use crate::data_obj::DataObjectMgr;
+use crate::data_obj::{ObjectKind, ObjectScope, TypedObject};
 use crate::node::ContainerDef;
+use crate::placement::{GPUTopology, PlacementPolicy};
 use crate::resource::GPUResourceMap;
 use crate::resource::GPUType;
 use crate::resource::NodeResources;
 use crate::resource::Resources;
 use crate::resource::Standby;
+use crate::validation::*;
 
 use super::func_mgr::FuncSpec;
 use super::func_mgr::HttpEndpoint;
@@ -178,11 +182,38 @@ impl FuncPod {
             gpuType: GPUType::Any(),
             gpus: gpuResources,
             maxContextCnt: 0,
+            placement: PlacementPolicy::default(),
+            topology: GPUTopology::default(),
+            topologyScore: None,
         };
         return self.object.spec.allocResources.Add(&resources);
     }
 }
 
+impl ObjectKind for FuncPodObject {
+    const KIND: &'static str = FuncPod::KEY;
+    const SCOPE: ObjectScope = ObjectScope::Namespace;
+
+    fn Validate(obj: &DataObject<Value>) -> ErrorList {
+        return ValidateFuncPod(obj);
+    }
+
+    fn Typed(obj: FuncPod) -> TypedObject {
+        return TypedObject::Pod(obj);
+    }
+}
+
+// ValidateFuncPod checks a pod object. Pods are created by the scheduler, so only
+// the object identity and the object layout are checked.
+pub fn ValidateFuncPod(obj: &DataObject<Value>) -> ErrorList {
+    let mut errs = ErrorList::default();
+    ValidateObjectMeta(obj, FuncPod::KEY, &mut errs);
+    ValidateScope(obj, FuncPodObject::SCOPE, &mut errs);
+
+    DecodeObject::<FuncPodObject>(&obj.object, &mut errs);
+    return errs;
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 pub enum PodState {
     // init state
@@ -13,8 +13,10 @@
 // limitations under the License.
 
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::data_obj::*;
+use crate::validation::*;
 
 pub const SYSTEM_TENANT: &str = "system";
 pub const SYSTEM_NAMESPACE: &str = "system";
@@ -40,3 +42,28 @@ impl Tenant {
 }
 
 pub type TenantMgr = DataObjectMgr<TenantObject>;
+
+impl ObjectKind for TenantObject {
+    const KIND: &'static str = Tenant::KEY;
+    const SCOPE: ObjectScope = ObjectScope::System;
+
+    fn Validate(obj: &DataObject<Value>) -> ErrorList {
+        return ValidateTenant(obj);
+    }
+
+    fn Typed(obj: Tenant) -> TypedObject {
+        return TypedObject::Tenant(obj);
+    }
+}
+
+// ValidateTenant checks a tenant object. Tenants are stored under the system
+// tenant/namespace and the tenant name is used as a DNS label.
+pub fn ValidateTenant(obj: &DataObject<Value>) -> ErrorList {
+    let mut errs = ErrorList::default();
+    ValidateObjectMeta(obj, Tenant::KEY, &mut errs);
+    ValidateScope(obj, TenantObject::SCOPE, &mut errs);
+    errs.Check("name", IsDNS1123Label(&obj.name));
+
+    DecodeObject::<TenantObject>(&obj.object, &mut errs);
+    return errs;
+}
@@ -0,0 +1,302 @@
+// Copyright (c) 2023 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::*;
+
+// GPUPlacement decides which GPUs of a node a pod gets.
+pub trait GPUPlacement {
+    // Select returns count phyGpuIds out of candidates, each of which has at least the
+    // requested free slots. candidates are (free slotCnt, phyGpuId) sorted by phyGpuId
+    // and there are at least count of them.
+    fn Select(&self, topology: &GPUTopology, candidates: &[(u32, i32)], count: usize) -> Vec<i32>;
+}
+
+// SpreadPlacement takes the emptiest GPUs first, so the pods of a node share as few
+// GPUs as possible.
+pub struct SpreadPlacement;
+
+impl GPUPlacement for SpreadPlacement {
+    fn Select(&self, _topology: &GPUTopology, candidates: &[(u32, i32)], count: usize) -> Vec<i32> {
+        let mut v = candidates.to_vec();
+        v.sort();
+        v.reverse();
+        return v.iter().take(count).map(|(_, id)| *id).collect();
+    }
+}
+
+// BinPackPlacement takes the fullest GPUs which still fit first, so the small pods fill
+// the partially used GPUs and keep whole GPUs free for the multi GPU pods.
+pub struct BinPackPlacement;
+
+impl GPUPlacement for BinPackPlacement {
+    fn Select(&self, _topology: &GPUTopology, candidates: &[(u32, i32)], count: usize) -> Vec<i32> {
+        let mut v = candidates.to_vec();
+        v.sort();
+        return v.iter().take(count).map(|(_, id)| *id).collect();
+    }
+}
+
+// TopologyPlacement takes the GPU set with the best interconnect of the node topology.
+// Without a topology description GPUs with adjacent ids are assumed to be closest, as
+// they usually share a PCIe switch. Among equally close sets the fullest one is taken,
+// as with bin-pack.
+pub struct TopologyPlacement;
+
+impl GPUPlacement for TopologyPlacement {
+    fn Select(&self, topology: &GPUTopology, candidates: &[(u32, i32)], count: usize) -> Vec<i32> {
+        if count > 1 && !topology.IsEmpty() {
+            let mut best: Option<(u32, u32, Vec<i32>)> = None; // (score, free slots, gpus)
+            Combinations(candidates, count, &mut Vec::new(), &mut |set| {
+                let gpus: Vec<i32> = set.iter().map(|(_, id)| *id).collect();
+                let score = topology.Score(&gpus).unwrap_or(0);
+                let free = set.iter().map(|(slotCnt, _)| *slotCnt).sum();
+                let better = match &best {
+                    None => true,
+                    Some((s, f, _)) => score > *s || (score == *s && free < *f),
+                };
+                if better {
+                    best = Some((score, free, gpus));
+                }
+            });
+            return best.map(|(_, _, gpus)| gpus).unwrap_or_default();
+        }
+
+        let mut best: Option<(i32, u32, usize)> = None; // (id span, free slots, start)
+        for start in 0..candidates.len() + 1 - count {
+            let window = &candidates[start..start + count];
+            let span = window[count - 1].1 - window[0].1;
+            let free = window.iter().map(|(slotCnt, _)| *slotCnt).sum();
+            if best.is_none_or(|(s, f, _)| (span, free) < (s, f)) {
+                best = Some((span, free, start));
+            }
+        }
+
+        let start = match best {
+            None => return Vec::new(),
+            Some((_, _, start)) => start,
+        };
+        return candidates[start..start + count]
+            .iter()
+            .map(|(_, id)| *id)
+            .collect();
+    }
+}
+
+// Combinations calls f with every count sized subset of v, in order.
+fn Combinations<T: Clone>(v: &[T], count: usize, set: &mut Vec<T>, f: &mut dyn FnMut(&[T])) {
+    if set.len() == count {
+        f(set);
+        return;
+    }
+    for i in 0..v.len() {
+        if v.len() - i < count - set.len() {
+            break;
+        }
+        set.push(v[i].clone());
+        Combinations(&v[i + 1..], count, set, f);
+        set.pop();
+    }
+}
+
+// PlacementPolicy is the configured GPUPlacement. A node sets the default policy of its
+// GPUs, a function may override it in its GPU resource.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PlacementPolicy {
+    Spread,
+    BinPack,
+    Topology,
+}
+
+impl Default for PlacementPolicy {
+    fn default() -> Self {
+        return Self::Spread;
+    }
+}
+
+impl PlacementPolicy {
+    pub fn Placement(&self) -> &'static dyn GPUPlacement {
+        match self {
+            Self::Spread => return &SpreadPlacement,
+            Self::BinPack => return &BinPackPlacement,
+            Self::Topology => return &TopologyPlacement,
+        }
+    }
+}
+
+// GPULink is the connection between two GPUs, as shown by nvidia-smi topo -m.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub enum GPULink {
+    // X, the GPU itself
+    Self_,
+    // NV#, # bonded NVLinks
+    NVLink(u32),
+    // PIX, at most a single PCIe bridge
+    PIX,
+    // PXB, multiple PCIe bridges without the host bridge
+    PXB,
+    // PHB, a PCIe host bridge
+    PHB,
+    // NODE, the interconnect between the host bridges of a NUMA node
+    NODE,
+    // SYS, the interconnect between NUMA nodes
+    SYS,
+}
+
+impl GPULink {
+    // Score is the higher the faster the link is.
+    pub fn Score(&self) -> u32 {
+        match self {
+            Self::Self_ => return 0,
+            Self::NVLink(n) => return 100 + *n,
+            Self::PIX => return 50,
+            Self::PXB => return 40,
+            Self::PHB => return 30,
+            Self::NODE => return 20,
+            Self::SYS => return 10,
+        }
+    }
+}
+
+impl TryFrom<String> for GPULink {
+    type Error = String;
+
+    fn try_from(s: String) -> core::result::Result<Self, Self::Error> {
+        match s.as_str() {
+            "X" => return Ok(Self::Self_),
+            "PIX" => return Ok(Self::PIX),
+            "PXB" => return Ok(Self::PXB),
+            "PHB" => return Ok(Self::PHB),
+            "NODE" => return Ok(Self::NODE),
+            "SYS" => return Ok(Self::SYS),
+            _ => (),
+        }
+        match s.strip_prefix("NV").map(|n| n.parse::<u32>()) {
+            Some(Ok(n)) if n > 0 => return Ok(Self::NVLink(n)),
+            _ => {
+                return Err(format!(
+                    "invalid GPU link {:?}, expect one of X, NV#, PIX, PXB, PHB, NODE, SYS",
+                    s
+                ))
+            }
+        }
+    }
+}
+
+impl From<GPULink> for String {
+    fn from(link: GPULink) -> Self {
+        match link {
+            GPULink::Self_ => return "X".to_owned(),
+            GPULink::NVLink(n) => return format!("NV{}", n),
+            GPULink::PIX => return "PIX".to_owned(),
+            GPULink::PXB => return "PXB".to_owned(),
+            GPULink::PHB => return "PHB".to_owned(),
+            GPULink::NODE => return "NODE".to_owned(),
+            GPULink::SYS => return "SYS".to_owned(),
+        }
+    }
+}
+
+// GPUTopology describes how the GPUs of a node are connected, both indexed by phyGpuId.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct GPUTopology {
+    // links[a][b] is the link between GPU a and GPU b
+    #[serde(rename = "Links", default)]
+    pub links: Vec<Vec<GPULink>>,
+    // numa[a] is the NUMA node of GPU a
+    #[serde(rename = "Numa", default)]
+    pub numa: Vec<u32>,
+}
+
+impl GPUTopology {
+    pub fn IsEmpty(&self) -> bool {
+        return self.links.is_empty() && self.numa.is_empty();
+    }
+
+    pub fn Load(filename: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(filename)?;
+        let topology: Self = serde_json::from_str(&content)?;
+        topology.Validate()?;
+        return Ok(topology);
+    }
+
+    // Validate checks the link matrix is square and symmetric with X on the diagonal.
+    pub fn Validate(&self) -> Result<()> {
+        let n = self.links.len();
+        // every row is checked before links[b][a] is indexed below
+        for (a, row) in self.links.iter().enumerate() {
+            if row.len() != n {
+                return Err(Error::CommonError(format!(
+                    "GPU topology links row {} has {} entries, expect {}",
+                    a,
+                    row.len(),
+                    n
+                )));
+            }
+        }
+        for (a, row) in self.links.iter().enumerate() {
+            for (b, link) in row.iter().enumerate() {
+                if (a == b) != (*link == GPULink::Self_) || *link != self.links[b][a] {
+                    return Err(Error::CommonError(format!(
+                        "GPU topology link {}-{} is {:?}, {}-{} is {:?}",
+                        a, b, link, b, a, self.links[b][a]
+                    )));
+                }
+            }
+        }
+        if n > 0 && !self.numa.is_empty() && self.numa.len() != n {
+            return Err(Error::CommonError(format!(
+                "GPU topology has {} NUMA nodes for {} GPUs",
+                self.numa.len(),
+                n
+            )));
+        }
+        return Ok(());
+    }
+
+    // Link is the link between two GPUs. Without a link matrix the one implied by the
+    // NUMA nodes is assumed.
+    pub fn Link(&self, a: i32, b: i32) -> Option<GPULink> {
+        if a == b {
+            return Some(GPULink::Self_);
+        }
+        if let Some(link) = self
+            .links
+            .get(a as usize)
+            .and_then(|row| row.get(b as usize))
+        {
+            return Some(*link);
+        }
+        match (self.numa.get(a as usize), self.numa.get(b as usize)) {
+            (Some(x), Some(y)) if x == y => return Some(GPULink::NODE),
+            (Some(_), Some(_)) => return Some(GPULink::SYS),
+            _ => return None,
+        }
+    }
+
+    // Score is the score of the slowest link between the gpus, which bounds the collective
+    // operations of a multi GPU pod. None for a single GPU or an unknown link.
+    pub fn Score(&self, gpus: &[i32]) -> Option<u32> {
+        let mut score: Option<u32> = None;
+        for i in 0..gpus.len() {
+            for j in i + 1..gpus.len() {
+                let link = self.Link(gpus[i], gpus[j])?.Score();
+                score = Some(score.map_or(link, |s| s.min(link)));
+            }
+        }
+        return score;
+    }
+}
@@ -20,12 +20,14 @@ use std::collections::{BTreeMap, BTreeSet};
 // use serde_derive::Serialize;
 
 use crate::common::*;
+use crate::placement::{GPUTopology, PlacementPolicy};
+use crate::selector::{Labels, Selector};
 
 pub const MAX_GPU_COUNT: usize = 8;
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
 
-pub struct GPUType(String);
+pub struct GPUType(pub String);
 
 impl Default for GPUType {
     fn default() -> Self {
@@ -38,12 +40,173 @@ impl GPUType {
         return Self("Any".to_string());
     }
 
-    pub fn CanAlloc(&self, req: &Self) -> bool {
-        if &req.0 == "Any" {
-            return true;
+    pub fn IsAny(&self) -> bool {
+        return &self.0 == "Any";
+    }
+
+    // Family is the model family, the type up to the first '-', e.g. A100 of A100-SXM4-80GB
+    pub fn Family(&self) -> &str {
+        return self.0.split('-').next().unwrap_or(&self.0);
+    }
+}
+
+// GPUInfo describes a GPU card of a node.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Default)]
+pub struct GPUInfo {
+    #[serde(rename = "Type")]
+    pub type_: GPUType,
+    // the family of the type by default
+    #[serde(rename = "Family", default, skip_serializing_if = "String::is_empty")]
+    pub family: String,
+    #[serde(rename = "vRam", default)]
+    pub vRam: u64, // MB vRam of the card
+}
+
+impl GPUInfo {
+    pub fn New(type_: GPUType, vRam: u64) -> Self {
+        return Self {
+            type_: type_,
+            family: String::new(),
+            vRam: vRam,
+        };
+    }
+
+    pub fn Family(&self) -> &str {
+        if self.family.is_empty() {
+            return self.type_.Family();
+        }
+        return &self.family;
+    }
+
+    // Labels are the GPU properties a GPUTypeSelector selects on: type, family and vram.
+    pub fn Labels(&self) -> Labels {
+        let mut map = BTreeMap::new();
+        map.insert("type".to_owned(), self.type_.0.clone());
+        map.insert("family".to_owned(), self.Family().to_owned());
+        map.insert("vram".to_owned(), format!("{}", self.vRam));
+        return Labels::NewFromMap(map);
+    }
+}
+
+// GPUTypeSelector is the GPU type required by a function. It is written as
+//   "Any"
+//   a type or family, "A100", or a list of them, ["A100", "H100"] or "A100,H100"
+//   a label selector on the GPUInfo labels, "family in (A100,H100),vram>=40000"
+// A label selector is kept with the string it was parsed from.
+#[derive(Debug, Clone)]
+pub enum GPUTypeSelector {
+    Any,
+    Types(Vec<GPUType>),
+    Selector(String, Selector),
+}
+
+impl PartialEq for GPUTypeSelector {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Any, Self::Any) => return true,
+            (Self::Types(a), Self::Types(b)) => return a == b,
+            (Self::Selector(a, _), Self::Selector(b, _)) => return a == b,
+            _ => return false,
+        }
+    }
+}
+
+impl Eq for GPUTypeSelector {}
+
+impl Default for GPUTypeSelector {
+    fn default() -> Self {
+        return Self::Any;
+    }
+}
+
+impl GPUTypeSelector {
+    pub fn Parse(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if s.is_empty() || s == "Any" {
+            return Ok(Self::Any);
+        }
+
+        let isSelector = s.contains(['=', '!', '<', '>', '('])
+            || s.split_whitespace().any(|w| w == "in" || w == "notin");
+        if isSelector {
+            let selector = Selector::Parse(s)?;
+            return Ok(Self::Selector(s.to_owned(), selector));
+        }
+
+        return Ok(Self::Types(
+            s.split(',').map(|t| GPUType(t.trim().to_owned())).collect(),
+        ));
+    }
+
+    // Match returns whether gpu is of the selected types. A type in the list matches
+    // the GPU type or family.
+    pub fn Match(&self, gpu: &GPUInfo) -> bool {
+        match self {
+            Self::Any => return true,
+            Self::Types(types) => {
+                return types
+                    .iter()
+                    .any(|t| t.IsAny() || *t == gpu.type_ || t.0 == gpu.Family());
+            }
+            Self::Selector(_, selector) => return selector.Match(&gpu.Labels()),
+        }
+    }
+
+    pub fn String(&self) -> String {
+        match self {
+            Self::Any => return "Any".to_owned(),
+            Self::Types(types) => {
+                return types
+                    .iter()
+                    .map(|t| t.0.clone())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            }
+            Self::Selector(s, _) => return s.clone(),
+        }
+    }
+}
+
+impl Serialize for GPUTypeSelector {
+    fn serialize<S>(&self, serializer: S) -> SResult<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::Types(types) if types.len() > 1 => {
+                let types: Vec<&str> = types.iter().map(|t| t.0.as_str()).collect();
+                return types.serialize(serializer);
+            }
+            _ => return serializer.serialize_str(&self.String()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for GPUTypeSelector {
+    fn deserialize<D>(deserializer: D) -> SResult<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            One(String),
+            List(Vec<String>),
         }
 
-        return self == req;
+        match Repr::deserialize(deserializer)? {
+            Repr::One(s) => {
+                return Self::Parse(&s).map_err(|e| {
+                    serde::de::Error::custom(format!("invalid GPU type {:?}: {:?}", s, e))
+                })
+            }
+            Repr::List(types) => {
+                if types.is_empty() {
+                    return Ok(Self::Any);
+                }
+                return Ok(Self::Types(types.into_iter().map(GPUType).collect()));
+            }
+        }
     }
 }
 
@@ -80,20 +243,62 @@ impl NodeGPUResource {
 #[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct GPUResource {
     #[serde(rename = "Type", default)]
-    pub type_: GPUType,
+    pub type_: GPUTypeSelector,
     #[serde(rename = "Count")]
     pub gpuCount: u64,
     #[serde(rename = "vRam")]
     pub vRam: u64,
+    // overrides the placement policy of the node
+    #[serde(rename = "Placement", default, skip_serializing_if = "Option::is_none")]
+    pub placement: Option<PlacementPolicy>,
 }
 
 impl GPUResource {
-    pub fn Sub(&mut self, other: &GPUResource) {
+    pub fn Sub(&mut self, other: &GPUResource) -> Result<()> {
         if other.gpuCount == 0 || other.vRam == 0 {
-            return;
+            return Ok(());
+        }
+        if self.gpuCount != other.gpuCount {
+            return Err(Error::CommonError(format!(
+                "can't subtract the vRam of {} GPUs from {} GPUs",
+                other.gpuCount, self.gpuCount
+            )));
+        }
+        self.vRam = CheckedSub("vRam", self.vRam, other.vRam)?;
+        return Ok(());
+    }
+}
+
+// CheckedSub returns have - want, or ResourceUnderflow if there is not enough resource.
+pub fn CheckedSub<T: Into<u64> + TryFrom<u64> + Copy>(
+    resource: &str,
+    have: T,
+    want: T,
+) -> Result<T> {
+    let (h, w) = (have.into(), want.into());
+    match h.checked_sub(w).and_then(|v| T::try_from(v).ok()) {
+        Some(v) => return Ok(v),
+        None => {
+            return Err(Error::ResourceUnderflow {
+                resource: resource.to_owned(),
+                have: h,
+                want: w,
+            })
+        }
+    }
+}
+
+// CheckedAdd returns a + b, or an error if the sum overflows T.
+pub fn CheckedAdd<T: Into<u64> + TryFrom<u64> + Copy>(resource: &str, a: T, b: T) -> Result<T> {
+    let (a, b) = (a.into(), b.into());
+    match a.checked_add(b).and_then(|v| T::try_from(v).ok()) {
+        Some(v) => return Ok(v),
+        None => {
+            return Err(Error::CommonError(format!(
+                "{} overflows adding {} to {}",
+                resource, b, a
+            )))
         }
-        assert!(self.gpuCount == other.gpuCount);
-        self.vRam -= other.vRam;
     }
 }
 
@@ -164,143 +369,217 @@ impl GPUResourceMap {
         return gpus;
     }
 
-    pub fn Add(&mut self, alloc: &Self) {
+    // Add returns the GPUs of alloc. Nothing is changed on error.
+    pub fn Add(&mut self, alloc: &Self) -> Result<()> {
+        self.CheckSlotSize(alloc)?;
+
+        let mut sums = Vec::new();
+        for (pGpuId, resource) in &alloc.map {
+            let sum = match self.map.get(pGpuId) {
+                None => resource.clone(),
+                Some(cnt) => GPUAlloc {
+                    slotCnt: CheckedAdd(
+                        &format!("GPU {} slots", pGpuId),
+                        cnt.slotCnt,
+                        resource.slotCnt,
+                    )?,
+                    contextCnt: CheckedAdd(
+                        &format!("GPU {} contexts", pGpuId),
+                        cnt.contextCnt,
+                        resource.contextCnt,
+                    )?,
+                },
+            };
+            sums.push((*pGpuId, sum));
+        }
+
         if self.slotSize == 0 {
             self.slotSize = alloc.slotSize;
         }
-        // assert!(
-        //     self.slotSize == alloc.slotSize,
-        //     "self.slotSize is {} alloc {}",
-        //     self.slotSize,
-        //     alloc.slotSize
-        // );
-        for (pGpuId, resource) in &alloc.map {
-            match self.map.get_mut(pGpuId) {
-                None => {
-                    self.map.insert(*pGpuId, resource.clone());
-                }
-                Some(alloc) => {
-                    alloc.slotCnt += resource.slotCnt;
-                    alloc.contextCnt += resource.contextCnt;
-                }
-            }
+        self.map.extend(sums);
+        for (pGpuId, info) in &alloc.types {
+            self.types.entry(*pGpuId).or_insert_with(|| info.clone());
         }
+        return Ok(());
     }
 
-    pub fn Sub(&mut self, alloc: &Self) {
-        assert!(
-            alloc.slotSize == 0 || self.slotSize == alloc.slotSize,
-            "self.slotSize is {} alloc.slotSize is {}",
-            self.slotSize,
-            alloc.slotSize
-        );
+    // Sub takes the GPUs of alloc. Nothing is changed on error.
+    pub fn Sub(&mut self, alloc: &Self) -> Result<()> {
+        if alloc.map.is_empty() {
+            return Ok(());
+        }
+        self.CheckSlotSize(alloc)?;
 
+        let mut diffs = Vec::new();
         for (pGpuId, resource) in &alloc.map {
-            match self.map.get_mut(pGpuId) {
-                None => unreachable!(),
-                Some(cnt) => {
-                    cnt.slotCnt -= resource.slotCnt;
-                    cnt.contextCnt -= resource.contextCnt;
+            let cnt = match self.map.get(pGpuId) {
+                None => {
+                    return Err(Error::CommonError(format!(
+                        "can't subtract GPU {}, which is not in {:?}",
+                        pGpuId,
+                        self.Gpus()
+                    )))
                 }
-            }
+                Some(cnt) => cnt,
+            };
+            let diff = GPUAlloc {
+                slotCnt: CheckedSub(
+                    &format!("GPU {} slots", pGpuId),
+                    cnt.slotCnt,
+                    resource.slotCnt,
+                )?,
+                contextCnt: CheckedSub(
+                    &format!("GPU {} contexts", pGpuId),
+                    cnt.contextCnt,
+                    resource.contextCnt,
+                )?,
+            };
+            diffs.push((*pGpuId, diff));
         }
-    }
 
-    pub fn ReqSlotCnt(&self, vRam: u64) -> u32 {
-        let vRam = vRam * 1024 * 1024; // MB to Bytes
-        let slotCnt = (vRam + self.slotSize - 1) / self.slotSize;
-        return slotCnt as u32;
+        self.map.extend(diffs);
+        return Ok(());
     }
 
-    pub fn Alloc(&mut self, usage: &GPUResource) -> Result<Self> {
-        if !self.CanAlloc(usage) {
-            return Err(Error::SchedulerNoEnoughResource(format!("")));
+    fn CheckSlotSize(&self, alloc: &Self) -> Result<()> {
+        if self.slotSize != 0 && alloc.slotSize != 0 && self.slotSize != alloc.slotSize {
+            return Err(Error::CommonError(format!(
+                "GPU slot size {} doesn't match {}",
+                alloc.slotSize, self.slotSize
+            )));
         }
+        return Ok(());
+    }
 
-        let mut v = self.GenArr();
-        v.sort();
-        v.reverse();
+    // ReqSlotCnt is the number of slots for vRam MB on each GPU. It fails for a map
+    // without slot size and for a vRam no GPU has.
+    pub fn ReqSlotCnt(&self, vRam: u64) -> Result<u32> {
+        if self.slotSize == 0 {
+            return Err(Error::CommonError(
+                "GPU slot size is 0, can't allocate vRam".to_owned(),
+            ));
+        }
+        let tooLarge = || Error::CommonError(format!("requested vRam {} MB is too large", vRam));
+        let bytes = vRam.checked_mul(1024 * 1024).ok_or_else(tooLarge)?; // MB to Bytes
+        let slotCnt = bytes.div_ceil(self.slotSize);
+        return u32::try_from(slotCnt).map_err(|_| tooLarge());
+    }
 
-        let mut map = BTreeMap::new();
+    pub fn Alloc(&mut self, usage: &GPUResource) -> Result<Self> {
+        return self.AllocWith(
+            usage,
+            usage.placement.unwrap_or_default(),
+            &GPUTopology::default(),
+        );
+    }
 
-        let mut count = usage.gpuCount;
-        if count == 0 {
-            return Ok(Self {
-                totalSlotCnt: self.totalSlotCnt,
-                map: map,
-                slotSize: self.slotSize,
-            });
-        }
-
-        let slotCnt = self.ReqSlotCnt(usage.vRam);
-
-        for i in 0..v.len() {
-            if v[i].0 >= slotCnt {
-                match self.map.get_mut(&v[i].1) {
-                    None => unreachable!(),
-                    Some(resource) => {
-                        resource.contextCnt -= 1;
-                        resource.slotCnt -= slotCnt;
-                    }
-                }
+    // AllocWith allocates the GPUs chosen by policy, usage.placement is ignored.
+    pub fn AllocWith(
+        &mut self,
+        usage: &GPUResource,
+        policy: PlacementPolicy,
+        topology: &GPUTopology,
+    ) -> Result<Self> {
+        let gpus = self.Choose(usage, policy, topology)?;
 
-                map.insert(
-                    v[i].1,
-                    GPUAlloc {
-                        contextCnt: 1,
-                        slotCnt: slotCnt,
-                    },
-                );
-                count -= 1;
-
-                if count == 0 {
-                    break;
-                }
+        let mut map = BTreeMap::new();
+        let mut types = BTreeMap::new();
+        let slotCnt = match gpus.len() {
+            0 => 0,
+            _ => self.ReqSlotCnt(usage.vRam)?,
+        };
+        for gpuId in gpus {
+            if let Some(info) = self.types.get(&gpuId) {
+                types.insert(gpuId, info.clone());
             }
+            map.insert(
+                gpuId,
+                GPUAlloc {
+                    contextCnt: 1,
+                    slotCnt: slotCnt,
+                },
+            );
         }
 
-        return Ok(Self {
+        let alloc = Self {
             totalSlotCnt: self.totalSlotCnt,
             map: map,
             slotSize: self.slotSize,
-        });
+            types: types,
+        };
+        self.Sub(&alloc)?;
+        return Ok(alloc);
     }
 
+    // Choose returns the GPUs policy picks for usage without allocating them. It fails
+    // with SchedulerNoEnoughResource if there are not enough free GPUs.
+    pub fn Choose(
+        &self,
+        usage: &GPUResource,
+        policy: PlacementPolicy,
+        topology: &GPUTopology,
+    ) -> Result<Vec<i32>> {
+        let count = usage.gpuCount as usize;
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+
+        let candidates = self.Candidates(usage)?;
+        if candidates.len() < count {
+            return Err(Error::SchedulerNoEnoughResource(format!(
+                "{} GPUs with {} MB vRam free, require {}",
+                candidates.len(),
+                usage.vRam,
+                count
+            )));
+        }
+
+        let gpus = policy.Placement().Select(topology, &candidates, count);
+        if gpus.len() != count {
+            return Err(Error::CommonError(format!(
+                "{:?} placement picks {} of {} GPUs",
+                policy,
+                gpus.len(),
+                count
+            )));
+        }
+        return Ok(gpus);
+    }
+
+    // CanAlloc is false for a usage which can't be allocated at all, e.g. of a vRam no
+    // GPU has.
     pub fn CanAlloc(&self, usage: &GPUResource) -> bool {
-        let mut cnt = usage.gpuCount;
+        let cnt = usage.gpuCount;
         if cnt == 0 {
             return true;
         }
 
-        let reqSlotCnt = self.ReqSlotCnt(usage.vRam);
-        for (_pGpuId, resource) in &self.map {
-            if resource.contextCnt == 0 {
-                continue;
-            }
-            if resource.slotCnt >= reqSlotCnt {
-                cnt -= 1;
-                if cnt == 0 {
-                    return true;
-                }
-            }
+        match self.Candidates(usage) {
+            Err(_) => return false,
+            Ok(candidates) => return candidates.len() as u64 >= cnt,
         }
-
-        // error!(
-        //     "can't alloc GPU reqSlotCnt {} map {:?}",
-        //     reqSlotCnt, &self.map
-        // );
-
-        return false;
     }
 
-    // 0: SlotCnt 1: phyGpuId
-    fn GenArr(&self) -> Vec<(u32, i32)> {
-        let mut v = Vec::with_capacity(self.map.len());
+    // Candidates are the GPUs of the usage type with a free context and the usage vRam
+    // free. GPUs without a GPUInfo are of an unknown type and only match Any.
+    // 0: SlotCnt 1: phyGpuId, sorted by phyGpuId
+    pub fn Candidates(&self, usage: &GPUResource) -> Result<Vec<(u32, i32)>> {
+        let slotCnt = self.ReqSlotCnt(usage.vRam)?;
+        let mut v = Vec::new();
         for (phyGpuId, resource) in &self.map {
-            v.push((resource.slotCnt, *phyGpuId));
+            if resource.contextCnt == 0 || resource.slotCnt < slotCnt {
+                continue;
+            }
+            let typeMatch = match self.types.get(phyGpuId) {
+                None => self.types.is_empty() || usage.type_ == GPUTypeSelector::Any,
+                Some(info) => usage.type_.Match(info),
+            };
+            if typeMatch {
+                v.push((resource.slotCnt, *phyGpuId));
+            }
         }
 
-        return v;
+        return Ok(v);
     }
 }
 
@@ -323,6 +602,22 @@ pub struct NodeResources {
     pub gpus: GPUResourceMap,
     #[serde(rename = "MaxContextPerGPU", default)]
     pub maxContextCnt: u64,
+    // GPU placement policy of the functions which don't set one, see Placement
+    #[serde(rename = "Placement", default)]
+    pub placement: PlacementPolicy,
+    #[serde(
+        rename = "Topology",
+        default,
+        skip_serializing_if = "GPUTopology::IsEmpty"
+    )]
+    pub topology: GPUTopology,
+    // of an allocation, the topology score of the allocated GPUs
+    #[serde(
+        rename = "TopologyScore",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub topologyScore: Option<u32>,
 }
 
 impl NodeResources {
@@ -341,6 +636,9 @@ impl NodeResources {
             gpuType: gpuType.clone(),
             gpus: gpus,
             maxContextCnt: maxContextPerGpu,
+            placement: PlacementPolicy::default(),
+            topology: GPUTopology::default(),
+            topologyScore: None,
         };
     }
 
@@ -352,6 +650,9 @@ impl NodeResources {
             gpuType: self.gpuType.clone(),
             gpus: self.gpus.clone(),
             maxContextCnt: self.maxContextCnt,
+            placement: self.placement,
+            topology: self.topology.clone(),
+            topologyScore: self.topologyScore,
         };
     }
 
@@ -363,13 +664,16 @@ impl NodeResources {
             gpuType: self.gpuType.clone(),
             gpus: self.gpus.clone(),
             maxContextCnt: self.maxContextCnt,
+            placement: self.placement,
+            topology: GPUTopology::default(),
+            topologyScore: None,
         };
     }
 
     pub fn CanAlloc(&self, req: &Resources) -> bool {
         let canAlloc = self.cpu >= req.cpu
             && self.memory >= req.memory
-            && self.gpuType.CanAlloc(&req.gpu.type_)
+            && self.GPUTypeMatch(&req.gpu.type_)
             && self.gpus.CanAlloc(&req.gpu);
 
         // if !canAlloc {
@@ -384,11 +688,46 @@ impl NodeResources {
         return canAlloc;
     }
 
+    // GPUTypeMatch checks the node GPUType for the nodes without per GPU types, the
+    // GPUResourceMap checks each GPU otherwise. Such GPUs share the node vRam evenly.
+    pub fn GPUTypeMatch(&self, selector: &GPUTypeSelector) -> bool {
+        if !self.gpus.types.is_empty() {
+            return true;
+        }
+        return selector.Match(&GPUInfo::New(self.gpuType.clone(), self.gpus.GPUVRam()));
+    }
+
+    // Placement is the placement policy of req: the one of the function if it sets one.
+    // Otherwise a multi GPU pod on a node with a topology is placed by the topology,
+    // whatever the node policy, and the other pods by the node policy.
+    pub fn Placement(&self, req: &Resources) -> PlacementPolicy {
+        match req.gpu.placement {
+            Some(policy) => return policy,
+            None if req.gpu.gpuCount > 1 && !self.topology.IsEmpty() => {
+                return PlacementPolicy::Topology
+            }
+            None => return self.placement,
+        }
+    }
+
+    // TopologyScore is the topology score of the GPUs Alloc would take for req, so the
+    // nodes which can run req can be ranked by interconnect. None if the node can't run
+    // req, takes a single GPU or has no topology.
+    pub fn TopologyScore(&self, req: &Resources) -> Option<u32> {
+        if !self.CanAlloc(req) {
+            return None;
+        }
+        let gpus = self.gpus.Choose(&req.gpu, self.Placement(req), &self.topology).ok()?;
+        return self.topology.Score(&gpus);
+    }
+
+    // Sub takes the resources of other. Nothing is changed on error.
     pub fn Sub(&mut self, other: &Self) -> Result<()> {
         // error!("NodeResources sub \n curr is {:?} \n sub {:?}", self, other);
         // self.cpu -= other.cpu;
-        self.memory -= other.memory;
-        self.gpus.Sub(&other.gpus);
+        let memory = CheckedSub("memory", self.memory, other.memory)?;
+        self.gpus.Sub(&other.gpus)?;
+        self.memory = memory;
 
         return Ok(());
     }
@@ -402,6 +741,9 @@ impl NodeResources {
             gpuType: self.gpuType.clone(),
             gpus: GPUResourceMap::default(),
             maxContextCnt: self.maxContextCnt,
+            placement: self.placement,
+            topology: GPUTopology::default(),
+            topologyScore: None,
         };
     }
 
@@ -415,8 +757,12 @@ impl NodeResources {
 
         // we don't allc/free cpu resource, assume there are enough cpu resource
         // self.cpu -= req.cpu;
-        self.memory -= req.memory;
-        let gpus = self.gpus.Alloc(&req.gpu)?;
+        let memory = CheckedSub("memory", self.memory, req.memory)?;
+        let gpus = self
+            .gpus
+            .AllocWith(&req.gpu, self.Placement(req), &self.topology)?;
+        self.memory = memory;
+        let topologyScore = self.topology.Score(&gpus.Gpus());
 
         return Ok(NodeResources {
             nodename: self.nodename.clone(),
@@ -425,14 +771,25 @@ impl NodeResources {
             gpuType: self.gpuType.clone(),
             gpus: gpus,
             maxContextCnt: self.maxContextCnt,
+            placement: self.placement,
+            topology: GPUTopology::default(),
+            topologyScore: topologyScore,
         });
     }
 
+    // Add returns the resources of free. free may leave the GPUType Any, e.g. when only
+    // GPUs are returned. Nothing is changed on error.
     pub fn Add(&mut self, free: &NodeResources) -> Result<()> {
-        assert!(self.gpuType == free.gpuType);
-        self.gpus.Add(&free.gpus);
+        if self.gpuType != free.gpuType && !free.gpuType.IsAny() {
+            return Err(Error::CommonError(format!(
+                "can't add {} GPUs to {} GPUs",
+                free.gpuType.0, self.gpuType.0
+            )));
+        }
+        let memory = CheckedAdd("memory", self.memory, free.memory)?;
+        self.gpus.Add(&free.gpus)?;
         // self.cpu += free.cpu;
-        self.memory += free.memory;
+        self.memory = memory;
 
         return Ok(());
     }
@@ -440,6 +797,23 @@ impl NodeResources {
     pub fn Gpus(&self) -> GPUResourceMap {
         return self.gpus.clone();
     }
+
+    // GPUTypes summarizes the GPU types of the node, e.g. A100-SXM4-80GBx2,H100x2
+    pub fn GPUTypes(&self) -> String {
+        if self.gpus.types.is_empty() {
+            return self.gpuType.0.clone();
+        }
+
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for info in self.gpus.types.values() {
+            *counts.entry(info.type_.0.as_str()).or_default() += 1;
+        }
+        return counts
+            .iter()
+            .map(|(t, n)| format!("{}x{}", t, n))
+            .collect::<Vec<_>>()
+            .join(",");
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -481,10 +855,14 @@ impl Resources {
         };
     }
 
-    pub fn Sub(&mut self, other: &Self) {
-        self.cpu -= other.cpu;
-        self.memory -= other.memory;
-        self.gpu.Sub(&other.gpu);
+    // Sub takes the resources of other. Nothing is changed on error.
+    pub fn Sub(&mut self, other: &Self) -> Result<()> {
+        let cpu = CheckedSub("cpu", self.cpu, other.cpu)?;
+        let memory = CheckedSub("memory", self.memory, other.memory)?;
+        self.gpu.Sub(&other.gpu)?;
+        self.cpu = cpu;
+        self.memory = memory;
+        return Ok(());
     }
 }
 
@@ -513,6 +891,9 @@ pub struct GPUResourceMap {
     // phyGpuId --> GPUResource
     pub map: BTreeMap<i32, GPUAlloc>,
     pub slotSize: u64,
+    // phyGpuId --> GPUInfo, empty if all GPUs are of the node GPUType
+    #[serde(default)]
+    pub types: BTreeMap<i32, GPUInfo>,
 }
 
 impl Serialize for GPUResourceMap {
@@ -525,11 +906,16 @@ impl Serialize for GPUResourceMap {
             Some((_, alloc)) => alloc.slotCnt as u64 * self.slotSize / 1024 / 1024,
         };
 
-        let mut s = serializer.serialize_struct("GPUResourceMap", 3)?;
+        let mut s = serializer.serialize_struct("GPUResourceMap", 5)?;
         s.serialize_field("vRam", &allocRam)?;
         s.serialize_field("map", &self.map)?;
         s.serialize_field("slotSize", &self.slotSize)?;
         s.serialize_field("totalSlotCnt", &self.totalSlotCnt)?;
+        if self.types.is_empty() {
+            s.skip_field("types")?;
+        } else {
+            s.serialize_field("types", &self.types)?;
+        }
         s.end()
     }
 }
@@ -557,6 +943,16 @@ impl GPUResourceMap {
         return self.totalSlotCnt as u64 * self.slotSize;
     }
 
+    // GPUVRam is the MB vRam of each GPU, assuming all GPUs have the same, for the maps
+    // without per GPU types.
+    pub fn GPUVRam(&self) -> u64 {
+        if self.map.is_empty() {
+            return 0;
+        }
+        let slotCnt = self.totalSlotCnt as u64 / self.map.len() as u64;
+        return slotCnt.saturating_mul(self.slotSize) / (1024 * 1024);
+    }
+
     pub fn SlotCnt(&self, gpuId: i32) -> u32 {
         match self.map.get(&gpuId) {
             None => return 0,
@@ -615,6 +1011,7 @@ impl GPUResourceInfo {
             totalSlotCnt: self.total,
             map: BTreeMap::new(),
             slotSize: self.slotSize,
+            types: BTreeMap::new(),
         };
 
         for i in 0..self.map.len() {
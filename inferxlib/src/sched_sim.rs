@@ -0,0 +1,664 @@
+// Copyright (c) 2023 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// sched_sim replays the pod lifecycle of a set of functions on a set of nodes with the
+// allocation primitives of NodeResources, without a gateway or a node, to tell whether
+// the functions fit and how fragmented the GPUs end up.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::common::*;
+use crate::data_obj::DataObject;
+use crate::obj_mgr::func_mgr::{FuncObject, FuncSpec, Function};
+use crate::obj_mgr::pod_mgr::PodState;
+use crate::placement::{GPUTopology, PlacementPolicy};
+use crate::resource::*;
+
+// the GPU slot size of the simulated nodes, 1 MB so that vRam is allocated exactly
+pub const SIM_SLOT_SIZE: u64 = 1024 * 1024;
+
+fn CountDefault() -> usize {
+    return 1;
+}
+
+// SimNode describes a node, or Count identical nodes named <name>-1 .. <name>-<Count>.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SimNode {
+    pub name: String,
+    #[serde(default = "CountDefault")]
+    pub count: usize,
+    #[serde(rename = "CPU", default)]
+    pub cpu: u64, // 1/1000 CPU cores
+    #[serde(rename = "Mem")]
+    pub memory: u64, // MB memory
+    #[serde(rename = "GPUType", default)]
+    pub gpuType: GPUType,
+    #[serde(rename = "GPUCount", default)]
+    pub gpuCount: usize,
+    #[serde(rename = "vRam", default)]
+    pub vRam: u64, // MB vRam per GPU
+    // per GPU types, instead of GPUCount GPUs of GPUType with vRam
+    #[serde(rename = "GPUs", default)]
+    pub gpus: Vec<GPUInfo>,
+    #[serde(rename = "MaxContextPerGPU", default = "CountDefault")]
+    pub maxContextPerGPU: usize,
+    #[serde(rename = "Placement", default)]
+    pub placement: PlacementPolicy,
+    #[serde(rename = "Topology", default)]
+    pub topology: GPUTopology,
+}
+
+impl SimNode {
+    pub fn Names(&self) -> Vec<String> {
+        if self.count == 1 {
+            return vec![self.name.clone()];
+        }
+        return (1..=self.count)
+            .map(|i| format!("{}-{}", &self.name, i))
+            .collect();
+    }
+
+    pub fn GPUs(&self) -> Vec<GPUInfo> {
+        if !self.gpus.is_empty() {
+            return self.gpus.clone();
+        }
+        return vec![GPUInfo::New(self.gpuType.clone(), self.vRam); self.gpuCount];
+    }
+
+    pub fn NodeResources(&self, nodename: &str) -> NodeResources {
+        let mut gpus = GPUResourceMap {
+            slotSize: SIM_SLOT_SIZE,
+            ..Default::default()
+        };
+        let infos = self.GPUs();
+        for (i, info) in infos.iter().enumerate() {
+            let slotCnt = (info.vRam * 1024 * 1024 / SIM_SLOT_SIZE) as u32;
+            gpus.totalSlotCnt += slotCnt;
+            gpus.map.insert(
+                i as i32,
+                GPUAlloc {
+                    contextCnt: self.maxContextPerGPU as u64,
+                    slotCnt: slotCnt,
+                },
+            );
+            if !self.gpus.is_empty() {
+                gpus.types.insert(i as i32, info.clone());
+            }
+        }
+
+        let mut node = NodeResources::New(
+            nodename,
+            self.cpu,
+            self.memory,
+            if self.gpus.is_empty() {
+                self.gpuType.clone()
+            } else {
+                GPUType::Any()
+            },
+            gpus,
+            self.maxContextPerGPU as u64,
+        );
+        node.placement = self.placement;
+        node.topology = self.topology.clone();
+        return node;
+    }
+}
+
+// SimFunction is a function given by its object file or inline by its resources and
+// standby policy.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SimFunction {
+    #[serde(default)]
+    pub name: String,
+    // function object file, relative to the simulation file
+    #[serde(default)]
+    pub file: String,
+    #[serde(default)]
+    pub resources: Option<Resources>,
+    #[serde(default)]
+    pub standby: Option<Standby>,
+    // the standby pods restored per function by a restore without count
+    #[serde(default = "CountDefault")]
+    pub replicas: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SimAction {
+    // start a pod with the full resources, without a snapshot
+    Create,
+    // run a snapshot pod with the full resources and keep the snapshot on the node
+    Snapshot,
+    // restore a standby pod from a snapshot of the node
+    Restore,
+    // resume a standby pod to serve
+    Resume,
+    // release the GPUs of a resumed pod, it goes back to standby
+    Hibernate,
+}
+
+// SimEvent applies action to the functions, all of them if none is given.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SimEvent {
+    pub action: SimAction,
+    #[serde(default)]
+    pub functions: Vec<String>,
+    // pods per function: 1 by default, the function replicas for restore and all the
+    // resumed pods for hibernate
+    #[serde(default)]
+    pub count: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SimConfig {
+    #[serde(default)]
+    pub blobStoreEnable: bool,
+    pub nodes: Vec<SimNode>,
+    pub functions: Vec<SimFunction>,
+    // snapshot, restore and resume all functions by default
+    #[serde(default)]
+    pub events: Vec<SimEvent>,
+}
+
+impl SimConfig {
+    // Load reads a yaml or json simulation file.
+    pub fn Load(filename: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(filename)?;
+        let config: Self = match serde_yaml::from_str(&content) {
+            Ok(c) => c,
+            Err(e) => {
+                return Err(Error::CommonError(format!(
+                    "invalid simulation file {}: {}",
+                    filename, e
+                )))
+            }
+        };
+        for node in &config.nodes {
+            match node.topology.Validate() {
+                Ok(()) => (),
+                Err(Error::CommonError(e)) => {
+                    return Err(Error::CommonError(format!(
+                        "invalid simulation file {}: node {}: {}",
+                        filename, node.name, e
+                    )))
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        return Ok(config);
+    }
+
+    // Functions resolves the functions to their specs and replicas, the function files are
+    // relative to dir.
+    pub fn Functions(&self, dir: &Path) -> Result<Vec<(String, FuncSpec, usize)>> {
+        let mut funcs: Vec<(String, FuncSpec, usize)> = Vec::new();
+        for f in &self.functions {
+            let (mut name, mut spec) = (f.name.clone(), FuncSpec::default());
+            if !f.file.is_empty() {
+                let path = dir.join(&f.file);
+                let content = std::fs::read_to_string(&path).map_err(|e| {
+                    Error::CommonError(format!("read function file {:?}: {}", path, e))
+                })?;
+                let obj: DataObject<Value> = serde_json::from_str(&content)?;
+                let func: Function = obj.To::<FuncObject>()?;
+                if name.is_empty() {
+                    name = func.name.clone();
+                }
+                spec = func.object.spec;
+            }
+            if let Some(resources) = &f.resources {
+                spec.resources = resources.clone();
+            }
+            if let Some(standby) = &f.standby {
+                spec.standby = *standby;
+            }
+
+            if name.is_empty() {
+                return Err(Error::CommonError(
+                    "simulation function needs a name or a file".to_owned(),
+                ));
+            }
+            if funcs.iter().any(|(n, _, _)| *n == name) {
+                return Err(Error::CommonError(format!(
+                    "simulation function {} is given twice",
+                    name
+                )));
+            }
+            funcs.push((name, spec, f.replicas));
+        }
+        return Ok(funcs);
+    }
+
+    pub fn Events(&self) -> Vec<SimEvent> {
+        if !self.events.is_empty() {
+            return self.events.clone();
+        }
+        return [SimAction::Snapshot, SimAction::Restore, SimAction::Resume]
+            .iter()
+            .map(|a| SimEvent {
+                action: *a,
+                functions: Vec::new(),
+                count: None,
+            })
+            .collect();
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SimPod {
+    pub id: String,
+    pub funcname: String,
+    pub node: String,
+    pub state: PodState,
+    pub gpus: Vec<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topologyScore: Option<u32>,
+
+    #[serde(skip)]
+    restoreAlloc: Option<NodeResources>,
+    // the full resources of a created pod or the resume resources of a restored one
+    #[serde(skip)]
+    runAlloc: Option<NodeResources>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SimFailure {
+    pub step: usize,
+    pub action: SimAction,
+    pub funcname: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct SimNodeReport {
+    pub name: String,
+    pub gpuCount: usize,
+    // GPUs without any pod
+    pub freeGpus: usize,
+    pub freeVRam: u64, // MB
+    // the most free vRam of a single GPU
+    pub maxFreeVRam: u64, // MB
+    // the share of the free vRam on GPUs used by some pod, which only the pods smaller
+    // than the GPU can use
+    pub fragmentation: f64,
+    pub freeMemory: u64, // MB
+    pub snapshots: usize,
+    pub standbyPods: usize,
+    pub readyPods: usize,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct SimReport {
+    pub pods: Vec<SimPod>,
+    // funcname -> the nodes with a snapshot of the function
+    pub snapshots: BTreeMap<String, BTreeSet<String>>,
+    pub nodes: Vec<SimNodeReport>,
+    pub failures: Vec<SimFailure>,
+}
+
+pub struct SchedSim {
+    pub blobStoreEnable: bool,
+    // (funcname, spec, replicas)
+    pub funcs: Vec<(String, FuncSpec, usize)>,
+    // nodename -> (total, available)
+    pub nodes: BTreeMap<String, NodeResourcesStatus>,
+    pub report: SimReport,
+    podCnt: usize,
+}
+
+impl SchedSim {
+    pub fn New(config: &SimConfig, funcs: Vec<(String, FuncSpec, usize)>) -> Result<Self> {
+        let mut nodes = BTreeMap::new();
+        for node in &config.nodes {
+            for name in node.Names() {
+                let resources = node.NodeResources(&name);
+                let status = NodeResourcesStatus {
+                    total: resources.Copy(),
+                    available: resources,
+                };
+                if nodes.insert(name.clone(), status).is_some() {
+                    return Err(Error::CommonError(format!(
+                        "simulation node {} is given twice",
+                        name
+                    )));
+                }
+            }
+        }
+
+        return Ok(Self {
+            blobStoreEnable: config.blobStoreEnable,
+            funcs: funcs,
+            nodes: nodes,
+            report: SimReport::default(),
+            podCnt: 0,
+        });
+    }
+
+    // Run loads the functions relative to dir and replays the config events.
+    pub fn Run(config: &SimConfig, dir: &Path) -> Result<SimReport> {
+        let mut sim = Self::New(config, config.Functions(dir)?)?;
+        for (step, event) in config.Events().iter().enumerate() {
+            sim.Apply(step + 1, event)?;
+        }
+        return Ok(sim.Report());
+    }
+
+    pub fn Apply(&mut self, step: usize, event: &SimEvent) -> Result<()> {
+        let funcnames: Vec<String> = if event.functions.is_empty() {
+            self.funcs.iter().map(|(n, _, _)| n.clone()).collect()
+        } else {
+            event.functions.clone()
+        };
+
+        for funcname in &funcnames {
+            let (spec, replicas) = match self.funcs.iter().find(|(n, _, _)| n == funcname) {
+                None => {
+                    return Err(Error::CommonError(format!(
+                        "simulation step {} refers to the unknown function {}",
+                        step, funcname
+                    )))
+                }
+                Some((_, spec, replicas)) => (spec.clone(), *replicas),
+            };
+
+            let count = match (event.action, event.count) {
+                (_, Some(count)) => count,
+                (SimAction::Restore, None) => replicas,
+                (SimAction::Hibernate, None) => usize::MAX,
+                (_, None) => 1,
+            };
+
+            for _ in 0..count {
+                let res = match event.action {
+                    SimAction::Create => self.Create(funcname, &spec),
+                    SimAction::Snapshot => self.Snapshot(funcname, &spec),
+                    SimAction::Restore => self.Restore(funcname, &spec),
+                    SimAction::Resume => self.Resume(funcname, &spec),
+                    SimAction::Hibernate => match self.Hibernate(funcname) {
+                        // nothing left to hibernate
+                        Err(_) if count == usize::MAX => break,
+                        r => r,
+                    },
+                };
+                if let Err(e) = res {
+                    let reason = match e {
+                        Error::CommonError(s) => s,
+                        Error::SchedulerNoEnoughResource(s) => s,
+                        e => format!("{:?}", e),
+                    };
+                    self.report.failures.push(SimFailure {
+                        step: step,
+                        action: event.action,
+                        funcname: funcname.clone(),
+                        reason: reason,
+                    });
+                    break;
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    pub fn Create(&mut self, funcname: &str, spec: &FuncSpec) -> Result<()> {
+        let req = spec.resources.clone();
+        let nodename = self.PickNode(&req, |_| true, "create")?;
+        let alloc = self.Alloc(&nodename, &req)?;
+        self.AddPod(funcname, &nodename, PodState::Ready, None, Some(alloc));
+        return Ok(());
+    }
+
+    // Snapshot runs the snapshot pod on a node without a snapshot of the function yet and
+    // frees its resources once the snapshot is taken.
+    pub fn Snapshot(&mut self, funcname: &str, spec: &FuncSpec) -> Result<()> {
+        let req = spec.SnapshotResource();
+        let snapshots = self.SnapshotNodes(funcname);
+        let nodename = self.PickNode(&req, |n| !snapshots.contains(n), "snapshot")?;
+        let alloc = self.Alloc(&nodename, &req)?;
+        self.Free(&nodename, &alloc)?;
+        self.report
+            .snapshots
+            .entry(funcname.to_owned())
+            .or_default()
+            .insert(nodename);
+        return Ok(());
+    }
+
+    pub fn Restore(&mut self, funcname: &str, spec: &FuncSpec) -> Result<()> {
+        let snapshots = self.SnapshotNodes(funcname);
+        if snapshots.is_empty() {
+            return Err(Error::CommonError(format!(
+                "no snapshot of {} to restore",
+                funcname
+            )));
+        }
+
+        let req = spec.RestoreResource(self.blobStoreEnable);
+        let nodename = self.PickNode(&req, |n| snapshots.contains(n), "restore")?;
+        let alloc = self.Alloc(&nodename, &req)?;
+        self.AddPod(funcname, &nodename, PodState::Standby, Some(alloc), None);
+        return Ok(());
+    }
+
+    // Resume resumes a standby pod of the function on the best node which has room for
+    // the resume resources.
+    pub fn Resume(&mut self, funcname: &str, spec: &FuncSpec) -> Result<()> {
+        let standby: BTreeSet<String> = self
+            .report
+            .pods
+            .iter()
+            .filter(|p| p.funcname == funcname && p.state == PodState::Standby)
+            .map(|p| p.node.clone())
+            .collect();
+        if standby.is_empty() {
+            return Err(Error::CommonError(format!(
+                "no standby pod of {} to resume",
+                funcname
+            )));
+        }
+
+        let req = spec.ResumeResource(self.blobStoreEnable)?;
+        let nodename = self.PickNode(&req, |n| standby.contains(n), "resume")?;
+        let alloc = self.Alloc(&nodename, &req)?;
+        let pod = self
+            .report
+            .pods
+            .iter_mut()
+            .find(|p| p.funcname == funcname && p.state == PodState::Standby && p.node == nodename)
+            .unwrap();
+        pod.state = PodState::Ready;
+        pod.gpus = alloc.gpus.Gpus();
+        pod.topologyScore = alloc.topologyScore;
+        pod.runAlloc = Some(alloc);
+        return Ok(());
+    }
+
+    // Hibernate frees the GPUs of a resumed pod, which goes back to standby. A created
+    // pod has no snapshot to go back to and is left running.
+    pub fn Hibernate(&mut self, funcname: &str) -> Result<()> {
+        let idx = self.report.pods.iter().position(|p| {
+            p.funcname == funcname && p.state == PodState::Ready && p.restoreAlloc.is_some()
+        });
+        let idx = match idx {
+            None => {
+                return Err(Error::CommonError(format!(
+                    "no resumed pod of {} to hibernate",
+                    funcname
+                )))
+            }
+            Some(idx) => idx,
+        };
+
+        let pod = &mut self.report.pods[idx];
+        let alloc = pod.runAlloc.take().unwrap();
+        pod.state = PodState::Standby;
+        pod.gpus = Vec::new();
+        pod.topologyScore = None;
+        let nodename = pod.node.clone();
+        self.Free(&nodename, &alloc)?;
+        return Ok(());
+    }
+
+    // PickNode returns the node for req among the ones filter accepts: the best GPU
+    // topology score first, then the fullest node, so that whole nodes are kept free.
+    fn PickNode<F: Fn(&String) -> bool>(
+        &self,
+        req: &Resources,
+        filter: F,
+        what: &str,
+    ) -> Result<String> {
+        let mut best: Option<(Option<u32>, u64, &String)> = None;
+        let mut candidates = 0;
+        for (name, status) in &self.nodes {
+            if !filter(name) {
+                continue;
+            }
+            candidates += 1;
+            if !status.IsAvailable(req) {
+                continue;
+            }
+            let score = status.available.TopologyScore(req);
+            let free = FreeVRam(&status.available);
+            let better = match &best {
+                None => true,
+                Some((s, f, _)) => score > *s || (score == *s && free < *f),
+            };
+            if better {
+                best = Some((score, free, name));
+            }
+        }
+
+        match best {
+            Some((_, _, name)) => return Ok(name.clone()),
+            None => {
+                return Err(Error::SchedulerNoEnoughResource(format!(
+                    "no node to {}, {} candidate nodes, require CPU {} Mem {}MB GPU {}x{}MB {}",
+                    what,
+                    candidates,
+                    req.cpu,
+                    req.memory,
+                    req.gpu.gpuCount,
+                    req.gpu.vRam,
+                    req.gpu.type_.String()
+                )))
+            }
+        }
+    }
+
+    fn Alloc(&mut self, nodename: &str, req: &Resources) -> Result<NodeResources> {
+        let status = self.nodes.get_mut(nodename).unwrap();
+        return status.available.Alloc(req);
+    }
+
+    fn Free(&mut self, nodename: &str, alloc: &NodeResources) -> Result<()> {
+        let status = self.nodes.get_mut(nodename).unwrap();
+        return status.available.Add(alloc);
+    }
+
+    fn SnapshotNodes(&self, funcname: &str) -> BTreeSet<String> {
+        return self
+            .report
+            .snapshots
+            .get(funcname)
+            .cloned()
+            .unwrap_or_default();
+    }
+
+    fn AddPod(
+        &mut self,
+        funcname: &str,
+        nodename: &str,
+        state: PodState,
+        restoreAlloc: Option<NodeResources>,
+        runAlloc: Option<NodeResources>,
+    ) {
+        self.podCnt += 1;
+        let (gpus, topologyScore) = match &runAlloc {
+            None => (Vec::new(), None),
+            Some(alloc) => (alloc.gpus.Gpus(), alloc.topologyScore),
+        };
+        self.report.pods.push(SimPod {
+            id: format!("{}_{}", funcname, self.podCnt),
+            funcname: funcname.to_owned(),
+            node: nodename.to_owned(),
+            state: state,
+            gpus: gpus,
+            topologyScore: topologyScore,
+            restoreAlloc: restoreAlloc,
+            runAlloc: runAlloc,
+        });
+    }
+
+    pub fn Report(&self) -> SimReport {
+        let mut report = self.report.clone();
+        report.nodes = self
+            .nodes
+            .iter()
+            .map(|(name, status)| self.NodeReport(name, status))
+            .collect();
+        return report;
+    }
+
+    fn NodeReport(&self, name: &str, status: &NodeResourcesStatus) -> SimNodeReport {
+        let (total, available) = (&status.total.gpus, &status.available.gpus);
+        let mut report = SimNodeReport {
+            name: name.to_owned(),
+            gpuCount: total.map.len(),
+            freeMemory: status.available.memory,
+            ..Default::default()
+        };
+
+        let mut fragmented = 0;
+        for (gpuId, alloc) in &total.map {
+            let free = available.VRam(*gpuId) / 1024 / 1024;
+            report.freeVRam += free;
+            report.maxFreeVRam = report.maxFreeVRam.max(free);
+            if available.SlotCnt(*gpuId) == alloc.slotCnt {
+                report.freeGpus += 1;
+            } else {
+                fragmented += free;
+            }
+        }
+        if report.freeVRam > 0 {
+            report.fragmentation = fragmented as f64 / report.freeVRam as f64;
+        }
+
+        report.snapshots = self
+            .report
+            .snapshots
+            .values()
+            .filter(|nodes| nodes.contains(name))
+            .count();
+        for pod in self.report.pods.iter().filter(|p| p.node == name) {
+            match pod.state {
+                PodState::Ready => report.readyPods += 1,
+                _ => report.standbyPods += 1,
+            }
+        }
+        return report;
+    }
+}
+
+fn FreeVRam(node: &NodeResources) -> u64 {
+    return node
+        .gpus
+        .map
+        .keys()
+        .map(|gpuId| node.gpus.VRam(*gpuId))
+        .sum();
+}
@@ -14,6 +14,7 @@
 
 use core::ops::Deref;
 use lazy_static::lazy_static;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::cmp::Ord;
@@ -28,16 +29,18 @@ use super::validation::*;
 
 #[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum SelectionOp {
-    None,         // ""
-    DoesNotExist, // "!"
-    Equals,       // "="
-    DoubleEquals, // "=="
-    In,           // "in"
-    NotEquals,    // "!="
-    NotIn,        // "notin"
-    Exists,       // "exists"
-    GreaterThan,  // "gt"
-    LessThan,     // "lt"
+    None,                // ""
+    DoesNotExist,        // "!"
+    Equals,              // "="
+    DoubleEquals,        // "=="
+    In,                  // "in"
+    NotEquals,           // "!="
+    NotIn,               // "notin"
+    Exists,              // "exists"
+    GreaterThan,         // "gt"
+    LessThan,            // "lt"
+    GreaterThanOrEquals, // "ge"
+    LessThanOrEquals,    // "le"
 }
 
 impl Default for SelectionOp {
@@ -59,11 +62,13 @@ impl SelectionOp {
             Self::Exists => return "exists",
             Self::GreaterThan => return "gt",
             Self::LessThan => return "lt",
+            Self::GreaterThanOrEquals => return "ge",
+            Self::LessThanOrEquals => return "le",
         }
     }
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Selector(pub Vec<Requirement>);
 
 impl Selector {
@@ -126,18 +131,23 @@ impl Selector {
         }
     }
 
+    // GetAttribute returns the field of val at the dotted path key, None if it is missing.
+    pub fn GetAttribute<'a>(val: &'a serde_json::Value, key: &str) -> Option<&'a Value> {
+        let mut tmp = val;
+        for s in key.split(".") {
+            tmp = match tmp.get(s) {
+                None => return None,
+                Some(v) => &v,
+            };
+        }
+
+        return Some(tmp);
+    }
+
     pub fn GetAttributes(&self, val: &serde_json::Value) -> Option<BTreeMap<String, String>> {
         let mut map = BTreeMap::new();
         for r in &self.0 {
-            let split = r.key.split(".");
-            let mut tmp = val;
-            for s in split {
-                tmp = match tmp.get(s) {
-                    None => return None,
-                    Some(v) => &v,
-                };
-            }
-
+            let tmp = Self::GetAttribute(val, &r.key)?;
             let str = Self::ToString(tmp);
             map.insert(r.key.clone(), str);
         }
@@ -194,7 +204,7 @@ fn GetRequirement(key: &str, op: SelectionOp, vals: Vec<String>) -> Requirement
     return req;
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Requirement {
     pub key: String,
     pub op: SelectionOp,
@@ -264,7 +274,18 @@ impl Requirement {
     //  5. If the operator is Gt or Lt, the values set must contain only one value, which will be interpreted as an integer.
     //  6. The key is invalid due to its length, or sequence of characters. See validateLabelKey for more details.
     pub fn New(key: &str, op: SelectionOp, vals: Vec<String>) -> Result<Requirement> {
-        ValidateLabelKey(key)?;
+        return Self::NewWithKeyValidator(key, op, vals, ValidateLabelKey);
+    }
+
+    // NewWithKeyValidator is New with the key checked by keyValidator instead of
+    // ValidateLabelKey, e.g. for the JSON paths of a FieldSelector.
+    pub fn NewWithKeyValidator(
+        key: &str,
+        op: SelectionOp,
+        vals: Vec<String>,
+        keyValidator: fn(&str) -> Result<()>,
+    ) -> Result<Requirement> {
+        keyValidator(key)?;
         match op {
             SelectionOp::None => panic!("selector::None"),
             SelectionOp::In | SelectionOp::NotIn => {
@@ -288,20 +309,23 @@ impl Requirement {
                     ));
                 }
             }
-            SelectionOp::GreaterThan | SelectionOp::LessThan => {
+            SelectionOp::GreaterThan
+            | SelectionOp::LessThan
+            | SelectionOp::GreaterThanOrEquals
+            | SelectionOp::LessThanOrEquals => {
                 if vals.len() != 1 {
                     return Err(Error::CommonError(
-                        "for 'Gt', 'Lt' operators, exactly one value is required".to_owned(),
+                        "for 'Gt', 'Lt', 'Ge', 'Le' operators, exactly one value is required"
+                            .to_owned(),
                     ));
                 }
 
                 for val in &vals {
                     match val.parse::<u64>() {
-                        Err(_) => {
-                            return Err(Error::CommonError(
-                                "for 'Gt', 'Lt' operators, the value must be an integer".to_owned(),
-                            ))
-                        }
+                        Err(_) => return Err(Error::CommonError(
+                            "for 'Gt', 'Lt', 'Ge', 'Le' operators, the value must be an integer"
+                                .to_owned(),
+                        )),
                         _ => {}
                     }
                 }
@@ -360,7 +384,10 @@ impl Requirement {
             SelectionOp::DoesNotExist => {
                 return !ls.Has(&self.key);
             }
-            SelectionOp::GreaterThan | SelectionOp::LessThan => {
+            SelectionOp::GreaterThan
+            | SelectionOp::LessThan
+            | SelectionOp::GreaterThanOrEquals
+            | SelectionOp::LessThanOrEquals => {
                 if !ls.Has(&self.key) {
                     return false;
                 }
@@ -385,26 +412,20 @@ impl Requirement {
                     return false;
                 }
 
-                for val in &self.strVals {
-                    let rValue: i64 = match val.parse() {
-                        Err(_) => {
-                            // error!("ParseInt failed for value {} in requirement {:?}, for 'Gt', 'Lt' operators, the value must be an integer", val, self);
-                            return false;
-                        }
-                        Ok(v) => v,
-                    };
-
-                    if self.op == SelectionOp::GreaterThan {
-                        return lsValue > rValue;
+                let rValue: i64 = match self.strVals[0].parse() {
+                    Err(_) => {
+                        // error!("ParseInt failed for value {} in requirement {:?}, for 'Gt', 'Lt' operators, the value must be an integer", self.strVals[0], self);
+                        return false;
                     }
+                    Ok(v) => v,
+                };
 
-                    //if self.op == SelectionOp::LessThan {
-                    return lsValue < rValue;
-                    //}
+                match self.op {
+                    SelectionOp::GreaterThan => return lsValue > rValue,
+                    SelectionOp::GreaterThanOrEquals => return lsValue >= rValue,
+                    SelectionOp::LessThanOrEquals => return lsValue <= rValue,
+                    _ => return lsValue < rValue,
                 }
-
-                // won't reach
-                return false;
             }
         }
     }
@@ -444,7 +465,7 @@ impl Requirement {
         }
 
         for i in 0..self.strVals.len() {
-            if self.strVals[i] != self.strVals[i] {
+            if self.strVals[i] != x.strVals[i] {
                 return false;
             }
         }
@@ -475,6 +496,8 @@ impl Requirement {
             SelectionOp::NotIn => output = output + " notin ",
             SelectionOp::GreaterThan => output = output + ">",
             SelectionOp::LessThan => output = output + "<",
+            SelectionOp::GreaterThanOrEquals => output = output + ">=",
+            SelectionOp::LessThanOrEquals => output = output + "<=",
             _ => {}
         }
 
@@ -726,12 +749,16 @@ pub enum Token {
     EqualsToken,
     // GreaterThanToken represents greater than
     GreaterThanToken,
+    // GreaterThanOrEqualsToken represents greater than or equal
+    GreaterThanOrEqualsToken,
     // IdentifierToken represents identifier, e.g. keys and values
     IdentifierToken,
     // InToken represents in
     InToken,
     // LessThanToken represents less than
     LessThanToken,
+    // LessThanOrEqualsToken represents less than or equal
+    LessThanOrEqualsToken,
     // NotEqualsToken represents not equal
     NotEqualsToken,
     // NotInToken represents not in
@@ -756,8 +783,10 @@ lazy_static! {
         ("==".to_owned(),    Token::DoubleEqualsToken),
         ("=".to_owned(),     Token::EqualsToken),
         (">".to_owned(),     Token::GreaterThanToken),
+        (">=".to_owned(),    Token::GreaterThanOrEqualsToken),
         ("in".to_owned(),    Token::InToken),
         ("<".to_owned(),     Token::LessThanToken),
+        ("<=".to_owned(),    Token::LessThanOrEqualsToken),
         ("!=".to_owned(),    Token::NotEqualsToken),
         ("notin".to_owned(), Token::NotInToken),
         ("(".to_owned(),     Token::OpenParToken),
@@ -917,6 +946,8 @@ pub struct Parser {
     pub l: Lexer,
     pub scanItems: Vec<ScannedItem>,
     pub position: usize,
+    // validates the KEY of each requirement, label keys or field paths
+    pub keyValidator: fn(&str) -> Result<()>,
 }
 
 // ParserContext represents context during parsing:
@@ -1019,7 +1050,12 @@ impl Parser {
     pub fn ParseRequirement(&mut self) -> Result<Requirement> {
         let (key, operator) = self.ParseKeyAndInferOperator()?;
         if operator == SelectionOp::Exists || operator == SelectionOp::DoesNotExist {
-            return Ok(Requirement::New(&key, operator, Vec::new())?);
+            return Ok(Requirement::NewWithKeyValidator(
+                &key,
+                operator,
+                Vec::new(),
+                self.keyValidator,
+            )?);
         }
 
         let operator = self.ParseOperator()?;
@@ -1032,16 +1068,19 @@ impl Parser {
             | SelectionOp::DoubleEquals
             | SelectionOp::NotEquals
             | SelectionOp::GreaterThan
-            | SelectionOp::LessThan => {
+            | SelectionOp::LessThan
+            | SelectionOp::GreaterThanOrEquals
+            | SelectionOp::LessThanOrEquals => {
                 values = self.ParseExactValue()?;
             }
             _ => (),
         }
 
-        return Ok(Requirement::New(
+        return Ok(Requirement::NewWithKeyValidator(
             &key,
             operator,
             values.into_iter().collect(),
+            self.keyValidator,
         )?);
     }
 
@@ -1065,7 +1104,7 @@ impl Parser {
             )));
         }
 
-        ValidateLabelKey(&literal)?;
+        (self.keyValidator)(&literal)?;
 
         let (t, _) = self.Lookahead(ParserContext::Values);
         if t == Token::EndOfStringToken || t == Token::CommaToken {
@@ -1088,6 +1127,8 @@ impl Parser {
             Token::DoubleEqualsToken => op = SelectionOp::DoubleEquals,
             Token::GreaterThanToken => op = SelectionOp::GreaterThan,
             Token::LessThanToken => op = SelectionOp::LessThan,
+            Token::GreaterThanOrEqualsToken => op = SelectionOp::GreaterThanOrEquals,
+            Token::LessThanOrEqualsToken => op = SelectionOp::LessThanOrEquals,
             Token::NotInToken => op = SelectionOp::NotIn,
             Token::NotEqualsToken => op = SelectionOp::NotEquals,
             _ => {
@@ -1233,6 +1274,13 @@ impl Parser {
 //     the KEY exists and can be any VALUE.
 //  5. A requirement with just !KEY requires that the KEY not exist.
 pub fn Parse(selector: &str) -> Result<Selector> {
+    return ParseWithKeyValidator(selector, ValidateLabelKey);
+}
+
+pub fn ParseWithKeyValidator(
+    selector: &str,
+    keyValidator: fn(&str) -> Result<()>,
+) -> Result<Selector> {
     let mut p = Parser {
         l: Lexer {
             s: selector.chars().collect(),
@@ -1240,6 +1288,7 @@ pub fn Parse(selector: &str) -> Result<Selector> {
         },
         scanItems: Vec::new(),
         position: 0,
+        keyValidator: keyValidator,
     };
 
     let mut items = p.Parse()?;
@@ -1303,3 +1352,74 @@ pub fn SelectorFromValidatedSet(ls: &Labels) -> Selector {
     rs.Sort();
     return rs;
 }
+
+lazy_static! {
+    pub static ref FIELD_PATH_SEGMENT_REGEXP: Regex = Regex::new("^[A-Za-z0-9_-]+$").unwrap();
+}
+
+// ValidateFieldPath checks the KEY of a field selector requirement, which is a
+// dotted path into the JSON of an object, e.g. "object.spec.resources.GPU.Count".
+pub fn ValidateFieldPath(k: &str) -> Result<()> {
+    if k.len() == 0 {
+        return Err(Error::CommonError("field path must be non empty".to_owned()));
+    }
+
+    for segment in k.split(".") {
+        if !FIELD_PATH_SEGMENT_REGEXP.is_match(segment) {
+            return Err(Error::CommonError(format!(
+                "invalid field path {}: each segment must be non empty and consist of alphanumeric characters, '-' or '_'",
+                k
+            )));
+        }
+    }
+
+    return Ok(());
+}
+
+// FieldSelector selects objects by the value of their fields instead of their
+// labels. It takes the same syntax as the label Selector, with each KEY being a
+// dotted JSON path, e.g. "object.status.state=Ready,object.spec.resources.GPU.Count>1".
+// Scalar fields are compared by their string form; a missing or null field is
+// treated as an absent label.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FieldSelector(pub Selector);
+
+impl FieldSelector {
+    pub fn Parse(selector: &str) -> Result<Self> {
+        return Ok(Self(ParseWithKeyValidator(selector, ValidateFieldPath)?));
+    }
+
+    pub fn String(&self) -> String {
+        return self.0.String();
+    }
+
+    pub fn Empty(&self) -> bool {
+        return self.0.Empty();
+    }
+
+    // Fields returns the values of the selected fields of val as a label set.
+    pub fn Fields(&self, val: &Value) -> Labels {
+        let mut map = BTreeMap::new();
+        for r in &self.0 .0 {
+            match Selector::GetAttribute(val, &r.key) {
+                None | Some(Value::Null) => (),
+                Some(v) => {
+                    map.insert(r.key.clone(), Selector::ToString(v));
+                }
+            }
+        }
+
+        return map.into();
+    }
+
+    pub fn Match(&self, val: &Value) -> bool {
+        return self.0.Match(&self.Fields(val));
+    }
+
+    pub fn MatchObject<T: Serialize>(&self, obj: &T) -> bool {
+        match serde_json::to_value(obj) {
+            Err(_) => return false,
+            Ok(v) => return self.Match(&v),
+        }
+    }
+}
@@ -12,9 +12,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::common::*;
+use core::fmt;
 use lazy_static::lazy_static;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::common::*;
+use crate::data_obj::{DataObject, ObjectScope};
+use crate::obj_mgr::tenant_mgr::{SYSTEM_NAMESPACE, SYSTEM_TENANT};
+use crate::selector::{ValidateLabelKey, ValidateLabelValue};
 
 lazy_static! {
     pub static ref QUALIFIED_NAME_FMT: String =
@@ -510,3 +517,257 @@ pub fn IsConfigMapKey(value: &str) -> Result<()> {
 pub fn InclusiveRangeError(lo: i32, hi: i32) -> Error {
     return Error::CommonError(format!("must be between {} and {}, inclusive", lo, hi));
 }
+
+pub const IMAGE_DOMAIN_COMPONENT_FMT: &str = "(?:[a-zA-Z0-9]|[a-zA-Z0-9][a-zA-Z0-9-]*[a-zA-Z0-9])";
+pub const IMAGE_PATH_COMPONENT_FMT: &str = "[a-z0-9]+(?:(?:[._]|__|[-]*)[a-z0-9]+)*";
+pub const IMAGE_TAG_FMT: &str = r"[\w][\w.-]{0,127}";
+pub const IMAGE_DIGEST_FMT: &str = "[A-Za-z][A-Za-z0-9]*(?:[-_+.][A-Za-z][A-Za-z0-9]*)*:[0-9A-Fa-f]{32,}";
+pub const IMAGE_REFERENCE_ERR_MSG : &str = "a valid image reference must be of the form [domain[:port]/]path[:tag][@digest], with a lower case path";
+// NameTotalLengthMax is the maximum total number of characters in a repository name.
+pub const IMAGE_NAME_MAX_LENGTH: usize = 255;
+
+lazy_static! {
+    pub static ref IMAGE_DOMAIN_FMT: String = IMAGE_DOMAIN_COMPONENT_FMT.to_owned()
+        + "(?:\\."
+        + IMAGE_DOMAIN_COMPONENT_FMT
+        + ")*(?::[0-9]+)?";
+    pub static ref IMAGE_NAME_FMT: String = "(?:".to_owned()
+        + &IMAGE_DOMAIN_FMT
+        + "/)?"
+        + IMAGE_PATH_COMPONENT_FMT
+        + "(?:/"
+        + IMAGE_PATH_COMPONENT_FMT
+        + ")*";
+    pub static ref IMAGE_REFERENCE_REGEXP: Regex = Regex::new(
+        &("^(".to_owned()
+            + &IMAGE_NAME_FMT
+            + ")(?::"
+            + IMAGE_TAG_FMT
+            + ")?(?:@"
+            + IMAGE_DIGEST_FMT
+            + ")?$")
+    )
+    .unwrap();
+}
+
+// IsImageReference tests for a string that conforms to the docker distribution
+// reference grammar, e.g. "vllm/vllm-openai:v0.6.2" or "docker.io/library/ubuntu@sha256:...".
+pub fn IsImageReference(value: &str) -> Result<()> {
+    if value.len() == 0 {
+        return Err(Error::CommonError("image must be non empty".to_owned()));
+    }
+
+    let caps = match IMAGE_REFERENCE_REGEXP.captures(value) {
+        None => return Err(Error::CommonError(IMAGE_REFERENCE_ERR_MSG.to_owned())),
+        Some(caps) => caps,
+    };
+
+    if caps[1].len() > IMAGE_NAME_MAX_LENGTH {
+        return Err(Error::CommonError(format!(
+            "image repository name must be no more than {} characters",
+            IMAGE_NAME_MAX_LENGTH
+        )));
+    }
+
+    return Ok(());
+}
+
+// IsAbsPath tests that the argument is a non empty absolute file path.
+pub fn IsAbsPath(value: &str) -> Result<()> {
+    if value.len() == 0 {
+        return Err(Error::CommonError("path must be non empty".to_owned()));
+    }
+
+    if !value.starts_with("/") {
+        return Err(Error::CommonError(format!(
+            "path must be absolute, actual: {}",
+            value
+        )));
+    }
+
+    return Ok(());
+}
+
+// IsHttpPath tests that the argument is an absolute HTTP path made of the
+// characters allowed by RFC 3986.
+pub fn IsHttpPath(value: &str) -> Result<()> {
+    if !value.starts_with("/") {
+        return Err(Error::CommonError(format!(
+            "http path must start with '/', actual: {}",
+            value
+        )));
+    }
+
+    if !HTTP_PATH_REGEXP.is_match(value) {
+        return Err(Error::CommonError("Invalid path: ".to_owned() + value));
+    }
+
+    return Ok(());
+}
+
+// FieldError is a validation failure of one field of an object. The path is the
+// JSON path of the field inside the object, e.g. "object.spec.envs[0][0]".
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldError {
+    pub path: String,
+    pub detail: String,
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.len() == 0 {
+            return write!(f, "{}", self.detail);
+        }
+        return write!(f, "{}: {}", self.path, self.detail);
+    }
+}
+
+// ErrorList collects all the FieldErrors found while validating an object, so that
+// the caller can report them at once instead of stopping at the first one.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ErrorList {
+    pub errs: Vec<FieldError>,
+}
+
+impl fmt::Display for ErrorList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for e in &self.errs {
+            writeln!(f, "{}", e)?;
+        }
+        return Ok(());
+    }
+}
+
+impl ErrorList {
+    pub fn IsEmpty(&self) -> bool {
+        return self.errs.len() == 0;
+    }
+
+    pub fn Len(&self) -> usize {
+        return self.errs.len();
+    }
+
+    pub fn Add(&mut self, path: &str, detail: &str) {
+        self.errs.push(FieldError {
+            path: path.to_owned(),
+            detail: detail.to_owned(),
+        });
+    }
+
+    // Check records the error returned by one of the IsXXX helpers against path.
+    pub fn Check(&mut self, path: &str, res: Result<()>) {
+        match res {
+            Ok(()) => (),
+            Err(Error::CommonError(s)) => self.Add(path, &s),
+            Err(e) => self.Add(path, &format!("{:?}", e)),
+        }
+    }
+}
+
+// FieldPath returns the JSON path of the child field of path.
+pub fn FieldPath(path: &str, field: &str) -> String {
+    if path.len() == 0 {
+        return field.to_owned();
+    }
+    return format!("{}.{}", path, field);
+}
+
+// IndexPath returns the JSON path of the idx-th element of the array at path.
+pub fn IndexPath(path: &str, idx: usize) -> String {
+    return format!("{}[{}]", path, idx);
+}
+
+// ValidateObjectMeta checks the identity fields shared by all the DataObjects.
+// Tenants are used as DNS labels, namespace and name only need to be qualified names
+// as they mirror model names such as "Llama-3.2-3B-Instruct".
+pub fn ValidateObjectMeta(obj: &DataObject<Value>, objType: &str, errs: &mut ErrorList) {
+    if obj.objType != objType {
+        errs.Add(
+            "type",
+            &format!("expect object type {}, actual: {}", objType, &obj.objType),
+        );
+    }
+
+    errs.Check("tenant", IsDNS1123Label(&obj.tenant));
+    errs.Check("namespace", IsQualifiedName(&obj.namespace));
+    errs.Check("name", IsQualifiedName(&obj.name));
+
+    for (k, v) in obj.labels.iter() {
+        let path = FieldPath("labels", k);
+        errs.Check(&path, ValidateLabelKey(k));
+        errs.Check(&path, ValidateLabelValue(k, v));
+    }
+}
+
+// ValidateScope checks that the object is stored where the objects of its kind live.
+pub fn ValidateScope(obj: &DataObject<Value>, scope: ObjectScope, errs: &mut ErrorList) {
+    if scope == ObjectScope::System && obj.tenant != SYSTEM_TENANT {
+        errs.Add(
+            "tenant",
+            &format!("must be {}, actual: {}", SYSTEM_TENANT, &obj.tenant),
+        );
+    }
+
+    if scope != ObjectScope::Namespace && obj.namespace != SYSTEM_NAMESPACE {
+        errs.Add(
+            "namespace",
+            &format!("must be {}, actual: {}", SYSTEM_NAMESPACE, &obj.namespace),
+        );
+    }
+}
+
+// DecodeObject decodes the object field of a DataObject into its typed spec. On failure
+// the JSON path of the offending field is recorded instead of the bare serde error. Fields
+// which are dropped silently by serde, e.g. a misspelled "sample_qurey", are reported as
+// unknown.
+pub fn DecodeObject<T: Serialize + for<'a> Deserialize<'a>>(
+    object: &Value,
+    errs: &mut ErrorList,
+) -> Option<T> {
+    let typed: T = match serde_path_to_error::deserialize(object.clone()) {
+        Ok(t) => t,
+        Err(e) => {
+            let path = e.path().to_string();
+            let path = if path == "." {
+                "object".to_owned()
+            } else {
+                FieldPath("object", &path)
+            };
+            errs.Add(&path, &e.into_inner().to_string());
+            return None;
+        }
+    };
+
+    let known = match serde_json::to_value(&typed) {
+        Ok(v) => v,
+        Err(e) => {
+            errs.Add("object", &e.to_string());
+            return None;
+        }
+    };
+
+    UnknownFields("object", object, &known, errs);
+    return Some(typed);
+}
+
+// UnknownFields reports the fields of input which are missing in known, the value
+// serialized back from the typed spec.
+pub fn UnknownFields(path: &str, input: &Value, known: &Value, errs: &mut ErrorList) {
+    match (input, known) {
+        (Value::Object(input), Value::Object(known)) => {
+            for (k, v) in input {
+                let childPath = FieldPath(path, k);
+                match known.get(k) {
+                    None => errs.Add(&childPath, "unknown field"),
+                    Some(kv) => UnknownFields(&childPath, v, kv, errs),
+                }
+            }
+        }
+        (Value::Array(input), Value::Array(known)) => {
+            for (i, (v, kv)) in input.iter().zip(known.iter()).enumerate() {
+                UnknownFields(&IndexPath(path, i), v, kv, errs);
+            }
+        }
+        _ => (),
+    }
+}
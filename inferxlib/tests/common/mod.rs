@@ -0,0 +1,56 @@
+// Copyright (c) 2023 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// the node and request factories shared by the resource tests, each test uses some
+#![allow(dead_code)]
+
+use inferxlib::resource::*;
+
+pub const SLOT_SIZE: u64 = 1024 * 1024 * 1024; // 1 GB
+
+// GPUs with the free GB of each GPU, GPU i is the ith one
+pub fn GPUs(free: &[u32], contextCnt: u64) -> GPUResourceMap {
+    let mut gpus = GPUResourceMap {
+        slotSize: SLOT_SIZE,
+        ..Default::default()
+    };
+    for (i, slotCnt) in free.iter().enumerate() {
+        gpus.totalSlotCnt += *slotCnt;
+        gpus.map.insert(
+            i as i32,
+            GPUAlloc {
+                contextCnt: contextCnt,
+                slotCnt: *slotCnt,
+            },
+        );
+    }
+    return gpus;
+}
+
+// Node is a node of any GPU type with 64 cores and 500 GB memory
+pub fn Node(gpus: GPUResourceMap, maxContextCnt: u64) -> NodeResources {
+    return NodeResources::New("node1", 64000, 512000, GPUType::Any(), gpus, maxContextCnt);
+}
+
+pub fn Req(gpuCount: u64, vRamGB: u64) -> Resources {
+    return Resources {
+        cpu: 1000,
+        memory: 1000,
+        gpu: GPUResource {
+            gpuCount: gpuCount,
+            vRam: vRamGB * 1024,
+            ..Default::default()
+        },
+    };
+}
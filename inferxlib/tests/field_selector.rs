@@ -0,0 +1,124 @@
+// Copyright (c) 2023 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(non_snake_case)]
+
+use serde_json::{json, Value};
+
+use inferxlib::selector::{FieldSelector, Selector};
+
+fn Object(state: Value, gpuCount: u64) -> Value {
+    return json!({
+        "type": "function",
+        "tenant": "t1",
+        "namespace": "ns1",
+        "name": "f1",
+        "object": {
+            "spec": {"resources": {"GPU": {"Type": "Any", "Count": gpuCount, "vRam": 4500}}},
+            "status": {"state": state}
+        }
+    });
+}
+
+fn Match(selector: &str, obj: &Value) -> bool {
+    return FieldSelector::Parse(selector).unwrap().Match(obj);
+}
+
+#[test]
+fn FieldEquals() {
+    let ready = Object(json!("Ready"), 1);
+    let failed = Object(json!("Fail"), 1);
+
+    assert!(Match("object.status.state=Ready", &ready));
+    assert!(!Match("object.status.state=Ready", &failed));
+    assert!(Match("object.status.state!=Ready", &failed));
+    assert!(Match("object.status.state in (Ready,Fail)", &failed));
+    assert!(Match("name=f1,object.status.state=Ready", &ready));
+}
+
+#[test]
+fn FieldCompare() {
+    let gpu1 = Object(json!("Ready"), 1);
+    let gpu2 = Object(json!("Ready"), 2);
+
+    assert!(!Match("object.spec.resources.GPU.Count>1", &gpu1));
+    assert!(Match("object.spec.resources.GPU.Count>1", &gpu2));
+    assert!(Match("object.spec.resources.GPU.Count<2", &gpu1));
+    assert!(Match(
+        "object.spec.resources.GPU.Count>1,object.status.state=Ready",
+        &gpu2
+    ));
+}
+
+#[test]
+fn MissingAndNullFields() {
+    // a missing or null field is an absent label
+    let null = Object(Value::Null, 1);
+    let mut missing = Object(json!("Ready"), 1);
+    missing["object"]["status"]
+        .as_object_mut()
+        .unwrap()
+        .remove("state");
+    for obj in [null, missing] {
+        assert!(!Match("object.status.state=Ready", &obj));
+        assert!(!Match("object.status.state", &obj));
+        assert!(Match("!object.status.state", &obj));
+        assert!(Match("object.status.state!=Ready", &obj));
+        assert!(Match("object.status.state notin (Ready)", &obj));
+        assert!(!Match("object.status.state>0", &obj));
+    }
+
+    // a path through a scalar is missing too
+    let obj = Object(json!("Ready"), 1);
+    assert!(!Match("object.status.state.phase=Ready", &obj));
+    assert!(Match("!object.status.state.phase", &obj));
+
+    // the same walk backs Selector::GetAttributes
+    let selector = Selector::Parse("tier=gold").unwrap();
+    assert_eq!(selector.GetAttributes(&obj), None);
+    assert_eq!(
+        Selector::GetAttribute(&obj, "object.status.state"),
+        Some(&json!("Ready"))
+    );
+}
+
+#[test]
+fn InvalidFieldPath() {
+    for selector in [
+        "object..state=Ready",
+        ".object.state=Ready",
+        "object.state.=Ready",
+        "object/status=Ready",
+        "object.status.state=Ready,",
+        "object.spec.resources.GPU.Count>one",
+    ] {
+        assert!(FieldSelector::Parse(selector).is_err(), "{}", selector);
+    }
+}
+
+#[test]
+fn LabelKeyStillValidated() {
+    // a field path which isn't a valid label key is rejected by the label selector
+    for selector in [
+        "-tier=gold",
+        "tier_=gold",
+        "object.spec.resources.GPU.Count.with-a-segment-well-past-the-63-characters=1",
+    ] {
+        assert!(FieldSelector::Parse(selector).is_ok(), "{}", selector);
+        assert!(Selector::Parse(selector).is_err(), "{}", selector);
+    }
+
+    assert!(Selector::Parse("example.com/tier=gold").is_ok());
+    assert!(FieldSelector::Parse("example.com/tier=gold").is_err());
+}
@@ -0,0 +1,148 @@
+// Copyright (c) 2023 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(non_snake_case)]
+
+use serde_json::{json, Value};
+
+use inferxlib::data_obj::{DataObject, DataObjectMgr, DataObjectMgrInner};
+use inferxlib::obj_mgr::func_mgr::{
+    ChangedSpecFields, FuncMgr, FuncObject, FuncState, Rollback, SpecVersion,
+};
+
+fn Function(version: i64, image: &str, vRam: u64) -> DataObject<Value> {
+    return serde_json::from_value(json!({
+        "type": "function",
+        "tenant": "t1",
+        "namespace": "ns1",
+        "name": "f1",
+        "revision": version * 10,
+        "object": {
+            "spec": {
+                "version": version,
+                "image": image,
+                "resources": {"GPU": {"Count": 1, "vRam": vRam}}
+            },
+            "status": {"state": "Normal"}
+        }
+    }))
+    .unwrap();
+}
+
+#[test]
+fn RollbackKeepsCurrentVersion() {
+    let mut current = Function(3, "vllm:v2", 16000);
+    current.labels = serde_json::from_value(json!({"tier": "gold"})).unwrap();
+    let target = Function(1, "vllm:v1", 8000);
+
+    let obj = Rollback(&current, &target).unwrap();
+    assert_eq!(obj.object["spec"]["image"], json!("vllm:v1"));
+    assert_eq!(
+        obj.object["spec"]["resources"],
+        target.object["spec"]["resources"]
+    );
+    assert_eq!(SpecVersion(&obj), 3);
+    assert_eq!(obj.revision, current.revision);
+    assert_eq!(obj.labels.String(), current.labels.String());
+    assert_eq!(obj.object["status"], current.object["status"]);
+
+    let mut noSpec = target.clone();
+    noSpec.object = json!({});
+    assert!(Rollback(&current, &noSpec).is_err());
+}
+
+#[test]
+fn ChangedFields() {
+    let v1 = Function(1, "vllm:v1", 8000);
+    let v2 = Function(2, "vllm:v1", 16000);
+    let mut v3 = Function(3, "vllm:v2", 16000);
+    v3.object["spec"]["envs"] = json!([["A", "1"]]);
+
+    // the version always changes and isn't listed
+    assert_eq!(ChangedSpecFields(&v1, &v2), vec!["resources"]);
+    assert_eq!(ChangedSpecFields(&v2, &v3), vec!["envs", "image"]);
+    assert_eq!(ChangedSpecFields(&v3, &v2), vec!["envs", "image"]);
+    assert!(ChangedSpecFields(&v1, &v1).is_empty());
+
+    let mut noSpec = v1.clone();
+    noSpec.object = json!({});
+    assert_eq!(SpecVersion(&noSpec), 0);
+    assert_eq!(ChangedSpecFields(&noSpec, &v1), vec!["image", "resources"]);
+}
+
+fn SpecChanged(old: &DataObject<Value>, new: &DataObject<Value>) -> bool {
+    return !ChangedSpecFields(old, new).is_empty();
+}
+
+#[test]
+fn HistoryLimit() {
+    let mgr: DataObjectMgr<Value> = DataObjectMgr::NewWithHistory(SpecChanged);
+    mgr.Add(Function(1, "vllm:v1", 8000)).unwrap();
+    assert_eq!(mgr.History("t1", "ns1", "f1").unwrap().len(), 1);
+
+    let count = DataObjectMgrInner::<Value>::HISTORY_LIMIT as i64 + 5;
+    for version in 2..=count {
+        let mut obj = Function(version, "vllm:v1", 8000 + version as u64);
+        obj.revision = mgr.Get("t1", "ns1", "f1").unwrap().revision;
+        mgr.Update(obj).unwrap();
+    }
+
+    // the oldest versions are dropped, the current one comes last
+    let history = mgr.History("t1", "ns1", "f1").unwrap();
+    let versions: Vec<i64> = history.iter().map(SpecVersion).collect();
+    let expect: Vec<i64> =
+        (count - DataObjectMgrInner::<Value>::HISTORY_LIMIT as i64..=count).collect();
+    assert_eq!(versions, expect);
+
+    mgr.Remove(Function(count, "vllm:v1", 8000 + count as u64))
+        .unwrap();
+    assert!(mgr.History("t1", "ns1", "f1").is_err());
+}
+
+#[test]
+fn HistoryOptIn() {
+    // a manager without history keeps only the current version
+    let mgr: DataObjectMgr<Value> = DataObjectMgr::default();
+    mgr.Add(Function(1, "vllm:v1", 8000)).unwrap();
+    mgr.Update(Function(1, "vllm:v2", 8000)).unwrap();
+    let history = mgr.History("t1", "ns1", "f1").unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].object["spec"]["image"], json!("vllm:v2"));
+
+    // the function manager records the spec changes only
+    let mgr = FuncMgr::New();
+    let base: DataObject<FuncObject> =
+        DataObject::NewFromString(include_str!("../../config/TinyLlama-1.1B-Chat-v1.0.json"))
+            .unwrap();
+    let func = |version: i64, image: &str, state: FuncState| -> DataObject<FuncObject> {
+        let mut obj = base.clone();
+        obj.object.spec.version = version;
+        obj.object.spec.image = image.to_owned();
+        obj.object.status.state = state;
+        return obj;
+    };
+    mgr.Add(func(1, "vllm:v1", FuncState::Normal)).unwrap();
+    mgr.Update(func(1, "vllm:v1", FuncState::Fail)).unwrap();
+    mgr.Update(func(2, "vllm:v2", FuncState::Fail)).unwrap();
+    mgr.Update(func(2, "vllm:v2", FuncState::Normal)).unwrap();
+    // a version bump alone isn't a spec change
+    mgr.Update(func(3, "vllm:v2", FuncState::Normal)).unwrap();
+
+    let history = mgr
+        .History(&base.tenant, &base.namespace, &base.name)
+        .unwrap();
+    let versions: Vec<i64> = history.iter().map(|f| f.Version()).collect();
+    assert_eq!(versions, vec![1, 3]);
+    assert_eq!(history[0].object.spec.image, "vllm:v1");
+}
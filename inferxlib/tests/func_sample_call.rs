@@ -0,0 +1,96 @@
+// Copyright (c) 2023 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(non_snake_case)]
+
+use serde_json::{json, Value};
+
+use inferxlib::obj_mgr::func_mgr::{BodyValue, SampleCall};
+
+fn Decode(body: Value) -> SampleCall {
+    return serde_json::from_value(json!({
+        "apiType": "openai",
+        "path": "v1/completions",
+        "prompt": "Seattle is a",
+        "body": body
+    }))
+    .unwrap();
+}
+
+#[test]
+fn TypedBodyFields() {
+    let call = Decode(json!({
+        "model": "123",
+        "name": "true",
+        "image": "[1]",
+        "max_tokens": "1000",
+        "temperature": "0.5",
+        "stream": "true",
+        "stop": "null"
+    }));
+    assert_eq!(
+        Value::Object(call.RequestBody()),
+        json!({
+            "prompt": "Seattle is a",
+            "model": "123",
+            "name": "true",
+            "image": "[1]",
+            "max_tokens": 1000,
+            "temperature": 0.5,
+            "stream": true,
+            "stop": "null"
+        })
+    );
+
+    // a typed field which doesn't parse as its type stays a string
+    assert_eq!(BodyValue("max_tokens", "lots"), json!("lots"));
+    assert_eq!(BodyValue("max_tokens", "true"), json!("true"));
+    assert_eq!(BodyValue("stream", "1"), json!("1"));
+    assert_eq!(BodyValue("stream", "false"), json!(false));
+}
+
+#[test]
+fn ConfigRoundTrip() {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../config");
+    let mut count = 0;
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|e| e != "json") {
+            continue;
+        }
+        let content = std::fs::read_to_string(&path).unwrap();
+        let obj: Value = serde_json::from_str(&content).unwrap();
+        let query = &obj["object"]["spec"]["sample_query"];
+        if obj["type"] != "function" || query.is_null() {
+            continue;
+        }
+
+        let call: SampleCall = serde_json::from_value(query.clone()).unwrap();
+        let back: SampleCall =
+            serde_json::from_value(serde_json::to_value(&call).unwrap()).unwrap();
+        assert_eq!(back.body, call.body, "{:?}", path);
+
+        // only the typed fields are converted
+        for (k, v) in query["body"].as_object().unwrap() {
+            let expect = match k.as_str() {
+                "max_tokens" | "temperature" => json!(v.as_str().unwrap().parse::<u64>().unwrap()),
+                "stream" => json!(v.as_str().unwrap().parse::<bool>().unwrap()),
+                _ => v.clone(),
+            };
+            assert_eq!(call.body[k], expect, "{:?} {}", path, k);
+        }
+        count += 1;
+    }
+    assert!(count > 50, "{} functions", count);
+}
@@ -0,0 +1,107 @@
+// Copyright (c) 2023 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(non_snake_case)]
+
+use serde_json::json;
+
+use inferxlib::func_api::{FuncRequest, SseDecoder, StreamChunk};
+use inferxlib::obj_mgr::func_mgr::ApiType;
+
+// Decode feeds the response split at the given byte offsets
+fn Decode(resp: &str, splits: &[usize]) -> Vec<String> {
+    let bytes = resp.as_bytes();
+    let mut decoder = SseDecoder::New();
+    let mut events = Vec::new();
+    let mut start = 0;
+    for end in splits.iter().cloned().chain([bytes.len()]) {
+        events.extend(decoder.Feed(&bytes[start..end]));
+        start = end;
+    }
+    events.extend(decoder.Finish());
+    return events;
+}
+
+fn Request(path: &str) -> FuncRequest {
+    let body = json!({"prompt": "Seattle is a", "stream": true});
+    let body = body.as_object().unwrap().clone();
+    return FuncRequest::New(&ApiType::OpenAI, path, body).unwrap();
+}
+
+#[test]
+fn SplitChunks() {
+    let resp =
+        "data: {\"a\": 1}\n\ndata: caf\u{e9}\r\n\r\n: keep alive\n\nevent: done\ndata: [DONE]\n\n";
+    let expect = vec!["{\"a\": 1}", "caf\u{e9}", "[DONE]"];
+    assert_eq!(Decode(resp, &[]), expect);
+
+    // at every byte, including inside the "data:" prefix, the CRLF and the é
+    for split in 1..resp.len() {
+        assert_eq!(Decode(resp, &[split]), expect, "split at {}", split);
+    }
+    let every: Vec<usize> = (1..resp.len()).collect();
+    assert_eq!(Decode(resp, &every), expect);
+}
+
+#[test]
+fn MultiLineData() {
+    // the data lines of an event are joined with newlines
+    let resp = "data: line 1\ndata:line 2\ndata:\n\ndata: next\n\n";
+    assert_eq!(Decode(resp, &[]), vec!["line 1\nline 2\n", "next"]);
+
+    // the last event doesn't need the blank line
+    assert_eq!(
+        Decode("data: a\n\ndata: b", &[]),
+        vec!["a".to_owned(), "b".to_owned()]
+    );
+    assert_eq!(Decode("data: a\n", &[]), vec!["a"]);
+    assert!(Decode("\n\n: comment\n", &[]).is_empty());
+}
+
+#[test]
+fn DecodeChunk() {
+    let completion = Request("/v1/completions");
+    assert_eq!(
+        completion
+            .DecodeChunk(r#"{"id": "c1", "choices": [{"index": 0, "text": " city"}]}"#)
+            .unwrap(),
+        StreamChunk::Text(" city".to_owned())
+    );
+    assert_eq!(completion.DecodeChunk("[DONE]").unwrap(), StreamChunk::Done);
+    assert_eq!(
+        completion.DecodeChunk(" [DONE] ").unwrap(),
+        StreamChunk::Done
+    );
+    assert!(completion.DecodeChunk("{\"choices\": ").is_err());
+
+    let chat = Request("/v1/chat/completions");
+    let chunks = [
+        r#"{"choices": [{"index": 0, "delta": {"role": "assistant"}}]}"#,
+        r#"{"choices": [{"index": 0, "delta": {"content": "Hello"}}]}"#,
+        r#"{"choices": [{"index": 0, "delta": {}, "finish_reason": "stop"}]}"#,
+    ];
+    let text: Vec<StreamChunk> = chunks
+        .iter()
+        .map(|c| chat.DecodeChunk(c).unwrap())
+        .collect();
+    assert_eq!(
+        text,
+        vec![
+            StreamChunk::Text(String::new()),
+            StreamChunk::Text("Hello".to_owned()),
+            StreamChunk::Text(String::new()),
+        ]
+    );
+    assert_eq!(chat.DecodeChunk("[DONE]").unwrap(), StreamChunk::Done);
+}
@@ -0,0 +1,180 @@
+// Copyright (c) 2023 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(non_snake_case)]
+
+use serde_json::{json, Value};
+
+use inferxlib::data_obj::DataObject;
+use inferxlib::obj_mgr::func_mgr::ValidateFunction;
+use inferxlib::validation::*;
+
+const TINY_LLAMA: &str = include_str!("../../config/TinyLlama-1.1B-Chat-v1.0.json");
+
+fn Function() -> Value {
+    return serde_json::from_str(TINY_LLAMA).unwrap();
+}
+
+fn Validate(v: Value) -> ErrorList {
+    let obj: DataObject<Value> = serde_json::from_value(v).unwrap();
+    return ValidateFunction(&obj);
+}
+
+fn Paths(errs: &ErrorList) -> Vec<&str> {
+    return errs.errs.iter().map(|e| e.path.as_str()).collect();
+}
+
+#[test]
+fn ValidFunction() {
+    let errs = Validate(Function());
+    assert!(errs.IsEmpty(), "{}", errs);
+}
+
+#[test]
+fn UnknownField() {
+    let mut v = Function();
+    let spec = v["object"]["spec"].as_object_mut().unwrap();
+    let query = spec.remove("sample_query").unwrap();
+    spec.insert("sample_qurey".to_owned(), query);
+
+    let errs = Validate(v);
+    assert_eq!(Paths(&errs), vec!["object.spec.sample_qurey"]);
+    assert_eq!(errs.errs[0].detail, "unknown field");
+
+    // fields nested in a known one are reported with their full path
+    let mut known = json!({"a": {"b": 1}, "c": [{"d": 1}]});
+    let input = json!({"a": {"b": 1, "x": 2}, "c": [{"d": 1, "y": 3}]});
+    let mut errs = ErrorList::default();
+    UnknownFields("object", &input, &known, &mut errs);
+    assert_eq!(Paths(&errs), vec!["object.a.x", "object.c[0].y"]);
+
+    known["a"]["x"] = json!(2);
+    known["c"][0]["y"] = json!(3);
+    let mut errs = ErrorList::default();
+    UnknownFields("object", &input, &known, &mut errs);
+    assert!(errs.IsEmpty());
+}
+
+#[test]
+fn DecodeErrorPath() {
+    let mut v = Function();
+    v["object"]["spec"]["resources"]["GPU"]["Count"] = json!("two");
+
+    let errs = Validate(v);
+    assert_eq!(Paths(&errs), vec!["object.spec.resources.GPU.Count"]);
+}
+
+#[test]
+fn InvalidFields() {
+    let mut v = Function();
+    v["object"]["spec"]["resources"]["GPU"]["Count"] = json!(9);
+    assert_eq!(Paths(&Validate(v)), vec!["object.spec.resources.GPU.Count"]);
+
+    let mut v = Function();
+    v["object"]["spec"]["envs"] = json!([["PATH", "/bin"], ["1BAD=NAME", "x"]]);
+    assert_eq!(Paths(&Validate(v)), vec!["object.spec.envs[1][0]"]);
+
+    let mut v = Function();
+    v["object"]["spec"]["endpoint"]["port"] = json!(0);
+    assert_eq!(Paths(&Validate(v)), vec!["object.spec.endpoint.port"]);
+
+    let mut v = Function();
+    v["object"]["spec"]["endpoint"]["probe"] = json!("health check");
+    assert_eq!(Paths(&Validate(v)), vec!["object.spec.endpoint.probe"]);
+
+    let mut v = Function();
+    v["object"]["spec"]["resources"]["Mem"] = json!(0);
+    assert_eq!(Paths(&Validate(v)), vec!["object.spec.resources.Mem"]);
+
+    let mut v = Function();
+    v["object"]["spec"]["image"] = json!("vllm/vllm-openai:v0.4.2 ");
+    assert_eq!(Paths(&Validate(v)), vec!["object.spec.image"]);
+
+    // all the errors are reported at once
+    let mut v = Function();
+    v["object"]["spec"]["resources"]["Mem"] = json!(0);
+    v["object"]["spec"]["endpoint"]["port"] = json!(0);
+    v["name"] = json!("bad name");
+    assert_eq!(
+        Paths(&Validate(v)),
+        vec![
+            "name",
+            "object.spec.endpoint.port",
+            "object.spec.resources.Mem"
+        ]
+    );
+}
+
+#[test]
+fn ImageReference() {
+    for image in [
+        "ubuntu",
+        "vllm/vllm-openai:v0.6.2",
+        "docker.io/library/ubuntu:22.04",
+        "localhost:5000/inferx/vllm:latest",
+        "ubuntu@sha256:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef",
+    ] {
+        assert!(IsImageReference(image).is_ok(), "{}", image);
+    }
+
+    for image in [
+        "",
+        "Ubuntu",
+        "vllm/vllm-openai:",
+        "vllm/vllm-openai:v0.6.2:latest",
+        "vllm//vllm-openai",
+        "ubuntu@sha256:1234",
+        " ubuntu",
+    ] {
+        assert!(IsImageReference(image).is_err(), "{}", image);
+    }
+}
+
+#[test]
+fn HttpPath() {
+    for path in ["/", "/health", "/v1/models", "/a-b_c.d~e"] {
+        assert!(IsHttpPath(path).is_ok(), "{}", path);
+    }
+
+    for path in ["", "health", "/health check", "/health\n"] {
+        assert!(IsHttpPath(path).is_err(), "{}", path);
+    }
+}
+
+#[test]
+fn ConfigFunctionsValidate() {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../config");
+    let mut count = 0;
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|e| e != "json") {
+            continue;
+        }
+        let content = std::fs::read_to_string(&path).unwrap();
+        let v: Value = serde_json::from_str(&content).unwrap();
+        if v["type"] != "function" {
+            continue;
+        }
+        // the files of the legacy layout, without "object" or with an endpoint without
+        // probe, are no longer accepted
+        if v["object"].is_null() || v["object"]["spec"]["endpoint"]["probe"].is_null() {
+            continue;
+        }
+
+        let errs = Validate(v);
+        assert!(errs.IsEmpty(), "{}: {}", path.display(), errs);
+        count += 1;
+    }
+    assert!(count > 0);
+}
@@ -0,0 +1,60 @@
+// Copyright (c) 2023 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(non_snake_case)]
+
+mod common;
+
+use common::*;
+use inferxlib::placement::PlacementPolicy;
+use inferxlib::resource::*;
+
+fn Usage(gpuCount: u64, vRamGB: u64, policy: PlacementPolicy) -> GPUResource {
+    return GPUResource {
+        gpuCount: gpuCount,
+        vRam: vRamGB * 1024,
+        placement: Some(policy),
+        ..Default::default()
+    };
+}
+
+fn Ids(alloc: &GPUResourceMap) -> Vec<i32> {
+    return alloc.map.keys().cloned().collect();
+}
+
+#[test]
+fn BinPackKeepsWholeGPUsFree() {
+    // GPUs 0 and 1 already run a small function
+    let mut gpus = GPUs(&[40, 50, 80, 80], 4);
+
+    // the small functions fill the partially used GPUs, fullest first
+    let alloc = gpus.Alloc(&Usage(1, 20, PlacementPolicy::BinPack)).unwrap();
+    assert_eq!(Ids(&alloc), vec![0]);
+    let alloc = gpus.Alloc(&Usage(1, 30, PlacementPolicy::BinPack)).unwrap();
+    assert_eq!(Ids(&alloc), vec![1]);
+
+    // which leaves both whole GPUs to a _2gpu config
+    let alloc = gpus.Alloc(&Usage(2, 80, PlacementPolicy::BinPack)).unwrap();
+    assert_eq!(Ids(&alloc), vec![2, 3]);
+}
+
+#[test]
+fn SpreadFragmentsWholeGPUs() {
+    let mut gpus = GPUs(&[40, 50, 80, 80], 4);
+
+    // the same small function goes to an empty GPU
+    let alloc = gpus.Alloc(&Usage(1, 20, PlacementPolicy::Spread)).unwrap();
+    assert_eq!(Ids(&alloc), vec![3]);
+    assert!(gpus.Alloc(&Usage(2, 80, PlacementPolicy::Spread)).is_err());
+}
@@ -0,0 +1,172 @@
+// Copyright (c) 2023 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(non_snake_case)]
+
+mod common;
+
+use common::*;
+use inferxlib::placement::{GPULink, GPUTopology, PlacementPolicy};
+use inferxlib::resource::*;
+
+// 2 NVLink pairs on 2 NUMA nodes: 0-1 and 2-3
+fn Topology() -> GPUTopology {
+    let filename = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/gpu_topology_4gpu.json"
+    );
+    return GPUTopology::Load(filename).unwrap();
+}
+
+// a node of 4 80 GB GPUs
+fn TopologyNode(policy: PlacementPolicy, topology: GPUTopology) -> NodeResources {
+    let mut node = Node(GPUs(&[80; 4], 4), 4);
+    node.placement = policy;
+    node.topology = topology;
+    return node;
+}
+
+#[test]
+fn LoadFixture() {
+    let topology = Topology();
+    assert_eq!(topology.Link(0, 1), Some(GPULink::NVLink(4)));
+    assert_eq!(topology.Link(1, 2), Some(GPULink::SYS));
+    assert_eq!(topology.Link(3, 3), Some(GPULink::Self_));
+
+    let json = serde_json::to_string(&topology).unwrap();
+    let back: GPUTopology = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, topology);
+}
+
+#[test]
+fn RejectInvalidTopology() {
+    assert!(
+        serde_json::from_str::<GPUTopology>(r#"{"Links": [["X", "NVX"], ["NVX", "X"]]}"#).is_err()
+    );
+
+    let asymmetric: GPUTopology =
+        serde_json::from_str(r#"{"Links": [["X", "NV2"], ["PIX", "X"]]}"#).unwrap();
+    assert!(asymmetric.Validate().is_err());
+
+    let numa: GPUTopology =
+        serde_json::from_str(r#"{"Links": [["X", "NV2"], ["NV2", "X"]], "Numa": [0]}"#).unwrap();
+    assert!(numa.Validate().is_err());
+
+    // a short row after the one being checked
+    let ragged: GPUTopology =
+        serde_json::from_str(r#"{"Links": [["X", "NV1", "NV1"], ["NV1", "X", "NV1"], []]}"#)
+            .unwrap();
+    assert!(ragged.Validate().is_err());
+}
+
+#[test]
+fn TopologyPrefersNVLinkPair() {
+    let mut node = TopologyNode(PlacementPolicy::Topology, Topology());
+    let req = Req(2, 40);
+    assert_eq!(node.TopologyScore(&req), Some(104));
+
+    let first = node.Alloc(&req).unwrap();
+    assert_eq!(first.gpus.Gpus(), vec![0, 1]);
+    assert_eq!(first.topologyScore, Some(104));
+
+    // 0 and 1 still have room for another 40 GB each, which keeps the pair together
+    let second = node.Alloc(&req).unwrap();
+    assert_eq!(second.gpus.Gpus(), vec![0, 1]);
+
+    let third = node.Alloc(&Req(2, 60)).unwrap();
+    assert_eq!(third.gpus.Gpus(), vec![2, 3]);
+    assert_eq!(third.topologyScore, Some(104));
+}
+
+#[test]
+fn TopologyAvoidsCrossNumaSet() {
+    let mut node = TopologyNode(PlacementPolicy::Topology, Topology());
+
+    // a small function takes GPU 1, which leaves 0-2, 0-3 across the NUMA nodes and 2-3
+    node.gpus.map.get_mut(&1).unwrap().slotCnt = 10;
+    node.gpus.map.get_mut(&2).unwrap().slotCnt = 70;
+    node.gpus.map.get_mut(&3).unwrap().slotCnt = 70;
+
+    let alloc = node.Alloc(&Req(2, 60)).unwrap();
+    assert_eq!(alloc.gpus.Gpus(), vec![2, 3]);
+
+    // only the cross NUMA set 0-2 is left
+    node.gpus.map.get_mut(&1).unwrap().slotCnt = 0;
+    node.gpus.map.get_mut(&3).unwrap().slotCnt = 0;
+    let req = Req(2, 5);
+    assert_eq!(node.TopologyScore(&req), Some(GPULink::SYS.Score()));
+}
+
+// PartlyUsed frees 80, 40, 50 and 30 GB of the GPUs: spread would take 0 and 2 and
+// bin-pack 1 and 3, both across the NUMA nodes, where the topology takes the 2-3 pair.
+fn PartlyUsed(node: &mut NodeResources) {
+    for (gpu, free) in [(0, 80), (1, 40), (2, 50), (3, 30)] {
+        node.gpus.map.get_mut(&gpu).unwrap().slotCnt = free;
+    }
+}
+
+#[test]
+fn TopologyOverridesNodePolicy() {
+    // a multi GPU pod is placed by the topology whatever the node policy
+    for policy in [PlacementPolicy::Spread, PlacementPolicy::BinPack] {
+        let mut node = TopologyNode(policy, Topology());
+        PartlyUsed(&mut node);
+        let req = Req(2, 20);
+        assert_eq!(node.Placement(&req), PlacementPolicy::Topology);
+        assert_eq!(node.TopologyScore(&req), Some(104));
+
+        let alloc = node.Alloc(&req).unwrap();
+        assert_eq!(alloc.gpus.Gpus(), vec![2, 3]);
+        assert_eq!(alloc.topologyScore, Some(104));
+
+        // a single GPU pod keeps the node policy
+        assert_eq!(node.Placement(&Req(1, 20)), policy);
+    }
+
+    // the policy set by the function wins, the allocation reports the score of whatever
+    // GPUs it got
+    let mut node = TopologyNode(PlacementPolicy::Topology, Topology());
+    PartlyUsed(&mut node);
+    let mut req = Req(2, 20);
+    req.gpu.placement = Some(PlacementPolicy::Spread);
+    assert_eq!(node.Placement(&req), PlacementPolicy::Spread);
+    let alloc = node.Alloc(&req).unwrap();
+    assert_eq!(alloc.gpus.Gpus(), vec![0, 2]);
+    assert_eq!(alloc.topologyScore, Some(GPULink::SYS.Score()));
+}
+
+#[test]
+fn NumaOnlyTopology() {
+    let topology = GPUTopology {
+        links: Vec::new(),
+        numa: vec![0, 1, 0, 1],
+    };
+    assert_eq!(topology.Score(&[0, 2]), Some(GPULink::NODE.Score()));
+    assert_eq!(topology.Score(&[0, 1]), Some(GPULink::SYS.Score()));
+    assert_eq!(topology.Score(&[0]), None);
+
+    let mut node = TopologyNode(PlacementPolicy::Topology, topology);
+    let alloc = node.Alloc(&Req(2, 40)).unwrap();
+    assert_eq!(alloc.gpus.Gpus(), vec![0, 2]);
+}
+
+#[test]
+fn NoTopology() {
+    let mut node = TopologyNode(PlacementPolicy::Topology, GPUTopology::default());
+    let req = Req(2, 40);
+    assert_eq!(node.TopologyScore(&req), None);
+    let alloc = node.Alloc(&req).unwrap();
+    assert_eq!(alloc.gpus.Gpus(), vec![0, 1]);
+    assert_eq!(alloc.topologyScore, None);
+}
@@ -0,0 +1,173 @@
+// Copyright (c) 2023 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(non_snake_case)]
+
+mod common;
+
+use common::*;
+use inferxlib::resource::*;
+use inferxlib::selector::{Labels, Selector};
+
+// GPU 0, 1: A100 40GB, GPU 2: A100 80GB, GPU 3: H100 80GB, GPU 4: L4 24GB
+fn MixedNode() -> NodeResources {
+    let cards = [
+        ("A100-PCIE-40GB", 40960),
+        ("A100-PCIE-40GB", 40960),
+        ("A100-SXM4-80GB", 81920),
+        ("H100-SXM5-80GB", 81920),
+        ("L4", 24576),
+    ];
+
+    let slotCnts: Vec<u32> = cards
+        .iter()
+        .map(|(_, vRam)| (*vRam / 1024) as u32)
+        .collect();
+    let mut gpus = GPUs(&slotCnts, 2);
+    for (i, (type_, vRam)) in cards.iter().enumerate() {
+        gpus.types
+            .insert(i as i32, GPUInfo::New(GPUType(type_.to_string()), *vRam));
+    }
+    return Node(gpus, 2);
+}
+
+fn TypedReq(type_: &str, gpuCount: u64, vRamGB: u64) -> Resources {
+    let mut req = Req(gpuCount, vRamGB);
+    req.gpu.type_ = GPUTypeSelector::Parse(type_).unwrap();
+    return req;
+}
+
+#[test]
+fn ParseSelector() {
+    assert_eq!(GPUTypeSelector::Parse("Any").unwrap(), GPUTypeSelector::Any);
+    assert_eq!(GPUTypeSelector::Parse("").unwrap(), GPUTypeSelector::Any);
+    assert_eq!(
+        GPUTypeSelector::Parse("A100, H100").unwrap(),
+        GPUTypeSelector::Types(vec![GPUType("A100".to_owned()), GPUType("H100".to_owned())])
+    );
+    let selector = GPUTypeSelector::Parse("family in (A100,H100),vram>=40000").unwrap();
+    assert!(matches!(selector, GPUTypeSelector::Selector(..)));
+    assert_eq!(selector.String(), "family in (A100,H100),vram>=40000");
+    assert_eq!(
+        selector,
+        GPUTypeSelector::Parse("family in (A100,H100),vram>=40000").unwrap()
+    );
+    assert!(GPUTypeSelector::Parse("family in (A100").is_err());
+    assert!(GPUTypeSelector::Parse("vram>=lots").is_err());
+}
+
+#[test]
+fn SerdeGPUResource() {
+    let decode = |s: &str| serde_json::from_str::<GPUResource>(s);
+
+    let gpu = decode(r#"{"Type": "Any", "Count": 1, "vRam": 100}"#).unwrap();
+    assert_eq!(gpu.type_, GPUTypeSelector::Any);
+    // Type is optional
+    let gpu = decode(r#"{"Count": 1, "vRam": 100}"#).unwrap();
+    assert_eq!(gpu.type_, GPUTypeSelector::Any);
+
+    let gpu = decode(r#"{"Type": ["A100", "H100"], "Count": 2, "vRam": 100}"#).unwrap();
+    let json = serde_json::to_value(&gpu).unwrap();
+    assert_eq!(json["Type"], serde_json::json!(["A100", "H100"]));
+    assert_eq!(decode(&json.to_string()).unwrap(), gpu);
+
+    let gpu = decode(r#"{"Type": "vram>=40000", "Count": 1, "vRam": 100}"#).unwrap();
+    let json = serde_json::to_value(&gpu).unwrap();
+    assert_eq!(json["Type"], serde_json::json!("vram>=40000"));
+
+    assert!(decode(r#"{"Type": "family in (", "Count": 1, "vRam": 100}"#).is_err());
+}
+
+#[test]
+fn MatchGPUInfo() {
+    let a100 = GPUInfo::New(GPUType("A100-SXM4-80GB".to_owned()), 81920);
+    let l4 = GPUInfo::New(GPUType("L4".to_owned()), 24576);
+
+    let family = GPUTypeSelector::Parse("A100,H100").unwrap();
+    assert!(family.Match(&a100));
+    assert!(!family.Match(&l4));
+
+    let exact = GPUTypeSelector::Parse("A100-SXM4-80GB").unwrap();
+    assert!(exact.Match(&a100));
+
+    let selector = GPUTypeSelector::Parse("family in (A100,H100),vram>=81920").unwrap();
+    assert!(selector.Match(&a100));
+    assert!(!selector.Match(&l4));
+
+    let small = GPUTypeSelector::Parse("vram<=24576").unwrap();
+    assert!(small.Match(&l4));
+    assert!(!small.Match(&a100));
+}
+
+#[test]
+fn LabelSelectorComparison() {
+    let labels = Labels::New("vram=40960").unwrap();
+    assert!(Selector::Parse("vram>=40960").unwrap().Match(&labels));
+    assert!(!Selector::Parse("vram>40960").unwrap().Match(&labels));
+    assert!(Selector::Parse("vram<=40960").unwrap().Match(&labels));
+    assert!(!Selector::Parse("vram<40960").unwrap().Match(&labels));
+    assert_eq!(
+        Selector::Parse("vram>=40960").unwrap().String(),
+        "vram>=40960"
+    );
+}
+
+#[test]
+fn AllocEnforcesType() {
+    let mut node = MixedNode();
+
+    // any A100 or H100 with at least 40GB, the 40GB A100s have 40960 MB
+    let req = TypedReq("family in (A100,H100),vram>=40000", 2, 30);
+    assert!(node.CanAlloc(&req));
+    let alloc = node.Alloc(&req).unwrap();
+    for gpu in alloc.gpus.Gpus() {
+        assert!(gpu < 4, "got GPU {}", gpu);
+        assert_ne!(alloc.gpus.types[&gpu].Family(), "L4");
+    }
+
+    // only GPU 2 and 3 have 80GB cards
+    let req = TypedReq("vram>=80000", 3, 10);
+    assert!(!node.CanAlloc(&req));
+    let req = TypedReq("vram>=80000", 2, 10);
+    let alloc = node.Alloc(&req).unwrap();
+    assert_eq!(alloc.gpus.Gpus(), vec![2, 3]);
+
+    let req = TypedReq("L4", 1, 20);
+    let alloc = node.Alloc(&req).unwrap();
+    assert_eq!(alloc.gpus.Gpus(), vec![4]);
+    assert!(!node.CanAlloc(&TypedReq("L4", 1, 20)));
+    assert!(!node.CanAlloc(&TypedReq("B200", 1, 1)));
+    assert!(node.CanAlloc(&TypedReq("Any", 1, 1)));
+
+    assert_eq!(
+        node.GPUTypes(),
+        "A100-PCIE-40GBx2,A100-SXM4-80GBx1,H100-SXM5-80GBx1,L4x1"
+    );
+}
+
+#[test]
+fn NodeWithoutGPUTypes() {
+    let mut node = Node(GPUs(&[80], 1), 1);
+    node.gpuType = GPUType("A100-SXM4-80GB".to_owned());
+
+    // the node GPUType applies to all its GPUs, which share the node vRam
+    assert_eq!(node.gpus.GPUVRam(), 81920);
+    assert!(node.CanAlloc(&TypedReq("A100", 1, 10)));
+    assert!(node.CanAlloc(&TypedReq("Any", 1, 10)));
+    assert!(!node.CanAlloc(&TypedReq("H100", 1, 10)));
+    assert!(node.CanAlloc(&TypedReq("vram>=40000", 1, 10)));
+    assert!(node.CanAlloc(&TypedReq("family=A100,vram>=81920", 1, 10)));
+    assert!(!node.CanAlloc(&TypedReq("vram>=100000", 1, 10)));
+    assert_eq!(node.GPUTypes(), "A100-SXM4-80GB");
+}
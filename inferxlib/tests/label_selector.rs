@@ -0,0 +1,49 @@
+// Copyright (c) 2023 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(non_snake_case)]
+
+use inferxlib::selector::{Labels, Requirement, SelectionOp};
+
+fn Req(key: &str, op: SelectionOp, vals: &[&str]) -> Requirement {
+    let vals = vals.iter().map(|v| v.to_string()).collect();
+    return Requirement::New(key, op, vals).unwrap();
+}
+
+#[test]
+fn RequirementEqualComparesValues() {
+    let gold = Req("tier", SelectionOp::In, &["gold"]);
+
+    assert!(gold.Equal(&Req("tier", SelectionOp::In, &["gold"])));
+    assert!(!gold.Equal(&Req("tier", SelectionOp::In, &["silver"])));
+    assert!(!gold.Equal(&Req("tier", SelectionOp::NotIn, &["gold"])));
+    assert!(!gold.Equal(&Req("zone", SelectionOp::In, &["gold"])));
+    assert!(!gold.Equal(&Req("tier", SelectionOp::In, &["gold", "silver"])));
+}
+
+#[test]
+fn RequirementNumericCompare() {
+    let ls = Labels::New("gpus=4").unwrap();
+
+    assert!(Req("gpus", SelectionOp::GreaterThan, &["2"]).Matchs(&ls));
+    assert!(!Req("gpus", SelectionOp::GreaterThan, &["4"]).Matchs(&ls));
+    assert!(Req("gpus", SelectionOp::GreaterThanOrEquals, &["4"]).Matchs(&ls));
+    assert!(Req("gpus", SelectionOp::LessThan, &["8"]).Matchs(&ls));
+    assert!(!Req("gpus", SelectionOp::LessThanOrEquals, &["3"]).Matchs(&ls));
+
+    // a missing label or a non numeric value never matches
+    assert!(!Req("vram", SelectionOp::LessThan, &["8"]).Matchs(&ls));
+    let ls = Labels::New("gpus=many").unwrap();
+    assert!(!Req("gpus", SelectionOp::GreaterThan, &["2"]).Matchs(&ls));
+}
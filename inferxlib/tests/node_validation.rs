@@ -0,0 +1,57 @@
+// Copyright (c) 2023 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(non_snake_case)]
+
+use inferxlib::obj_mgr::node_mgr::NodeSpec;
+use inferxlib::validation::ErrorList;
+
+fn Errors(cidr: &str) -> Vec<String> {
+    let spec = NodeSpec {
+        nodeIp: "10.1.0.10".to_owned(),
+        podMgrPort: 1233,
+        tsotSvcPort: 1234,
+        stateSvcPort: 1235,
+        cidr: cidr.to_owned(),
+        ..Default::default()
+    };
+    let mut errs = ErrorList::default();
+    spec.Validate("object", &mut errs);
+    return errs.errs.iter().map(|e| e.path.clone()).collect();
+}
+
+#[test]
+fn CidrPrefixLength() {
+    for cidr in [
+        "10.1.0.0/16",
+        "10.1.0.0/0",
+        "10.1.0.1/32",
+        "fd00::/8",
+        "fd00::1/128",
+    ] {
+        assert!(Errors(cidr).is_empty(), "{}", cidr);
+    }
+
+    for cidr in [
+        "10.1.0.0/33",
+        "10.1.0.0/64",
+        "10.1.0.0/-1",
+        "fd00::/129",
+        "10.1.0.0/x",
+        "10.1.0/16",
+        "10.1.0.0",
+    ] {
+        assert_eq!(Errors(cidr), vec!["object.cidr"], "{}", cidr);
+    }
+}
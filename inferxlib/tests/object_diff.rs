@@ -0,0 +1,168 @@
+// Copyright (c) 2023 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(non_snake_case)]
+
+use serde_json::{json, Value};
+
+use inferxlib::data_obj::{Classify, DataObject, DiffClass, DiffValue, ValueDiff};
+use inferxlib::obj_mgr::func_mgr::FuncSpec;
+
+fn Function() -> DataObject<Value> {
+    let filename = concat!(env!("CARGO_MANIFEST_DIR"), "/../config/OLMo-1B-hf.json");
+    let content = std::fs::read_to_string(filename).unwrap();
+    return DataObject::NewFromString(&content).unwrap();
+}
+
+fn Diffs(old: Value, new: Value) -> Vec<ValueDiff> {
+    let mut diffs = Vec::new();
+    DiffValue("object", &old, &new, &mut diffs);
+    return diffs;
+}
+
+fn Paths(diffs: &[ValueDiff]) -> Vec<&str> {
+    return diffs.iter().map(|d| d.path.as_str()).collect();
+}
+
+#[test]
+fn DiffNestedObject() {
+    let diffs = Diffs(
+        json!({"spec": {"resources": {"CPU": 1000, "GPU": {"Count": 1, "vRam": 8000}}}}),
+        json!({"spec": {"resources": {"CPU": 1000, "GPU": {"Count": 2, "Type": "A100"}}}}),
+    );
+    assert_eq!(
+        diffs,
+        vec![
+            ValueDiff {
+                path: "object.spec.resources.GPU.Count".to_owned(),
+                old: Some(json!(1)),
+                new: Some(json!(2)),
+            },
+            ValueDiff {
+                path: "object.spec.resources.GPU.vRam".to_owned(),
+                old: Some(json!(8000)),
+                new: None,
+            },
+            ValueDiff {
+                path: "object.spec.resources.GPU.Type".to_owned(),
+                old: None,
+                new: Some(json!("A100")),
+            },
+        ]
+    );
+
+    // a value replaced by one of another type is a single change
+    let diffs = Diffs(json!({"a": {"b": 1}}), json!({"a": [1]}));
+    assert_eq!(Paths(&diffs), vec!["object.a"]);
+    assert!(Diffs(json!({"a": [1, {"b": 2}]}), json!({"a": [1, {"b": 2}]})).is_empty());
+}
+
+#[test]
+fn DiffArrayLength() {
+    let diffs = Diffs(
+        json!({"commands": ["--model", "m1"]}),
+        json!({"commands": ["--model", "m2", "--max-model-len", "2000"]}),
+    );
+    assert_eq!(
+        Paths(&diffs),
+        vec![
+            "object.commands[1]",
+            "object.commands[2]",
+            "object.commands[3]"
+        ]
+    );
+    assert_eq!(diffs[1].old, None);
+    assert_eq!(diffs[1].new, Some(json!("--max-model-len")));
+
+    let diffs = Diffs(json!({"commands": ["a", "b"]}), json!({"commands": []}));
+    assert_eq!(diffs.len(), 2);
+    assert!(diffs.iter().all(|d| d.new.is_none()));
+}
+
+#[test]
+fn DiffIgnoresStatusAndRevision() {
+    let local = Function();
+    let mut stored = local.CopyWithRev(7, 7);
+    stored.object["status"] = json!({
+        "state": "Normal",
+        "snapshotingFailureCnt": 2,
+        "resumingFailureCnt": 0
+    });
+    assert!(stored.Diff(&local).unwrap().is_empty());
+
+    // the version is set by the gateway
+    stored.object["spec"]["version"] = json!(3);
+    assert!(stored.Diff(&local).unwrap().is_empty());
+
+    // a field left out of the file doesn't differ from its stored default
+    let defaulted = local.Normalize().unwrap();
+    assert!(defaulted.object["spec"].get("probe").is_some());
+    assert!(local.object["spec"].get("probe").is_none());
+    assert!(defaulted.Diff(&local).unwrap().is_empty());
+}
+
+#[test]
+fn DiffFunction() {
+    let stored = Function();
+
+    let mut local = stored.clone();
+    local.object["spec"]["sample_query"]["prompt"] = json!("What is the capital of France?");
+    let diffs = stored.Diff(&local).unwrap();
+    assert_eq!(Paths(&diffs), vec!["object.spec.sample_query.prompt"]);
+    assert_eq!(
+        Classify(&local.objType, &diffs[0].path),
+        DiffClass::Cosmetic
+    );
+
+    local.object["spec"]["image"] = json!("vllm/vllm-openai:v0.7.0");
+    let classes: Vec<DiffClass> = stored
+        .Diff(&local)
+        .unwrap()
+        .iter()
+        .map(|d| Classify(&local.objType, &d.path))
+        .collect();
+    assert!(classes.contains(&DiffClass::Resnapshot));
+    assert!(classes.contains(&DiffClass::Cosmetic));
+}
+
+#[test]
+fn ClassifyFields() {
+    let cases = [
+        ("function", "object.spec.image", DiffClass::Resnapshot),
+        ("function", "object.spec.commands[2]", DiffClass::Resnapshot),
+        (
+            "function",
+            "object.spec.resources.GPU.vRam",
+            DiffClass::Resnapshot,
+        ),
+        (
+            "function",
+            "object.spec.sample_query.body.max_tokens",
+            DiffClass::Cosmetic,
+        ),
+        ("function", "object.spec.probe", DiffClass::Cosmetic),
+        ("function", "object.spec.version", DiffClass::System),
+        ("function", "labels.tier", DiffClass::Cosmetic),
+        ("namespace", "object.spec.image", DiffClass::Cosmetic),
+    ];
+    for (objType, path, class) in cases {
+        assert_eq!(Classify(objType, path), class, "{} {}", objType, path);
+    }
+
+    assert!(FuncSpec::ForcesResnapshot("image"));
+    assert!(FuncSpec::ForcesResnapshot("standby"));
+    assert!(!FuncSpec::ForcesResnapshot("sample_query"));
+    assert!(!FuncSpec::ForcesResnapshot("probe"));
+    assert!(!FuncSpec::ForcesResnapshot("version"));
+}
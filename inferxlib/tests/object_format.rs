@@ -0,0 +1,114 @@
+// Copyright (c) 2023 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(non_snake_case)]
+
+use serde_json::Value;
+
+use inferxlib::data_obj::{DataObject, ObjectFormat};
+
+fn Names(content: &str) -> Vec<String> {
+    let format = ObjectFormat::Detect(content);
+    let objs = DataObject::<Value>::NewListFromString(content, format).unwrap();
+    return objs.into_iter().map(|o| o.name).collect();
+}
+
+const F1: &str =
+    r#"{"type": "function", "tenant": "t1", "namespace": "ns1", "name": "f1", "object": {}}"#;
+const F2: &str =
+    r#"{"type": "function", "tenant": "t1", "namespace": "ns1", "name": "f2", "object": {}}"#;
+
+#[test]
+fn DetectFormat() {
+    assert_eq!(ObjectFormat::Detect(F1), ObjectFormat::Json);
+    assert_eq!(
+        ObjectFormat::Detect(&format!("\n  [{}]", F1)),
+        ObjectFormat::Json
+    );
+    assert_eq!(
+        ObjectFormat::Detect(&format!("\u{feff}{}", F1)),
+        ObjectFormat::Json
+    );
+    assert_eq!(ObjectFormat::Detect("type: function\n"), ObjectFormat::Yaml);
+    assert_eq!(
+        ObjectFormat::Detect(&format!("# f1\n{}", F1)),
+        ObjectFormat::Yaml
+    );
+
+    assert_eq!(
+        ObjectFormat::FromFilename("f1.ndjson"),
+        Some(ObjectFormat::Json)
+    );
+    assert_eq!(
+        ObjectFormat::FromFilename("f1.yml"),
+        Some(ObjectFormat::Yaml)
+    );
+    assert_eq!(ObjectFormat::FromFilename("f1.txt"), None);
+    assert_eq!(ObjectFormat::FromFilename("f1"), None);
+}
+
+#[test]
+fn MultiDocumentYaml() {
+    let content = "\
+# functions of ns1
+---
+type: function
+tenant: t1
+namespace: ns1
+name: f1
+object: {}
+---
+---
+# an empty document is skipped
+---
+type: function
+tenant: t1
+namespace: ns1
+name: f2
+object: {}
+";
+    assert_eq!(Names(content), vec!["f1", "f2"]);
+
+    // json is yaml, also after a comment
+    assert_eq!(
+        Names(&format!("# f1\n{}\n---\n{}", F1, F2)),
+        vec!["f1", "f2"]
+    );
+}
+
+#[test]
+fn JsonListAndStream() {
+    assert_eq!(Names(&format!("[{}, {}]", F1, F2)), vec!["f1", "f2"]);
+    assert_eq!(Names(&format!("{}\n{}\n", F1, F2)), vec!["f1", "f2"]);
+    assert!(Names("[]").is_empty());
+    assert!(Names("").is_empty());
+
+    // a byte order mark is ignored
+    assert_eq!(Names(&format!("\u{feff}[{}]", F1)), vec!["f1"]);
+    assert_eq!(Names(&format!("\u{feff}{}", F1)), vec!["f1"]);
+    let yaml = "type: function\ntenant: t1\nnamespace: ns1\nname: f1\nobject: {}\n";
+    assert_eq!(Names(&format!("\u{feff}{}", yaml)), vec!["f1"]);
+}
+
+#[test]
+fn ParseErrorPosition() {
+    let content = format!("[{},\n  {{\"name\": }}]", F1);
+    let err = DataObject::<Value>::NewListFromString(&content, ObjectFormat::Json).unwrap_err();
+    assert!(format!("{:?}", err).contains("line 2"), "{:?}", err);
+
+    let content =
+        "type: function\ntenant: t1\nnamespace: ns1\nname: f1\nobject: {}\n---\nname: [f2\n";
+    let err = DataObject::<Value>::NewListFromString(content, ObjectFormat::Yaml).unwrap_err();
+    assert!(format!("{:?}", err).contains("line"), "{:?}", err);
+}
@@ -0,0 +1,151 @@
+// Copyright (c) 2023 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(non_snake_case)]
+
+use serde_json::{json, Value};
+
+use inferxlib::common::*;
+use inferxlib::data_obj::*;
+use inferxlib::obj_mgr::func_mgr::FuncObject;
+use inferxlib::obj_mgr::funcsnapshot_mgr::ContainerSnapshot;
+use inferxlib::obj_mgr::namespace_mgr::NamespaceObject;
+use inferxlib::obj_mgr::node_mgr::NodeSpec;
+use inferxlib::obj_mgr::pod_mgr::FuncPodObject;
+use inferxlib::obj_mgr::tenant_mgr::{TenantObject, SYSTEM_NAMESPACE, SYSTEM_TENANT};
+use inferxlib::validation::{ErrorList, ValidateScope};
+
+fn Object(kind: &str, tenant: &str, namespace: &str, object: Value) -> DataObject<Value> {
+    return serde_json::from_value(json!({
+        "type": kind,
+        "tenant": tenant,
+        "namespace": namespace,
+        "name": "o1",
+        "object": object
+    }))
+    .unwrap();
+}
+
+// DefaultObject is the default spec of T stored as a DataObject<Value>.
+fn DefaultObject<T: ObjectKind>() -> DataObject<Value> {
+    let object = serde_json::to_value(T::default()).unwrap();
+    return Object(T::KIND, "t1", "ns1", object);
+}
+
+#[test]
+fn KindsRoundTrip() {
+    let objs = [
+        DefaultObject::<TenantObject>(),
+        DefaultObject::<NamespaceObject>(),
+        DefaultObject::<NodeSpec>(),
+        DefaultObject::<FuncObject>(),
+        DefaultObject::<FuncPodObject>(),
+        DefaultObject::<ContainerSnapshot>(),
+    ];
+
+    // every registered kind is covered
+    let kinds: Vec<&str> = objs.iter().map(|o| o.objType.as_str()).collect();
+    let mut registered: Vec<&str> = OBJECT_KINDS.keys().cloned().collect();
+    registered.sort();
+    let mut sorted = kinds.clone();
+    sorted.sort();
+    assert_eq!(sorted, registered);
+
+    for obj in &objs {
+        let typed = obj.Typed().unwrap();
+        assert_eq!(typed.Kind(), obj.objType);
+        assert_eq!(LookupObjectKind(&obj.objType).unwrap().kind, obj.objType);
+
+        let back = typed.DataObject();
+        assert_eq!(
+            serde_json::to_value(&back).unwrap(),
+            serde_json::to_value(obj).unwrap(),
+            "{}",
+            obj.objType
+        );
+        assert_eq!(
+            serde_json::to_value(obj.Normalize().unwrap()).unwrap(),
+            serde_json::to_value(obj).unwrap()
+        );
+    }
+}
+
+#[test]
+fn UnknownKind() {
+    let err = match LookupObjectKind("functoin") {
+        Err(Error::CommonError(s)) => s,
+        r => panic!("unexpected {:?}", r.map(|k| k.kind)),
+    };
+    assert!(err.contains("functoin"), "{}", err);
+    for kind in OBJECT_KINDS.keys() {
+        assert!(err.contains(kind), "{} misses {}", err, kind);
+    }
+
+    let obj = Object("functoin", "t1", "ns1", json!({}));
+    assert!(obj.Typed().is_err());
+    assert!(obj.Normalize().is_err());
+    let errs = obj.Validate();
+    assert_eq!(errs.Len(), 1);
+    assert_eq!(errs.errs[0].path, "type");
+}
+
+#[test]
+fn NormalizeFillsDefaults() {
+    // the defaulted status of a function is filled in
+    let obj = Object(
+        "function",
+        "t1",
+        "ns1",
+        serde_json::to_value(FuncObject::default()).unwrap(),
+    );
+    let mut partial = obj.clone();
+    partial.object.as_object_mut().unwrap().remove("status");
+
+    let normalized = partial.Normalize().unwrap();
+    assert_eq!(normalized.object["status"], obj.object["status"]);
+}
+
+#[test]
+fn ScopeOrder() {
+    let scope = |kind: &str| LookupObjectKind(kind).unwrap().scope;
+    assert!(scope("tenant") < scope("namespace"));
+    assert!(scope("namespace") < scope("function"));
+    assert_eq!(scope("node_info"), ObjectScope::System);
+    assert_eq!(scope("pod"), ObjectScope::Namespace);
+
+    let Check = |scope: ObjectScope, tenant: &str, namespace: &str| -> Vec<String> {
+        let obj = Object("x", tenant, namespace, json!({}));
+        let mut errs = ErrorList::default();
+        ValidateScope(&obj, scope, &mut errs);
+        return errs.errs.into_iter().map(|e| e.path).collect();
+    };
+
+    // tenants live in the system namespace of the system tenant
+    assert!(Check(ObjectScope::System, SYSTEM_TENANT, SYSTEM_NAMESPACE).is_empty());
+    assert_eq!(
+        Check(ObjectScope::System, "t1", SYSTEM_NAMESPACE),
+        vec!["tenant"]
+    );
+    assert_eq!(
+        Check(ObjectScope::System, "t1", "ns1"),
+        vec!["tenant", "namespace"]
+    );
+
+    // namespaces in the system namespace of their tenant
+    assert!(Check(ObjectScope::Tenant, "t1", SYSTEM_NAMESPACE).is_empty());
+    assert_eq!(Check(ObjectScope::Tenant, "t1", "ns1"), vec!["namespace"]);
+
+    // functions in any namespace
+    assert!(Check(ObjectScope::Namespace, "t1", "ns1").is_empty());
+}
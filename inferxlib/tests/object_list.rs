@@ -0,0 +1,129 @@
+// Copyright (c) 2023 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(non_snake_case)]
+
+use serde_json::{json, Value};
+
+use inferxlib::data_obj::{DataObject, DataObjectMgr};
+use inferxlib::selector::{Labels, Selector};
+
+fn Object(tenant: &str, namespace: &str, name: &str) -> DataObject<Value> {
+    let obj: DataObject<Value> = serde_json::from_value(json!({
+        "type": "function",
+        "tenant": tenant,
+        "namespace": namespace,
+        "name": name,
+        "object": {"spec": {"image": "vllm:v1"}}
+    }))
+    .unwrap();
+    return obj;
+}
+
+fn Labeled(namespace: &str, name: &str, labels: &str) -> DataObject<Value> {
+    let mut obj = Object("t1", namespace, name);
+    obj.labels = Labels::New(labels).unwrap();
+    return obj;
+}
+
+fn Names(objs: Vec<DataObject<Value>>) -> Vec<String> {
+    return objs.into_iter().map(|o| o.name).collect();
+}
+
+fn LabeledMgr() -> DataObjectMgr<Value> {
+    let mgr: DataObjectMgr<Value> = DataObjectMgr::default();
+    mgr.Add(Labeled("ns1", "llama-7b", "tier=gold,gpus=1"))
+        .unwrap();
+    mgr.Add(Labeled("ns1", "llama-70b", "tier=silver,gpus=4"))
+        .unwrap();
+    mgr.Add(Labeled("ns1", "qwen-7b", "tier=bronze,gpus=2"))
+        .unwrap();
+    mgr.Add(Labeled("ns1", "qwen-vl", "")).unwrap();
+    mgr.Add(Labeled("ns2", "llama-8b", "tier=gold,gpus=8"))
+        .unwrap();
+    return mgr;
+}
+
+#[test]
+fn IsEmptyByScope() {
+    let mgr: DataObjectMgr<Value> = DataObjectMgr::default();
+    assert!(mgr.IsEmpty("", ""));
+
+    mgr.Add(Object("t2", "ns1", "f1")).unwrap();
+    assert!(!mgr.IsEmpty("", ""));
+    assert!(!mgr.IsEmpty("t2", ""));
+    assert!(!mgr.IsEmpty("t2", "ns1"));
+
+    // keys sorting before and after the object don't count
+    assert!(mgr.IsEmpty("t1", ""));
+    assert!(mgr.IsEmpty("t3", ""));
+    assert!(mgr.IsEmpty("t2", "ns0"));
+    assert!(mgr.IsEmpty("t2", "ns2"));
+
+    // a tenant name that is a prefix of another one is its own scope
+    assert!(mgr.IsEmpty("t", ""));
+}
+
+#[test]
+fn ListBySelector() {
+    let mgr = LabeledMgr();
+    let List = |namespace: &str, selector: &str| -> Vec<String> {
+        let selector = Selector::Parse(selector).unwrap();
+        return Names(
+            mgr.GetObjectsBySelector("t1", namespace, &selector)
+                .unwrap(),
+        );
+    };
+
+    assert_eq!(
+        List("ns1", "tier in (gold,silver)"),
+        vec!["llama-70b", "llama-7b"]
+    );
+    // notin also matches the objects without the label
+    assert_eq!(
+        List("ns1", "tier notin (gold,silver)"),
+        vec!["qwen-7b", "qwen-vl"]
+    );
+    assert_eq!(List("ns1", "gpus>1"), vec!["llama-70b", "qwen-7b"]);
+    assert_eq!(List("ns1", "gpus<2"), vec!["llama-7b"]);
+    assert_eq!(List("ns1", "tier=gold,gpus<2"), vec!["llama-7b"]);
+
+    // an empty selector matches everything in the scope
+    assert_eq!(List("ns1", "").len(), 4);
+    assert_eq!(List("", "tier=gold"), vec!["llama-7b", "llama-8b"]);
+    assert_eq!(List("ns2", "tier in (gold,silver)"), vec!["llama-8b"]);
+}
+
+#[test]
+fn ListByPrefixAndSelector() {
+    let mgr = LabeledMgr();
+    let List = |namespace: &str, prefix: &str, selector: &str| -> Vec<String> {
+        let selector = Selector::Parse(selector).unwrap();
+        return Names(
+            mgr.GetObjectsByPrefixAndSelector("t1", namespace, prefix, &selector)
+                .unwrap(),
+        );
+    };
+
+    assert_eq!(List("ns1", "llama", ""), vec!["llama-70b", "llama-7b"]);
+    assert_eq!(List("ns1", "llama", "gpus>2"), vec!["llama-70b"]);
+    assert_eq!(
+        List("ns1", "qwen", "tier notin (gold)"),
+        vec!["qwen-7b", "qwen-vl"]
+    );
+    assert_eq!(List("ns1", "qwen", "tier in (gold)"), Vec::<String>::new());
+    // the prefix doesn't reach into other namespaces
+    assert_eq!(List("ns2", "llama", "tier=gold"), vec!["llama-8b"]);
+    assert_eq!(List("ns1", "mistral", ""), Vec::<String>::new());
+}
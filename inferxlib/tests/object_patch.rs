@@ -0,0 +1,345 @@
+// Copyright (c) 2023 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(non_snake_case)]
+
+use serde_json::{json, Value};
+
+use inferxlib::common::*;
+use inferxlib::data_obj::{DataObject, DataObjectMgr, JsonPatch, MergePatch, PatchType};
+
+fn Merge(target: Value, patch: Value) -> Value {
+    let mut doc = target;
+    MergePatch(&mut doc, &patch);
+    return doc;
+}
+
+fn Json(target: Value, patch: Value) -> Result<Value> {
+    let mut doc = target;
+    JsonPatch(&mut doc, &patch)?;
+    return Ok(doc);
+}
+
+// RFC 7386 appendix A
+#[test]
+fn MergePatchExamples() {
+    let cases = [
+        (json!({"a": "b"}), json!({"a": "c"}), json!({"a": "c"})),
+        (
+            json!({"a": "b"}),
+            json!({"b": "c"}),
+            json!({"a": "b", "b": "c"}),
+        ),
+        (json!({"a": "b"}), json!({"a": null}), json!({})),
+        (
+            json!({"a": "b", "b": "c"}),
+            json!({"a": null}),
+            json!({"b": "c"}),
+        ),
+        (json!({"a": ["b"]}), json!({"a": "c"}), json!({"a": "c"})),
+        (json!({"a": "c"}), json!({"a": ["b"]}), json!({"a": ["b"]})),
+        (
+            json!({"a": {"b": "c"}}),
+            json!({"a": {"b": "d", "c": null}}),
+            json!({"a": {"b": "d"}}),
+        ),
+        (
+            json!({"a": [{"b": "c"}]}),
+            json!({"a": [1]}),
+            json!({"a": [1]}),
+        ),
+        (json!(["a", "b"]), json!(["c", "d"]), json!(["c", "d"])),
+        (json!({"a": "b"}), json!(["c"]), json!(["c"])),
+        (json!({"a": "foo"}), json!(null), json!(null)),
+        (json!({"a": "foo"}), json!("bar"), json!("bar")),
+        (
+            json!({"e": null}),
+            json!({"a": 1}),
+            json!({"e": null, "a": 1}),
+        ),
+        (
+            json!([1, 2]),
+            json!({"a": "b", "c": null}),
+            json!({"a": "b"}),
+        ),
+        (
+            json!({}),
+            json!({"a": {"bb": {"ccc": null}}}),
+            json!({"a": {"bb": {}}}),
+        ),
+    ];
+
+    for (target, patch, result) in cases {
+        assert_eq!(
+            Merge(target.clone(), patch.clone()),
+            result,
+            "{} {}",
+            target,
+            patch
+        );
+    }
+}
+
+// RFC 6902 appendix A
+#[test]
+fn JsonPatchExamples() {
+    let cases = [
+        // A.1 - A.4
+        (
+            json!({"foo": "bar"}),
+            json!([{"op": "add", "path": "/baz", "value": "qux"}]),
+            json!({"baz": "qux", "foo": "bar"}),
+        ),
+        (
+            json!({"foo": ["bar", "baz"]}),
+            json!([{"op": "add", "path": "/foo/1", "value": "qux"}]),
+            json!({"foo": ["bar", "qux", "baz"]}),
+        ),
+        (
+            json!({"baz": "qux", "foo": "bar"}),
+            json!([{"op": "remove", "path": "/baz"}]),
+            json!({"foo": "bar"}),
+        ),
+        (
+            json!({"foo": ["bar", "qux", "baz"]}),
+            json!([{"op": "remove", "path": "/foo/1"}]),
+            json!({"foo": ["bar", "baz"]}),
+        ),
+        // A.5 - A.7
+        (
+            json!({"baz": "qux", "foo": "bar"}),
+            json!([{"op": "replace", "path": "/baz", "value": "boo"}]),
+            json!({"baz": "boo", "foo": "bar"}),
+        ),
+        (
+            json!({"foo": {"bar": "baz", "waldo": "fred"}, "qux": {"corge": "grault"}}),
+            json!([{"op": "move", "from": "/foo/waldo", "path": "/qux/thud"}]),
+            json!({"foo": {"bar": "baz"}, "qux": {"corge": "grault", "thud": "fred"}}),
+        ),
+        (
+            json!({"foo": ["all", "grass", "cows", "eat"]}),
+            json!([{"op": "move", "from": "/foo/1", "path": "/foo/3"}]),
+            json!({"foo": ["all", "cows", "eat", "grass"]}),
+        ),
+        // A.8
+        (
+            json!({"baz": "qux", "foo": ["a", 2, "c"]}),
+            json!([
+                {"op": "test", "path": "/baz", "value": "qux"},
+                {"op": "test", "path": "/foo/1", "value": 2}
+            ]),
+            json!({"baz": "qux", "foo": ["a", 2, "c"]}),
+        ),
+        // A.10
+        (
+            json!({"foo": "bar"}),
+            json!([{"op": "add", "path": "/child", "value": {"grandchild": {}}}]),
+            json!({"foo": "bar", "child": {"grandchild": {}}}),
+        ),
+        // A.14
+        (
+            json!({"/": 9, "~1": 10}),
+            json!([{"op": "test", "path": "/~01", "value": 10}]),
+            json!({"/": 9, "~1": 10}),
+        ),
+        // A.16
+        (
+            json!({"foo": ["bar"]}),
+            json!([{"op": "add", "path": "/foo/-", "value": ["abc", "def"]}]),
+            json!({"foo": ["bar", ["abc", "def"]]}),
+        ),
+    ];
+
+    for (target, patch, result) in cases {
+        assert_eq!(
+            Json(target.clone(), patch.clone()).unwrap(),
+            result,
+            "{} {}",
+            target,
+            patch
+        );
+    }
+}
+
+#[test]
+fn JsonPatchErrors() {
+    let cases = [
+        // A.9, A.15: failed tests
+        (
+            json!({"baz": "qux"}),
+            json!([{"op": "test", "path": "/baz", "value": "bar"}]),
+        ),
+        (
+            json!({"/": 9, "~1": 10}),
+            json!([{"op": "test", "path": "/~01", "value": "10"}]),
+        ),
+        // A.12: adding to a nonexistent target
+        (
+            json!({"foo": "bar"}),
+            json!([{"op": "add", "path": "/baz/bat", "value": "qux"}]),
+        ),
+        // an array index past the end, except for add, or with a leading zero
+        (
+            json!({"foo": ["a"]}),
+            json!([{"op": "add", "path": "/foo/2", "value": "b"}]),
+        ),
+        (
+            json!({"foo": ["a"]}),
+            json!([{"op": "remove", "path": "/foo/1"}]),
+        ),
+        (
+            json!({"foo": ["a"]}),
+            json!([{"op": "remove", "path": "/foo/-"}]),
+        ),
+        (
+            json!({"foo": ["a", "b"]}),
+            json!([{"op": "add", "path": "/foo/01", "value": "c"}]),
+        ),
+        (
+            json!({"foo": ["a"]}),
+            json!([{"op": "remove", "path": "/foo/x"}]),
+        ),
+        // a member moved into itself
+        (
+            json!({"foo": {"bar": 1}}),
+            json!([{"op": "move", "from": "/foo", "path": "/foo/bar"}]),
+        ),
+        (
+            json!({"foo": 1}),
+            json!([{"op": "replace", "path": "/bar", "value": 2}]),
+        ),
+        (json!({"foo": 1}), json!([{"op": "frob", "path": "/foo"}])),
+        (json!({"foo": 1}), json!({"op": "remove", "path": "/foo"})),
+    ];
+
+    for (target, patch) in cases {
+        assert!(
+            Json(target.clone(), patch.clone()).is_err(),
+            "{} {}",
+            target,
+            patch
+        );
+    }
+
+    // the operations are applied all or nothing
+    let mut doc = json!({"foo": ["a"]});
+    let patch = json!([
+        {"op": "add", "path": "/foo/-", "value": "b"},
+        {"op": "remove", "path": "/bar"}
+    ]);
+    assert!(JsonPatch(&mut doc, &patch).is_err());
+    assert_eq!(doc, json!({"foo": ["a"]}));
+}
+
+#[test]
+fn ArrayIndexEdges() {
+    // add at the array length appends, as "-" does
+    let doc = Json(
+        json!({"foo": ["a"]}),
+        json!([{"op": "add", "path": "/foo/1", "value": "b"}]),
+    )
+    .unwrap();
+    assert_eq!(doc, json!({"foo": ["a", "b"]}));
+
+    let doc = Json(
+        json!({"foo": []}),
+        json!([{"op": "add", "path": "/foo/0", "value": "a"}]),
+    )
+    .unwrap();
+    assert_eq!(doc, json!({"foo": ["a"]}));
+
+    // copy to an array index inserts, the last element can be removed
+    let doc = Json(
+        json!({"foo": ["a", "b"]}),
+        json!([
+            {"op": "copy", "from": "/foo/1", "path": "/foo/0"},
+            {"op": "remove", "path": "/foo/2"}
+        ]),
+    )
+    .unwrap();
+    assert_eq!(doc, json!({"foo": ["b", "a"]}));
+}
+
+fn Function(revision: i64) -> DataObject<Value> {
+    let obj: DataObject<Value> = serde_json::from_value(json!({
+        "type": "function",
+        "tenant": "t1",
+        "namespace": "ns1",
+        "name": "f1",
+        "object": {"spec": {"image": "vllm:v1", "resources": {"GPU": {"Count": 1, "vRam": 8000}}}}
+    }))
+    .unwrap();
+    return obj.CopyWithRev(revision, revision);
+}
+
+#[test]
+fn PatchObject() {
+    let stored = Function(5);
+    let patched = stored
+        .Patch(
+            PatchType::Merge,
+            &json!({"object": {"spec": {"resources": {"GPU": {"vRam": 16000}}}}}),
+        )
+        .unwrap();
+    assert_eq!(
+        patched.object["spec"]["resources"]["GPU"]["vRam"],
+        json!(16000)
+    );
+    assert_eq!(patched.object["spec"]["image"], json!("vllm:v1"));
+    assert_eq!(patched.revision, 5);
+
+    let patched = stored
+        .Patch(
+            PatchType::Json,
+            &json!([{"op": "replace", "path": "/object/spec/image", "value": "vllm:v2"}]),
+        )
+        .unwrap();
+    assert_eq!(patched.object["spec"]["image"], json!("vllm:v2"));
+
+    // the identity can't be patched, nor do the revisions change
+    assert!(stored
+        .Patch(PatchType::Merge, &json!({"name": "f2"}))
+        .is_err());
+    let patched = stored
+        .Patch(PatchType::Merge, &json!({"revision": 9}))
+        .unwrap();
+    assert_eq!(patched.revision, 5);
+}
+
+#[test]
+fn StalePatchConflicts() {
+    let mgr: DataObjectMgr<Value> = DataObjectMgr::default();
+    mgr.Add(Function(1)).unwrap();
+
+    // read, patch and write back guarded by the revision the patch was applied to
+    let stored = mgr.Get("t1", "ns1", "f1").unwrap();
+    let patched = stored
+        .Patch(PatchType::Merge, &json!({"labels": {"tier": "gold"}}))
+        .unwrap();
+
+    // someone else updates the function in between
+    mgr.CompareAndUpdate(Function(2), 1).unwrap();
+
+    let expectRevision = stored.ExpectRevision().unwrap();
+    assert!(matches!(
+        mgr.CompareAndUpdate(patched, expectRevision),
+        Err(Error::Conflict(_))
+    ));
+    assert!(mgr
+        .Get("t1", "ns1", "f1")
+        .unwrap()
+        .labels
+        .String()
+        .is_empty());
+}
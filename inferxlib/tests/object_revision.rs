@@ -0,0 +1,122 @@
+// Copyright (c) 2023 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(non_snake_case)]
+
+use serde_json::{json, Value};
+
+use inferxlib::common::*;
+use inferxlib::data_obj::{DataObject, DataObjectMgr};
+
+fn Object(name: &str, revision: i64) -> DataObject<Value> {
+    let obj: DataObject<Value> = serde_json::from_value(json!({
+        "type": "function",
+        "tenant": "t1",
+        "namespace": "ns1",
+        "name": name,
+        "object": {"spec": {"image": "vllm:v1"}}
+    }))
+    .unwrap();
+    return obj.CopyWithRev(revision, revision);
+}
+
+#[test]
+fn RevisionOnTheWire() {
+    // a stored object is read with its revision
+    let stored = Object("f1", 7);
+    let v = serde_json::to_value(&stored).unwrap();
+    assert_eq!(v["revision"], json!(7));
+    let back: DataObject<Value> = serde_json::from_value(v).unwrap();
+    assert_eq!(back.revision, 7);
+    assert_eq!(back.ExpectRevision().unwrap(), 7);
+
+    // and written without
+    let v = serde_json::to_value(stored.CopyWithRev(0, 0)).unwrap();
+    assert!(v.get("revision").is_none());
+    assert!(v.get("channelRev").is_none());
+
+    // a stored object without revision can't guard an update
+    assert!(Object("f1", 0).ExpectRevision().is_err());
+}
+
+#[test]
+fn StaleUpdateConflicts() {
+    let mgr: DataObjectMgr<Value> = DataObjectMgr::default();
+    mgr.Add(Object("f1", 1)).unwrap();
+
+    // two clients read revision 1, the first write moves the object to 2
+    mgr.CompareAndUpdate(Object("f1", 2), 1).unwrap();
+    assert!(matches!(
+        mgr.CompareAndUpdate(Object("f1", 3), 1),
+        Err(Error::Conflict(_))
+    ));
+    assert_eq!(mgr.Get("t1", "ns1", "f1").unwrap().revision, 2);
+
+    // an update based on an older copy is rejected, one without revision isn't checked
+    assert!(matches!(
+        mgr.Update(Object("f1", 1)),
+        Err(Error::Conflict(_))
+    ));
+    mgr.Update(Object("f1", 0)).unwrap();
+}
+
+#[test]
+fn StaleWritersConflict() {
+    let mgr: DataObjectMgr<Value> = DataObjectMgr::default();
+    mgr.Add(Object("f1", 5)).unwrap();
+
+    // two clients read the object at revision 5
+    let mut first = mgr.Get("t1", "ns1", "f1").unwrap();
+    let mut second = mgr.Get("t1", "ns1", "f1").unwrap();
+    first.object["spec"]["image"] = json!("vllm:v2");
+    second.object["spec"]["image"] = json!("vllm:v3");
+
+    // the first write moves the object to the next revision, the second is stale
+    mgr.Update(first).unwrap();
+    assert!(matches!(
+        mgr.Update(second.clone()),
+        Err(Error::Conflict(_))
+    ));
+    let stored = mgr.Get("t1", "ns1", "f1").unwrap();
+    assert_eq!(stored.revision, 6);
+    assert_eq!(stored.object["spec"]["image"], json!("vllm:v2"));
+
+    // a newer revision than the stored one is no better than an older one
+    second.revision = 7;
+    assert!(matches!(
+        mgr.Update(second.clone()),
+        Err(Error::Conflict(_))
+    ));
+
+    // once read again, the second client's write goes through
+    second.revision = 6;
+    mgr.Update(second).unwrap();
+    let stored = mgr.Get("t1", "ns1", "f1").unwrap();
+    assert_eq!(stored.revision, 7);
+    assert_eq!(stored.object["spec"]["image"], json!("vllm:v3"));
+}
+
+#[test]
+fn StaleRemoveConflicts() {
+    let mgr: DataObjectMgr<Value> = DataObjectMgr::default();
+    mgr.Add(Object("f1", 3)).unwrap();
+
+    assert!(matches!(
+        mgr.CompareAndRemove(Object("f1", 0), 2),
+        Err(Error::Conflict(_))
+    ));
+    assert!(mgr.Contains("t1", "ns1", "f1"));
+    mgr.CompareAndRemove(Object("f1", 0), 3).unwrap();
+    assert!(!mgr.Contains("t1", "ns1", "f1"));
+}
@@ -0,0 +1,148 @@
+// Copyright (c) 2023 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(non_snake_case)]
+
+use serde_json::{json, Value};
+
+use inferxlib::data_obj::{DataObject, DataObjectMgr, DeltaEventStream, EventType};
+use inferxlib::selector::Selector;
+
+fn Object(namespace: &str, name: &str, tier: &str, image: &str) -> DataObject<Value> {
+    return serde_json::from_value(json!({
+        "type": "function",
+        "tenant": "t1",
+        "namespace": namespace,
+        "name": name,
+        "labels": {"tier": tier},
+        "object": {"spec": {"image": image}}
+    }))
+    .unwrap();
+}
+
+// Events drains the events received so far as (type, name, old image, new image)
+fn Events(stream: &mut DeltaEventStream) -> Vec<(EventType, String, String, String)> {
+    let mut events = Vec::new();
+    while let Ok(e) = stream.try_recv() {
+        let image =
+            |o: &DataObject<Value>| o.object["spec"]["image"].as_str().unwrap_or("").to_owned();
+        events.push((
+            e.type_,
+            e.obj.name.clone(),
+            e.oldObj.as_ref().map(image).unwrap_or_default(),
+            image(&e.obj),
+        ));
+    }
+    return events;
+}
+
+fn Event(
+    type_: EventType,
+    name: &str,
+    old: &str,
+    new: &str,
+) -> (EventType, String, String, String) {
+    return (type_, name.to_owned(), old.to_owned(), new.to_owned());
+}
+
+#[test]
+fn WatchDelivery() {
+    let mgr: DataObjectMgr<Value> = DataObjectMgr::default();
+    mgr.Add(Object("ns1", "f1", "gold", "v1")).unwrap();
+    mgr.Add(Object("ns2", "f2", "gold", "v1")).unwrap();
+
+    // the existing objects of the namespace come first, then InitDone
+    let mut stream = mgr.Watch("t1", "ns1", Selector::default());
+    assert_eq!(
+        Events(&mut stream),
+        vec![
+            Event(EventType::Added, "f1", "", "v1"),
+            Event(EventType::InitDone, "", "", ""),
+        ]
+    );
+
+    mgr.Add(Object("ns1", "f3", "gold", "v1")).unwrap();
+    mgr.Update(Object("ns1", "f1", "gold", "v2")).unwrap();
+    mgr.Remove(Object("ns1", "f3", "gold", "v1")).unwrap();
+    // other namespaces aren't watched
+    mgr.Update(Object("ns2", "f2", "gold", "v2")).unwrap();
+    assert_eq!(
+        Events(&mut stream),
+        vec![
+            Event(EventType::Added, "f3", "", "v1"),
+            Event(EventType::Modified, "f1", "v1", "v2"),
+            Event(EventType::Deleted, "f3", "", "v1"),
+        ]
+    );
+
+    // an empty tenant watches everything
+    let mut all = mgr.Watch("", "", Selector::default());
+    assert_eq!(Events(&mut all).len(), 3);
+}
+
+#[test]
+fn WatchLabelTransitions() {
+    let mgr: DataObjectMgr<Value> = DataObjectMgr::default();
+    mgr.Add(Object("ns1", "f1", "gold", "v1")).unwrap();
+    mgr.Add(Object("ns1", "f2", "silver", "v1")).unwrap();
+
+    let mut stream = mgr.Watch("t1", "ns1", Selector::Parse("tier=gold").unwrap());
+    assert_eq!(
+        Events(&mut stream),
+        vec![
+            Event(EventType::Added, "f1", "", "v1"),
+            Event(EventType::InitDone, "", "", ""),
+        ]
+    );
+
+    // f1 stops matching, the watcher sees it deleted as it was last seen
+    mgr.Update(Object("ns1", "f1", "silver", "v2")).unwrap();
+    // f2 starts matching, the watcher sees it added
+    mgr.Update(Object("ns1", "f2", "gold", "v2")).unwrap();
+    // changes of objects which never match aren't sent
+    mgr.Update(Object("ns1", "f1", "bronze", "v3")).unwrap();
+    mgr.Remove(Object("ns1", "f1", "bronze", "v3")).unwrap();
+    mgr.Remove(Object("ns1", "f2", "gold", "v2")).unwrap();
+    assert_eq!(
+        Events(&mut stream),
+        vec![
+            Event(EventType::Deleted, "f1", "", "v1"),
+            Event(EventType::Added, "f2", "", "v2"),
+            Event(EventType::Deleted, "f2", "", "v2"),
+        ]
+    );
+}
+
+#[test]
+fn DeadWatcherPruned() {
+    let mgr: DataObjectMgr<Value> = DataObjectMgr::default();
+    let stream = mgr.Watch("t1", "ns1", Selector::default());
+    let mut other = mgr.Watch("t1", "ns2", Selector::default());
+    assert_eq!(mgr.lock().unwrap().watchers.len(), 2);
+
+    // a watcher is dropped on the first event it would get after its stream is gone
+    drop(stream);
+    mgr.Add(Object("ns2", "f1", "gold", "v1")).unwrap();
+    assert_eq!(mgr.lock().unwrap().watchers.len(), 2);
+    mgr.Add(Object("ns1", "f1", "gold", "v1")).unwrap();
+    assert_eq!(mgr.lock().unwrap().watchers.len(), 1);
+
+    assert_eq!(
+        Events(&mut other),
+        vec![
+            Event(EventType::InitDone, "", "", ""),
+            Event(EventType::Added, "f1", "", "v1"),
+        ]
+    );
+}
@@ -0,0 +1,326 @@
+// Copyright (c) 2023 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(non_snake_case)]
+
+mod common;
+
+use std::collections::BTreeMap;
+
+use common::*;
+use inferxlib::common::Error;
+use inferxlib::obj_mgr::func_mgr::FuncSpec;
+use inferxlib::placement::PlacementPolicy;
+use inferxlib::resource::*;
+
+const CASES: u64 = 200;
+
+// Rng is a xorshift generator, seeded per case so that a failure can be replayed.
+struct Rng(u64);
+
+impl Rng {
+    fn New(seed: u64) -> Self {
+        return Self(seed.wrapping_mul(0x9E3779B97F4A7C15) | 1);
+    }
+
+    fn Next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        return self.0;
+    }
+
+    // Range returns a number in [lo, hi]
+    fn Range(&mut self, lo: u64, hi: u64) -> u64 {
+        return lo + self.Next() % (hi - lo + 1);
+    }
+}
+
+// GPUs with different vRam and context counts, with a random placement policy
+fn RandomNode(rng: &mut Rng) -> NodeResources {
+    let free: Vec<u32> = (0..rng.Range(1, 8))
+        .map(|_| rng.Range(4, 80) as u32)
+        .collect();
+    let mut gpus = GPUs(&free, 1);
+    for a in gpus.map.values_mut() {
+        a.contextCnt = rng.Range(1, 4);
+    }
+    let mut node = Node(gpus, 4);
+    node.memory = rng.Range(1000, 100000);
+    node.placement = [
+        PlacementPolicy::Spread,
+        PlacementPolicy::BinPack,
+        PlacementPolicy::Topology,
+    ][rng.Range(0, 2) as usize];
+    return node;
+}
+
+fn RandomReq(rng: &mut Rng) -> Resources {
+    return Resources {
+        cpu: rng.Range(0, 8000),
+        memory: rng.Range(0, 30000),
+        gpu: GPUResource {
+            gpuCount: rng.Range(0, 4),
+            vRam: rng.Range(1, 40000),
+            ..Default::default()
+        },
+    };
+}
+
+// Snapshot is the memory and (gpu, slotCnt, contextCnt) of a node
+fn Snapshot(node: &NodeResources) -> (u64, Vec<(i32, u32, u64)>) {
+    let gpus = node
+        .gpus
+        .map
+        .iter()
+        .map(|(id, a)| (*id, a.slotCnt, a.contextCnt))
+        .collect();
+    return (node.memory, gpus);
+}
+
+// Sum adds the allocations to the available resources of a node per GPU.
+fn Sum(node: &NodeResources, allocs: &[NodeResources]) -> (u64, Vec<(i32, u32, u64)>) {
+    let mut memory = node.memory;
+    let mut gpus: BTreeMap<i32, (u32, u64)> = node
+        .gpus
+        .map
+        .iter()
+        .map(|(id, a)| (*id, (a.slotCnt, a.contextCnt)))
+        .collect();
+    for alloc in allocs {
+        memory += alloc.memory;
+        for (id, a) in &alloc.gpus.map {
+            let e = gpus.get_mut(id).unwrap();
+            e.0 += a.slotCnt;
+            e.1 += a.contextCnt;
+        }
+    }
+    return (
+        memory,
+        gpus.into_iter().map(|(id, (s, c))| (id, s, c)).collect(),
+    );
+}
+
+#[test]
+fn AllocFreeConservesResources() {
+    for seed in 0..CASES {
+        let mut rng = Rng::New(seed);
+        let mut node = RandomNode(&mut rng);
+        let total = Snapshot(&node);
+
+        let mut allocs: Vec<NodeResources> = Vec::new();
+        for _ in 0..rng.Range(1, 40) {
+            if allocs.is_empty() || rng.Range(0, 2) > 0 {
+                let req = RandomReq(&mut rng);
+                let before = Snapshot(&node);
+                match node.Alloc(&req) {
+                    Ok(alloc) => {
+                        assert_eq!(alloc.memory, req.memory, "seed {}", seed);
+                        assert_eq!(
+                            alloc.gpus.map.len() as u64,
+                            req.gpu.gpuCount,
+                            "seed {}",
+                            seed
+                        );
+                        allocs.push(alloc);
+                    }
+                    // a failed allocation leaves the node as it was
+                    Err(_) => assert_eq!(Snapshot(&node), before, "seed {}", seed),
+                }
+            } else {
+                let idx = rng.Range(0, allocs.len() as u64 - 1) as usize;
+                let alloc = allocs.swap_remove(idx);
+                node.Add(&alloc).unwrap();
+            }
+            assert_eq!(Sum(&node, &allocs), total, "seed {}", seed);
+        }
+
+        for alloc in allocs.drain(..) {
+            node.Add(&alloc).unwrap();
+        }
+        assert_eq!(Snapshot(&node), total, "seed {}", seed);
+    }
+}
+
+#[test]
+fn SubAddRoundTrip() {
+    for seed in 0..CASES {
+        let mut rng = Rng::New(seed);
+        let mut node = RandomNode(&mut rng);
+        let total = Snapshot(&node);
+
+        // a random part of the node, possibly more than it has
+        let mut part = node.GPUResource();
+        part.memory = rng.Range(0, node.memory * 2);
+        for a in part.gpus.map.values_mut() {
+            a.slotCnt = rng.Range(0, a.slotCnt as u64 * 3 / 2) as u32;
+            a.contextCnt = rng.Range(0, a.contextCnt + 1);
+        }
+        let fits = part.memory <= node.memory
+            && part.gpus.map.iter().all(|(id, a)| {
+                a.slotCnt <= node.gpus.map[id].slotCnt
+                    && a.contextCnt <= node.gpus.map[id].contextCnt
+            });
+
+        match node.Sub(&part) {
+            Ok(()) => {
+                assert!(fits, "seed {}", seed);
+                node.Add(&part).unwrap();
+            }
+            Err(Error::ResourceUnderflow { have, want, .. }) => {
+                assert!(!fits, "seed {}", seed);
+                assert!(have < want, "seed {}", seed);
+            }
+            Err(e) => panic!("seed {}: {:?}", seed, e),
+        }
+        assert_eq!(Snapshot(&node), total, "seed {}", seed);
+    }
+}
+
+#[test]
+fn ResourcesSubUnderflow() {
+    for seed in 0..CASES {
+        let mut rng = Rng::New(seed);
+        let a = RandomReq(&mut rng);
+        let mut b = RandomReq(&mut rng);
+        b.gpu.gpuCount = a.gpu.gpuCount;
+
+        let mut c = a.clone();
+        match c.Sub(&b) {
+            Ok(()) => {
+                assert_eq!(c.cpu + b.cpu, a.cpu, "seed {}", seed);
+                assert_eq!(c.memory + b.memory, a.memory, "seed {}", seed);
+            }
+            Err(Error::ResourceUnderflow {
+                resource,
+                have,
+                want,
+            }) => {
+                assert!(
+                    have < want,
+                    "seed {}: {} {} < {}",
+                    seed,
+                    resource,
+                    have,
+                    want
+                );
+                // unchanged
+                assert_eq!((c.cpu, c.memory, c.gpu.vRam), (a.cpu, a.memory, a.gpu.vRam));
+            }
+            Err(e) => panic!("seed {}: {:?}", seed, e),
+        }
+    }
+}
+
+#[test]
+fn InvalidArithmetic() {
+    let mut rng = Rng::New(1);
+    let mut node = RandomNode(&mut rng);
+    let total = Snapshot(&node);
+
+    // a GPU the node doesn't have
+    let mut other = node.GPUResource();
+    other.memory = 0;
+    other.gpus.map.clear();
+    other.gpus.map.insert(
+        99,
+        GPUAlloc {
+            contextCnt: 1,
+            slotCnt: 1,
+        },
+    );
+    assert!(node.Sub(&other).is_err());
+
+    // GPUs of another slot size
+    let mut other = node.GPUResource();
+    other.gpus.slotSize = SLOT_SIZE * 2;
+    assert!(node.Sub(&other).is_err());
+    assert!(node.Add(&other).is_err());
+
+    // a GPU count which doesn't match
+    let mut gpu = GPUResource {
+        gpuCount: 2,
+        vRam: 100,
+        ..Default::default()
+    };
+    let other = GPUResource {
+        gpuCount: 1,
+        vRam: 10,
+        ..Default::default()
+    };
+    assert!(gpu.Sub(&other).is_err());
+    assert_eq!(gpu.vRam, 100);
+
+    assert_eq!(Snapshot(&node), total);
+}
+
+#[test]
+fn ResumeResourceUnderflow() {
+    // a function asking for less memory than a hibernated container keeps
+    let mut spec = FuncSpec::default();
+    spec.resources.memory = FuncSpec::HIBERNATE_CONTAINER_MEM_OVERHEAD - 1;
+    match spec.ResumeResource(false) {
+        Err(Error::ResourceUnderflow {
+            resource,
+            have,
+            want,
+        }) => {
+            assert_eq!(resource, "memory");
+            assert_eq!(have, FuncSpec::HIBERNATE_CONTAINER_MEM_OVERHEAD - 1);
+            assert_eq!(want, FuncSpec::HIBERNATE_CONTAINER_MEM_OVERHEAD);
+        }
+        r => panic!("{:?}", r),
+    }
+
+    spec.resources.memory = 2000;
+    assert_eq!(spec.ResumeResource(false).unwrap().memory, 1500);
+}
+
+#[test]
+fn InvalidRequests() {
+    // a node of one 80 GB GPU
+    let mut node = Node(GPUs(&[80], 4), 4);
+    let total = Snapshot(&node);
+
+    assert_eq!(node.gpus.ReqSlotCnt(1).unwrap(), 1);
+    assert_eq!(node.gpus.ReqSlotCnt(1024).unwrap(), 1);
+    assert_eq!(node.gpus.ReqSlotCnt(1025).unwrap(), 2);
+    assert_eq!(node.gpus.ReqSlotCnt(80 * 1024).unwrap(), 80);
+
+    // vRam whose bytes overflow u64, or whose slot count overflows u32
+    for vRam in [u64::MAX / 1024, (1 << 32) * 1024 + 1, (1 << 44) + 1] {
+        let mut req = Req(1, 0);
+        req.gpu.vRam = vRam;
+        assert!(node.gpus.ReqSlotCnt(vRam).is_err(), "vRam {}", vRam);
+        assert!(!node.CanAlloc(&req), "vRam {}", vRam);
+        assert_eq!(node.TopologyScore(&req), None);
+        assert!(node.Alloc(&req).is_err(), "vRam {}", vRam);
+    }
+
+    // a GPU map without slot size
+    let mut empty = GPUResourceMap::default();
+    let usage = GPUResource {
+        gpuCount: 1,
+        vRam: 1000,
+        ..Default::default()
+    };
+    assert!(empty.ReqSlotCnt(1000).is_err());
+    assert!(!empty.CanAlloc(&usage));
+    assert!(empty.Alloc(&usage).is_err());
+    let none = GPUResource::default();
+    assert!(empty.CanAlloc(&none));
+    assert!(empty.Alloc(&none).unwrap().map.is_empty());
+
+    assert_eq!(Snapshot(&node), total);
+}
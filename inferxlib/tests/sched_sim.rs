@@ -0,0 +1,189 @@
+// Copyright (c) 2023 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(non_snake_case)]
+
+use std::path::Path;
+
+use inferxlib::obj_mgr::pod_mgr::PodState;
+use inferxlib::sched_sim::*;
+
+// 2 nodes of 2 80 GB GPUs and a 40 GB, 1 GPU function with a gpu memory standby
+const CONFIG: &str = r#"
+nodes:
+  - name: node
+    count: 2
+    CPU: 64000
+    Mem: 256000
+    GPUCount: 2
+    vRam: 81920
+    MaxContextPerGPU: 2
+    Placement: BinPack
+functions:
+  - name: small
+    replicas: 2
+    resources:
+      CPU: 4000
+      Mem: 16000
+      GPU:
+        Count: 1
+        vRam: 40000
+    standby:
+      gpu: Mem
+"#;
+
+fn Run(config: &str) -> SimReport {
+    let config: SimConfig = serde_yaml::from_str(config).unwrap();
+    return SchedSim::Run(&config, Path::new(".")).unwrap();
+}
+
+fn Node<'a>(report: &'a SimReport, name: &str) -> &'a SimNodeReport {
+    return report.nodes.iter().find(|n| n.name == name).unwrap();
+}
+
+#[test]
+fn DefaultEvents() {
+    let report = Run(CONFIG);
+    assert!(report.failures.is_empty(), "{:?}", report.failures);
+
+    // snapshot, restore 2 replicas and resume 1 of them, all on the snapshot node
+    assert_eq!(report.snapshots["small"].len(), 1);
+    let states: Vec<PodState> = report.pods.iter().map(|p| p.state).collect();
+    assert_eq!(states, vec![PodState::Ready, PodState::Standby]);
+    assert_eq!(report.pods[0].gpus, vec![0]);
+
+    let node = Node(&report, "node-1");
+    assert_eq!(node.freeGpus, 1);
+    assert_eq!(node.freeVRam, 2 * 81920 - 40000);
+    assert_eq!(node.maxFreeVRam, 81920);
+    assert_eq!(node.snapshots, 1);
+    // the memory of both standby pods stays allocated
+    assert_eq!(node.freeMemory, 256000 - 2 * 16000);
+    assert_eq!(Node(&report, "node-2").freeGpus, 2);
+}
+
+#[test]
+fn HibernateFreesGPUs() {
+    let config = format!(
+        "{}{}",
+        CONFIG,
+        r#"
+events:
+  - action: snapshot
+  - action: restore
+  - action: resume
+    count: 2
+  - action: hibernate
+    count: 1
+"#
+    );
+    let report = Run(&config);
+    assert!(report.failures.is_empty(), "{:?}", report.failures);
+
+    // bin-pack put both pods on GPU 0, one of them is left
+    let node = Node(&report, "node-1");
+    assert_eq!(node.readyPods, 1);
+    assert_eq!(node.standbyPods, 1);
+    assert_eq!(node.freeGpus, 1);
+    assert_eq!(node.freeVRam, 2 * 81920 - 40000);
+    let fragmentation = (81920 - 40000) as f64 / node.freeVRam as f64;
+    assert!((node.fragmentation - fragmentation).abs() < 1e-9);
+}
+
+#[test]
+fn ReportFailures() {
+    let config = format!(
+        "{}{}",
+        CONFIG,
+        r#"
+  - name: large
+    resources:
+      CPU: 4000
+      Mem: 16000
+      GPU:
+        Count: 4
+        vRam: 1000
+events:
+  - action: restore
+    functions: [small]
+  - action: create
+  - action: resume
+    functions: [large]
+"#
+    );
+    let report = Run(&config);
+
+    let failures: Vec<(usize, SimAction, &str)> = report
+        .failures
+        .iter()
+        .map(|f| (f.step, f.action, f.funcname.as_str()))
+        .collect();
+    assert_eq!(
+        failures,
+        vec![
+            (1, SimAction::Restore, "small"),
+            (2, SimAction::Create, "large"),
+            (3, SimAction::Resume, "large"),
+        ]
+    );
+    assert!(report.failures[0].reason.contains("no snapshot"));
+    assert!(report.failures[1].reason.contains("no node to create"));
+
+    // the small function was still created
+    assert_eq!(report.pods.len(), 1);
+    assert_eq!(report.pods[0].funcname, "small");
+}
+
+#[test]
+fn UnknownFunction() {
+    let config: SimConfig = serde_yaml::from_str(&format!(
+        "{}{}",
+        CONFIG, "events:\n  - action: snapshot\n    functions: [missing]\n"
+    ))
+    .unwrap();
+    assert!(SchedSim::Run(&config, Path::new(".")).is_err());
+}
+
+#[test]
+fn InvalidTopology() {
+    let filename = std::env::temp_dir().join("sched_sim_invalid_topology.yaml");
+    let config = CONFIG.replace(
+        "    Placement: BinPack\n",
+        "    Placement: BinPack\n    Topology:\n      Links: [[X, NV1], []]\n",
+    );
+    std::fs::write(&filename, config).unwrap();
+    let res = SimConfig::Load(filename.to_str().unwrap());
+    std::fs::remove_file(&filename).unwrap();
+    assert!(res.is_err());
+}
+
+#[test]
+fn ExampleFixture() {
+    let filename = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../config/simulate/mixed_cluster.yaml"
+    );
+    let config = SimConfig::Load(filename).unwrap();
+    let dir = Path::new(filename).parent().unwrap();
+    let report = SchedSim::Run(&config, dir).unwrap();
+
+    // the multi GPU functions get NVLink pairs
+    for pod in report.pods.iter().filter(|p| p.gpus.len() == 2) {
+        assert_eq!(pod.topologyScore, Some(104), "{:?}", pod);
+    }
+    assert!(report
+        .failures
+        .iter()
+        .all(|f| f.funcname == "large-70b" && f.action == SimAction::Resume));
+}
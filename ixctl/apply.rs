@@ -0,0 +1,296 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use serde_json::Value;
+
+use inferxlib::common::*;
+use inferxlib::data_obj::{DataObject, LookupObjectKind};
+
+use crate::command::GlobalConfig;
+use crate::object_client::ObjectClient;
+use crate::validate::{ObjectFiles, ValidateFile};
+
+#[derive(Debug)]
+pub struct ApplyCmd {
+    pub filenames: Vec<String>,
+    pub dryRun: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApplyResult {
+    Created,
+    Updated,
+    Unchanged,
+}
+
+impl ApplyResult {
+    pub fn Name(&self) -> &'static str {
+        match self {
+            Self::Created => "created",
+            Self::Updated => "updated",
+            Self::Unchanged => "unchanged",
+        }
+    }
+}
+
+impl ApplyCmd {
+    pub fn Init(cmd_matches: &ArgMatches) -> Result<Self> {
+        Ok(Self {
+            filenames: cmd_matches
+                .values_of("filename")
+                .unwrap()
+                .map(|s| s.to_string())
+                .collect(),
+            dryRun: cmd_matches.is_present("dry-run"),
+        })
+    }
+
+    pub fn SubCommand<'a, 'b>() -> App<'a, 'b> {
+        SubCommand::with_name("apply")
+            .setting(AppSettings::ColoredHelp)
+            .arg(
+                Arg::with_name("filename")
+                    .long("filename")
+                    .short("f")
+                    .required(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .help("object file, directory of object files, or - for stdin")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("dry-run")
+                    .long("dry-run")
+                    .help("only validate the objects and print the apply order"),
+            )
+            .about("Create or update objects from files")
+    }
+
+    pub async fn Run(&self, gConfig: &GlobalConfig) -> Result<()> {
+        let objs = self.Load()?;
+
+        let client = gConfig.GetObjectClient();
+        let mut counts = [0; 3];
+        let mut failCnt = 0;
+        for obj in &objs {
+            let id = format!("{} {}", &obj.objType, obj.Key());
+            if self.dryRun {
+                println!("{}: valid", id);
+                continue;
+            }
+
+            match Apply(&client, &gConfig.accessToken, obj).await {
+                Err(e) => {
+                    println!("{}: failed {:?}", id, e);
+                    failCnt += 1;
+                }
+                Ok((res, version)) => {
+                    println!("{}: {} (revision {})", id, res.Name(), version);
+                    counts[res as usize] += 1;
+                }
+            }
+        }
+
+        if self.dryRun {
+            println!("{} objects valid", objs.len());
+            return Ok(());
+        }
+
+        println!(
+            "{} created, {} updated, {} unchanged, {} failed",
+            counts[ApplyResult::Created as usize],
+            counts[ApplyResult::Updated as usize],
+            counts[ApplyResult::Unchanged as usize],
+            failCnt
+        );
+
+        if failCnt > 0 {
+            return Err(Error::CommonError(format!(
+                "{} of {} objects fail to apply",
+                failCnt,
+                objs.len()
+            )));
+        }
+
+        Ok(())
+    }
+
+    pub fn Load(&self) -> Result<Vec<DataObject<Value>>> {
+        LoadObjects(&self.filenames)
+    }
+}
+
+// LoadObjects reads and validates all the objects of the files and directories and
+// returns them in dependency order. It fails if any of the objects is invalid, so
+// nothing is done with a partial set.
+pub fn LoadObjects(filenames: &[String]) -> Result<Vec<DataObject<Value>>> {
+    let mut files = Vec::new();
+    for filename in filenames {
+        files.append(&mut ObjectFiles(filename)?);
+    }
+
+    let mut objs = Vec::new();
+    let mut failCnt = 0;
+    for filename in &files {
+        for report in ValidateFile(filename) {
+            if !report.IsValid() {
+                report.Print();
+                failCnt += 1;
+                continue;
+            }
+            objs.push(report.obj.unwrap());
+        }
+    }
+
+    if failCnt > 0 {
+        return Err(Error::CommonError(format!(
+            "{} objects fail validation, nothing is done",
+            failCnt
+        )));
+    }
+
+    SortByDependency(&mut objs);
+    Ok(objs)
+}
+
+// SortByDependency orders the objects by their scope, so the tenants come before their
+// namespaces and the namespaces before the functions in them. The objects of the same
+// scope keep their order.
+pub fn SortByDependency(objs: &mut [DataObject<Value>]) {
+    // valid objects always have a registered kind
+    objs.sort_by_key(|o| LookupObjectKind(&o.objType).unwrap().scope);
+}
+
+// Classify tells what applying obj does to the stored object, None if it doesn't
+// exist yet.
+pub fn Classify(
+    stored: Option<&DataObject<Value>>,
+    obj: &DataObject<Value>,
+) -> Result<ApplyResult> {
+    match stored {
+        None => Ok(ApplyResult::Created),
+        Some(stored) if stored.Diff(obj)?.is_empty() => Ok(ApplyResult::Unchanged),
+        Some(_) => Ok(ApplyResult::Updated),
+    }
+}
+
+// Apply creates the object if it doesn't exist yet, otherwise updates it unless the
+// user set fields of the stored object are already the same, as diff tells. The update
+// is guarded by the stored revision.
+pub async fn Apply(
+    client: &ObjectClient,
+    token: &str,
+    obj: &DataObject<Value>,
+) -> Result<(ApplyResult, i64)> {
+    let stored = match client
+        .Get(&obj.objType, &obj.tenant, &obj.namespace, &obj.name)
+        .await
+    {
+        Err(Error::NotExist(_)) => None,
+        Err(e) => return Err(e),
+        Ok(stored) => Some(stored),
+    };
+
+    match (Classify(stored.as_ref(), obj)?, stored) {
+        (ApplyResult::Created, _) | (_, None) => {
+            let version = client.Create(token, obj.clone()).await?;
+            Ok((ApplyResult::Created, version))
+        }
+        (ApplyResult::Unchanged, Some(stored)) => {
+            Ok((ApplyResult::Unchanged, stored.ExpectRevision()?))
+        }
+        (ApplyResult::Updated, Some(stored)) => {
+            let expectRevision = stored.ExpectRevision()?;
+            let version = client.Update(token, obj.clone(), expectRevision).await?;
+            Ok((ApplyResult::Updated, version))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use inferxlib::selector::Labels;
+    use serde_json::json;
+
+    fn Object(objType: &str, tenant: &str, namespace: &str, name: &str) -> DataObject<Value> {
+        serde_json::from_value(json!({
+            "objType": objType,
+            "tenant": tenant,
+            "namespace": namespace,
+            "name": name,
+            "object": { "spec": {}, "status": { "disable": false } },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn DependencyOrder() {
+        let mut objs = vec![
+            Object("function", "t1", "ns1", "f1"),
+            Object("namespace", "t1", "system", "ns1"),
+            Object("function", "t2", "ns2", "f2"),
+            Object("tenant", "system", "system", "t2"),
+            Object("namespace", "t2", "system", "ns2"),
+            Object("tenant", "system", "system", "t1"),
+            Object("function", "t1", "ns1", "f0"),
+        ];
+        SortByDependency(&mut objs);
+
+        let order: Vec<String> = objs
+            .iter()
+            .map(|o| format!("{} {}", o.objType, o.name))
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                "tenant t2",
+                "tenant t1",
+                "namespace ns1",
+                "namespace ns2",
+                "function f1",
+                "function f2",
+                "function f0",
+            ]
+        );
+    }
+
+    #[test]
+    fn Classification() {
+        let obj = Object("namespace", "t1", "system", "ns1");
+        let mut stored = obj.clone();
+        stored.revision = 3;
+        let mut labeled = obj.clone();
+        labeled.labels = Labels::New("team=a").unwrap();
+
+        assert_eq!(Classify(None, &obj).unwrap(), ApplyResult::Created);
+        // the revision is set by the store, not by the user
+        assert_eq!(
+            Classify(Some(&stored), &obj).unwrap(),
+            ApplyResult::Unchanged
+        );
+        assert_eq!(
+            Classify(Some(&stored), &labeled).unwrap(),
+            ApplyResult::Updated
+        );
+        // the status isn't the user's to apply
+        let mut disabled = obj.clone();
+        disabled.object["status"]["disable"] = json!(true);
+        assert_eq!(
+            Classify(Some(&stored), &disabled).unwrap(),
+            ApplyResult::Unchanged
+        );
+    }
+}
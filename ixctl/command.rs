@@ -12,22 +12,30 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use clap::{App, AppSettings, Arg};
-use std::{collections::BTreeSet, env};
+use clap::{App, AppSettings, Arg, ArgMatches};
+use std::env;
 
 use inferxlib::common::*;
+use inferxlib::data_obj::OBJECT_KINDS;
 
+use crate::apply::ApplyCmd;
+use crate::config::{ConfigCmd, ContextConfig, IxConfig};
 use crate::create::CreateCmd;
 use crate::delete::DeleteCmd;
+use crate::describe::DescribeCmd;
+use crate::diff::DiffCmd;
 use crate::get::GetCmd;
+use crate::invoke::InvokeCmd;
 use crate::list::ListCmd;
 use crate::object_client::ObjectClient;
+use crate::patch::PatchCmd;
+use crate::rollout::RolloutCmd;
+use crate::simulate::SimulateCmd;
 use crate::update::UpdateCmd;
+use crate::validate::ValidateCmd;
 
 lazy_static::lazy_static! {
-    pub static ref SUPPORT_OBJ_TYPES : BTreeSet<String> = [
-        "package".to_string(),
-    ].iter().cloned().collect();
+    pub static ref SUPPORT_OBJ_TYPES : Vec<&'static str> = OBJECT_KINDS.keys().cloned().collect();
 }
 
 pub const INFX_GATEWAY_URL: &str = "INFX_GATEWAY_URL";
@@ -35,12 +43,13 @@ pub const INFX_GATEWAY_URL: &str = "INFX_GATEWAY_URL";
 pub struct GlobalConfig {
     pub gatewayUrl: String,
     pub accessToken: String,
+    // the selected context of ~/.inferx/config, empty if there is none
+    pub context: ContextConfig,
 }
 
 impl GlobalConfig {
     pub fn GetObjectClient(&self) -> ObjectClient {
-        let client = ObjectClient::New(&self.gatewayUrl);
-        return client;
+        ObjectClient::New(&self.gatewayUrl)
     }
 }
 
@@ -54,8 +63,65 @@ pub enum Command {
     Create(CreateCmd),
     List(ListCmd),
     Get(GetCmd),
+    Describe(DescribeCmd),
     Delete(DeleteCmd),
     Update(UpdateCmd),
+    Apply(ApplyCmd),
+    Patch(PatchCmd),
+    Validate(ValidateCmd),
+    Config(ConfigCmd),
+    Invoke(InvokeCmd),
+    Rollout(RolloutCmd),
+    Diff(DiffCmd),
+    Simulate(SimulateCmd),
+}
+
+impl Command {
+    // ResolveScope defaults the tenant and namespace of the commands working on
+    // existing objects to the ones of the context.
+    pub fn ResolveScope(&mut self, context: &ContextConfig) -> Result<()> {
+        match self {
+            Command::List(cmd) => {
+                DefaultScope(&mut cmd.tenant, "tenant", &context.tenant)?;
+                if !cmd.allNamespaces {
+                    DefaultScope(&mut cmd.namespace, "namespace", &context.namespace)?;
+                }
+            }
+            Command::Get(cmd) => {
+                ResolveTenantNamespace(&mut cmd.tenant, &mut cmd.namespace, context)?
+            }
+            Command::Describe(cmd) => {
+                ResolveTenantNamespace(&mut cmd.tenant, &mut cmd.namespace, context)?
+            }
+            Command::Delete(cmd) => {
+                ResolveTenantNamespace(&mut cmd.tenant, &mut cmd.namespace, context)?
+            }
+            Command::Patch(cmd) => {
+                ResolveTenantNamespace(&mut cmd.tenant, &mut cmd.namespace, context)?
+            }
+            Command::Invoke(cmd) => {
+                ResolveTenantNamespace(&mut cmd.tenant, &mut cmd.namespace, context)?
+            }
+            Command::Rollout(cmd) => {
+                ResolveTenantNamespace(&mut cmd.tenant, &mut cmd.namespace, context)?
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    // NeedGateway returns false for the commands which only work on local files.
+    pub fn NeedGateway(&self) -> bool {
+        match self {
+            Command::Create(cmd) => !cmd.dryRun,
+            Command::Update(cmd) => !cmd.dryRun,
+            Command::Apply(cmd) => !cmd.dryRun,
+            Command::Validate(_) => false,
+            Command::Config(_) => false,
+            Command::Simulate(_) => false,
+            _ => true,
+        }
+    }
 }
 
 pub async fn Run(args: &mut Arguments) -> Result<()> {
@@ -63,20 +129,29 @@ pub async fn Run(args: &mut Arguments) -> Result<()> {
         Command::Create(cmd) => return cmd.Run(&args.gConfig).await,
         Command::List(cmd) => return cmd.Run(&args.gConfig).await,
         Command::Get(cmd) => return cmd.Run(&args.gConfig).await,
+        Command::Describe(cmd) => return cmd.Run(&args.gConfig).await,
         Command::Delete(cmd) => return cmd.Run(&args.gConfig).await,
         Command::Update(cmd) => return cmd.Run(&args.gConfig).await,
+        Command::Apply(cmd) => return cmd.Run(&args.gConfig).await,
+        Command::Patch(cmd) => return cmd.Run(&args.gConfig).await,
+        Command::Validate(cmd) => return cmd.Run(&args.gConfig).await,
+        Command::Config(cmd) => return cmd.Run(&args.gConfig).await,
+        Command::Invoke(cmd) => return cmd.Run(&args.gConfig).await,
+        Command::Rollout(cmd) => return cmd.Run(&args.gConfig).await,
+        Command::Diff(cmd) => return cmd.Run(&args.gConfig).await,
+        Command::Simulate(cmd) => return cmd.Run(&args.gConfig).await,
     }
 }
 
 fn get_args() -> Vec<String> {
-    return env::args().collect();
+    env::args().collect()
 }
 
 pub fn Parse() -> Result<Arguments> {
     let matches = App::new("ixctl")
         .about("ixctl - inferx client command line tool")
         .setting(AppSettings::ColoredHelp)
-        .author(crate_authors!("\n"))
+        .author(crate_authors!())
         .setting(AppSettings::SubcommandRequired)
         .version(crate_version!())
         .arg(
@@ -86,55 +161,278 @@ pub fn Parse() -> Result<Arguments> {
                 .short("s")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("context")
+                .help("context of ~/.inferx/config to use instead of the current one")
+                .long("context")
+                .takes_value(true),
+        )
         .subcommand(CreateCmd::SubCommand())
         .subcommand(ListCmd::SubCommand())
         .subcommand(GetCmd::SubCommand())
+        .subcommand(DescribeCmd::SubCommand())
         .subcommand(DeleteCmd::SubCommand())
         .subcommand(UpdateCmd::SubCommand())
+        .subcommand(ApplyCmd::SubCommand())
+        .subcommand(PatchCmd::SubCommand())
+        .subcommand(ValidateCmd::SubCommand())
+        .subcommand(ConfigCmd::SubCommand())
+        .subcommand(InvokeCmd::SubCommand())
+        .subcommand(RolloutCmd::SubCommand())
+        .subcommand(DiffCmd::SubCommand())
+        .subcommand(SimulateCmd::SubCommand())
         .get_matches_from(get_args());
 
+    let mut cmd = match matches.subcommand() {
+        ("create", Some(cmd_matches)) => Command::Create(CreateCmd::Init(cmd_matches)?),
+        ("get", Some(cmd_matches)) => Command::Get(GetCmd::Init(cmd_matches)?),
+        ("describe", Some(cmd_matches)) => Command::Describe(DescribeCmd::Init(cmd_matches)?),
+        ("list", Some(cmd_matches)) => Command::List(ListCmd::Init(cmd_matches)?),
+        ("delete", Some(cmd_matches)) => Command::Delete(DeleteCmd::Init(cmd_matches)?),
+        ("update", Some(cmd_matches)) => Command::Update(UpdateCmd::Init(cmd_matches)?),
+        ("apply", Some(cmd_matches)) => Command::Apply(ApplyCmd::Init(cmd_matches)?),
+        ("patch", Some(cmd_matches)) => Command::Patch(PatchCmd::Init(cmd_matches)?),
+        ("validate", Some(cmd_matches)) => Command::Validate(ValidateCmd::Init(cmd_matches)?),
+        ("config", Some(cmd_matches)) => Command::Config(ConfigCmd::Init(cmd_matches)?),
+        ("invoke", Some(cmd_matches)) => Command::Invoke(InvokeCmd::Init(cmd_matches)?),
+        ("rollout", Some(cmd_matches)) => Command::Rollout(RolloutCmd::Init(cmd_matches)?),
+        ("diff", Some(cmd_matches)) => Command::Diff(DiffCmd::Init(cmd_matches)?),
+        ("simulate", Some(cmd_matches)) => Command::Simulate(SimulateCmd::Init(cmd_matches)?),
+        // We should never reach here because clap already enforces this
+        x => panic!("command not recognized {:?}", x),
+    };
+
+    // the context gives the gateway, its credentials and the default scope, the commands
+    // which only work on local files don't read it, so a broken or missing context doesn't
+    // stop them (nor the config commands which repair it)
+    let context = if cmd.NeedGateway() {
+        IxConfig::Load()?.Context(matches.value_of("context"))?
+    } else {
+        ContextConfig::default()
+    };
+
+    // the gateway url is taken from the commandline, the environment variable, then
+    // the context
     let gatewayUrl = match matches.value_of("server") {
+        Some(s) => s.to_owned(),
         None => match std::env::var(INFX_GATEWAY_URL) {
             Ok(s) => s,
-            Err(_e) => {
-                panic!(
-                    "can't get gateway url from commandline or Environment Variable {}",
-                    INFX_GATEWAY_URL
-                );
-            }
+            Err(_) => context.gatewayUrl.clone(),
         },
-        Some(s) => s.to_owned(),
     };
 
+    if gatewayUrl.is_empty() && cmd.NeedGateway() {
+        return Err(Error::CommonError(format!(
+            "can't get gateway url from commandline, Environment Variable {} or context",
+            INFX_GATEWAY_URL
+        )));
+    }
+
+    cmd.ResolveScope(&context)?;
+
     let gConfig = GlobalConfig {
-        gatewayUrl: gatewayUrl,
+        gatewayUrl,
         accessToken: "".to_owned(),
+        context,
     };
 
-    let args = match matches.subcommand() {
-        ("create", Some(cmd_matches)) => Arguments {
-            gConfig: gConfig,
-            cmd: Command::Create(CreateCmd::Init(&cmd_matches)?),
-        },
-        ("get", Some(cmd_matches)) => Arguments {
-            gConfig: gConfig,
-            cmd: Command::Get(GetCmd::Init(&cmd_matches)?),
-        },
-        ("list", Some(cmd_matches)) => Arguments {
-            gConfig: gConfig,
-            cmd: Command::List(ListCmd::Init(&cmd_matches)?),
-        },
-        ("delete", Some(cmd_matches)) => Arguments {
-            gConfig: gConfig,
-            cmd: Command::Delete(DeleteCmd::Init(&cmd_matches)?),
-        },
-        ("update", Some(cmd_matches)) => Arguments {
-            gConfig: gConfig,
-            cmd: Command::Update(UpdateCmd::Init(&cmd_matches)?),
-        },
-        // We should never reach here because clap already enforces this
-        x => panic!("command not recognized {:?}", x),
+    let args = Arguments {
+        gConfig,
+        cmd,
+    };
+
+    Ok(args)
+}
+
+// TenantArg and NamespaceArg scope the commands working on existing objects, they
+// default to the tenant and namespace of the current context.
+pub fn TenantArg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("tenant")
+        .long("tenant")
+        .short("t")
+        .help("object tenant, the context tenant by default")
+        .takes_value(true)
+}
+
+pub fn NamespaceArg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("namespace")
+        .long("namespace")
+        .short("n")
+        .help("object namespace, the context namespace by default")
+        .takes_value(true)
+}
+
+// ObjectNameArg is the "[[TENANT] NAMESPACE] NAME" positional of the commands working on
+// an existing object. A positional tenant or namespace wins over -t/-n.
+pub fn ObjectNameArg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("name")
+        .required(true)
+        .multiple(true)
+        .max_values(3)
+        .value_name("[[TENANT] NAMESPACE] NAME")
+        .help("object name, optionally preceded by its tenant and namespace")
+}
+
+// ParseObjectName returns the tenant, namespace and name of ObjectNameArg. The tenant and
+// namespace are empty when neither the positional nor -t/-n gives them, ResolveScope
+// then takes the ones of the context.
+pub fn ParseObjectName(cmd_matches: &ArgMatches) -> (String, String, String) {
+    let vals: Vec<&str> = cmd_matches.values_of("name").unwrap().collect();
+    let name = vals[vals.len() - 1].to_owned();
+    let (tenant, namespace) = match vals.len() {
+        3 => PositionalScope(cmd_matches, Some(vals[0]), Some(vals[1])),
+        2 => PositionalScope(cmd_matches, None, Some(vals[0])),
+        _ => PositionalScope(cmd_matches, None, None),
+    };
+    (tenant, namespace, name)
+}
+
+// ScopeArg is the "[TENANT [NAMESPACE]]" positional of the commands working on a tenant or
+// namespace. A positional tenant or namespace wins over -t/-n.
+pub fn ScopeArg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("scope")
+        .multiple(true)
+        .max_values(2)
+        .value_name("TENANT [NAMESPACE]")
+        .help("tenant and namespace, the same as -t and -n")
+}
+
+pub fn ParseScope(cmd_matches: &ArgMatches) -> (String, String) {
+    let vals: Vec<&str> = match cmd_matches.values_of("scope") {
+        None => Vec::new(),
+        Some(v) => v.collect(),
     };
+    PositionalScope(cmd_matches, vals.first().copied(), vals.get(1).copied())
+}
+
+fn PositionalScope(
+    cmd_matches: &ArgMatches,
+    tenant: Option<&str>,
+    namespace: Option<&str>,
+) -> (String, String) {
+    let tenant = tenant
+        .or(cmd_matches.value_of("tenant"))
+        .unwrap_or_default();
+    let namespace = namespace
+        .or(cmd_matches.value_of("namespace"))
+        .unwrap_or_default();
+    (tenant.to_owned(), namespace.to_owned())
+}
+
+fn ResolveTenantNamespace(
+    tenant: &mut String,
+    namespace: &mut String,
+    context: &ContextConfig,
+) -> Result<()> {
+    DefaultScope(tenant, "tenant", &context.tenant)?;
+    DefaultScope(namespace, "namespace", &context.namespace)
+}
 
-    return Ok(args);
+fn DefaultScope(value: &mut String, field: &str, default: &str) -> Result<()> {
+    if !value.is_empty() {
+        return Ok(());
+    }
+    if default.is_empty() {
+        return Err(Error::CommonError(format!(
+            "{} is not set, use --{} or set it in the context with ixctl config set-context",
+            field, field
+        )));
+    }
+    *value = default.to_owned();
+    Ok(())
+}
+
+// RevisionArg is the --revision option shared by the commands that modify an existing
+// object: the request fails with a conflict if the stored object is no longer at it.
+pub fn RevisionArg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("revision")
+        .long("revision")
+        .help("only apply if the stored object is still at this revision")
+        .takes_value(true)
+}
+
+pub fn ParseRevision(cmd_matches: &ArgMatches) -> Result<Option<i64>> {
+    match cmd_matches.value_of("revision") {
+        None => Ok(None),
+        Some(s) => match s.parse::<i64>() {
+            Ok(rev) if rev > 0 => Ok(Some(rev)),
+            _ => Err(Error::CommonError(format!(
+                "invalid revision {:?}, expect a positive integer",
+                s
+            ))),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn Delete(args: &[&str]) -> DeleteCmd {
+        let matches = DeleteCmd::SubCommand().get_matches_from_safe(args).unwrap();
+        DeleteCmd::Init(&matches).unwrap()
+    }
+
+    fn List(args: &[&str]) -> Result<ListCmd> {
+        let matches = ListCmd::SubCommand().get_matches_from_safe(args).unwrap();
+        ListCmd::Init(&matches)
+    }
+
+    fn Scope(cmd: &DeleteCmd) -> (&str, &str, &str) {
+        (&cmd.tenant, &cmd.namespace, &cmd.name)
+    }
+
+    #[test]
+    fn PositionalScope() {
+        // the commands of config/models.txt
+        let cmd = Delete(&[
+            "delete",
+            "function",
+            "public",
+            "TinyLlama",
+            "TinyLlama-1.1B",
+        ]);
+        assert_eq!(Scope(&cmd), ("public", "TinyLlama", "TinyLlama-1.1B"));
+        let cmd = Delete(&["delete", "tenant", "system", "system", "public"]);
+        assert_eq!(Scope(&cmd), ("system", "system", "public"));
+
+        // the positionals win over the options
+        let cmd = Delete(&[
+            "delete", "function", "-t", "t1", "-n", "ns1", "public", "Qwen", "f1",
+        ]);
+        assert_eq!(Scope(&cmd), ("public", "Qwen", "f1"));
+        let cmd = Delete(&["delete", "function", "-t", "t1", "-n", "ns1", "Qwen", "f1"]);
+        assert_eq!(Scope(&cmd), ("t1", "Qwen", "f1"));
+
+        // what is not given is left to the context
+        let cmd = Delete(&["delete", "function", "-n", "ns1", "f1"]);
+        assert_eq!(Scope(&cmd), ("", "ns1", "f1"));
+        let cmd = Delete(&["delete", "function", "f1"]);
+        assert_eq!(Scope(&cmd), ("", "", "f1"));
+
+        assert!(DeleteCmd::SubCommand()
+            .get_matches_from_safe(["delete", "function", "a", "b", "c", "d"])
+            .is_err());
+    }
+
+    #[test]
+    fn PositionalListScope() {
+        let cmd = List(&["list", "function", "public", "Qwen"]).unwrap();
+        assert_eq!(
+            (cmd.tenant.as_str(), cmd.namespace.as_str()),
+            ("public", "Qwen")
+        );
+        let cmd = List(&["list", "function", "-n", "ns1", "public"]).unwrap();
+        assert_eq!(
+            (cmd.tenant.as_str(), cmd.namespace.as_str()),
+            ("public", "ns1")
+        );
+        let cmd = List(&["list", "function", "-A", "public"]).unwrap();
+        assert_eq!(
+            (cmd.tenant.as_str(), cmd.namespace.as_str()),
+            ("public", "")
+        );
+
+        assert!(List(&["list", "function", "-A", "public", "Qwen"]).is_err());
+    }
 }
@@ -0,0 +1,499 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use inferxlib::common::*;
+
+use crate::command::GlobalConfig;
+use crate::output::PrintRows;
+
+// INFERX_CONFIG overrides the config file path, by default ~/.inferx/config
+pub const INFERX_CONFIG: &str = "INFERX_CONFIG";
+pub const DEFAULT_KEYCLOAK_URL: &str = "http://localhost:1260/authn";
+pub const DEFAULT_REALM: &str = "inferx";
+pub const KEYCLOAK_CLIENT_ID: &str = "infer_client";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthMethod {
+    // pick by the IFERX_* environment variables which are set
+    #[default]
+    Auto,
+    None,
+    // IFERX_APIKEY
+    Apikey,
+    // keycloak password grant with IFERX_USERNAME/IFERX_PASSWORD
+    Password,
+}
+
+impl AuthMethod {
+    pub fn Parse(s: &str) -> Result<Self> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "none" => Ok(Self::None),
+            "apikey" => Ok(Self::Apikey),
+            "password" => Ok(Self::Password),
+            _ => {
+                Err(Error::CommonError(format!(
+                    "unknown auth method {:?}, expect auto, none, apikey or password",
+                    s
+                )))
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ContextConfig {
+    pub name: String,
+    #[serde(default)]
+    pub gatewayUrl: String,
+    #[serde(default)]
+    pub keycloakUrl: String,
+    #[serde(default)]
+    pub realm: String,
+    #[serde(default)]
+    pub tenant: String,
+    #[serde(default)]
+    pub namespace: String,
+    #[serde(default)]
+    pub auth: AuthMethod,
+    // keycloak client secret, IFERX_SECRET overrides it
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub clientSecret: String,
+}
+
+impl ContextConfig {
+    pub fn KeycloakUrl(&self) -> String {
+        if let Ok(s) = std::env::var("KEYCLOAK_URL") {
+            return s;
+        }
+        if !self.keycloakUrl.is_empty() {
+            return self.keycloakUrl.clone();
+        }
+        DEFAULT_KEYCLOAK_URL.to_owned()
+    }
+
+    pub fn Realm(&self) -> String {
+        if self.realm.is_empty() {
+            return DEFAULT_REALM.to_owned();
+        }
+        self.realm.clone()
+    }
+
+    // CacheKey identifies the cached token of username in the context, so a token is
+    // never used for another user or client. Without a context the token is cached per
+    // keycloak realm.
+    pub fn CacheKey(&self, username: &str) -> String {
+        let context = if self.name.is_empty() {
+            format!("{}#{}", self.KeycloakUrl(), self.Realm())
+        } else {
+            self.name.clone()
+        };
+        format!("{}#{}#{}", context, KEYCLOAK_CLIENT_ID, username)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct IxConfig {
+    #[serde(default)]
+    pub currentContext: String,
+    #[serde(default)]
+    pub contexts: Vec<ContextConfig>,
+}
+
+impl IxConfig {
+    pub fn Dir() -> PathBuf {
+        let home = std::env::var("HOME").unwrap_or_default();
+        PathBuf::from(home).join(".inferx")
+    }
+
+    pub fn Path() -> PathBuf {
+        match std::env::var(INFERX_CONFIG) {
+            Ok(p) => PathBuf::from(p),
+            Err(_) => Self::Dir().join("config"),
+        }
+    }
+
+    // Load reads the config file, a missing file is an empty config.
+    pub fn Load() -> Result<Self> {
+        let path = Self::Path();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(&path)?;
+        match serde_yaml::from_str(&content) {
+            Err(e) => {
+                Err(Error::CommonError(format!(
+                    "can't parse {}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+            Ok(c) => Ok(c),
+        }
+    }
+
+    pub fn Save(&self) -> Result<()> {
+        let content =
+            serde_yaml::to_string(self).map_err(|e| Error::CommonError(format!("{}", e)))?;
+        // the context may hold the client secret
+        WritePrivateFile(&Self::Path(), &content)
+    }
+
+    pub fn Get(&self, name: &str) -> Option<&ContextConfig> {
+        self.contexts.iter().find(|c| c.name == name)
+    }
+
+    // Context returns the named context, or the current one if no name is given. An
+    // empty context is returned when there is no current context, so ixctl keeps
+    // working from the environment variables only.
+    pub fn Context(&self, name: Option<&str>) -> Result<ContextConfig> {
+        let name = match name {
+            Some(n) => n,
+            None if self.currentContext.is_empty() => return Ok(ContextConfig::default()),
+            None => &self.currentContext,
+        };
+
+        match self.Get(name) {
+            None => {
+                Err(Error::NotExist(format!(
+                    "context {} in {}",
+                    name,
+                    Self::Path().display()
+                )))
+            }
+            Some(c) => Ok(c.clone()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CachedToken {
+    pub accessToken: String,
+    // unix seconds, 0 if the token doesn't expire
+    pub expiresAt: u64,
+    #[serde(default)]
+    pub refreshToken: String,
+}
+
+impl CachedToken {
+    // a token which expires in less than this is refreshed before use
+    pub const EXPIRY_MARGIN_SECS: u64 = 30;
+
+    pub fn IsValid(&self) -> bool {
+        self.expiresAt == 0 || self.expiresAt > Now() + Self::EXPIRY_MARGIN_SECS
+    }
+}
+
+// TokenCache is kept in its own file beside the config, readable by the user only.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TokenCache {
+    pub tokens: BTreeMap<String, CachedToken>,
+}
+
+impl TokenCache {
+    pub fn Path() -> PathBuf {
+        IxConfig::Dir().join("tokens.json")
+    }
+
+    // Load returns an empty cache if the file is missing or broken, it's only a cache.
+    pub fn Load() -> Self {
+        let content = match std::fs::read_to_string(Self::Path()) {
+            Err(_) => return Self::default(),
+            Ok(c) => c,
+        };
+        serde_json::from_str(&content).unwrap_or_default()
+    }
+
+    pub fn Save(&self) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        WritePrivateFile(&Self::Path(), &content)
+    }
+}
+
+pub fn Now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn WritePrivateFile(path: &PathBuf, content: &str) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    // the mode only applies to a new file
+    file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    file.write_all(content.as_bytes())?;
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum ConfigAction {
+    GetContexts,
+    UseContext(String),
+    SetContext(SetContextArgs),
+}
+
+// SetContextArgs holds the fields given on the command line, the others are kept.
+#[derive(Debug, Default)]
+pub struct SetContextArgs {
+    pub name: String,
+    pub gatewayUrl: Option<String>,
+    pub keycloakUrl: Option<String>,
+    pub realm: Option<String>,
+    pub tenant: Option<String>,
+    pub namespace: Option<String>,
+    pub auth: Option<AuthMethod>,
+    pub clientSecret: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct ConfigCmd {
+    pub action: ConfigAction,
+}
+
+impl ConfigCmd {
+    pub fn Init(cmd_matches: &ArgMatches) -> Result<Self> {
+        let action = match cmd_matches.subcommand() {
+            ("get-contexts", Some(_)) => ConfigAction::GetContexts,
+            ("use-context", Some(m)) => {
+                ConfigAction::UseContext(m.value_of("name").unwrap().to_owned())
+            }
+            ("set-context", Some(m)) => {
+                let value = |name: &str| m.value_of(name).map(|s| s.to_owned());
+                ConfigAction::SetContext(SetContextArgs {
+                    name: m.value_of("name").unwrap().to_owned(),
+                    gatewayUrl: value("server"),
+                    keycloakUrl: value("keycloak-url"),
+                    realm: value("realm"),
+                    tenant: value("tenant"),
+                    namespace: value("namespace"),
+                    auth: match m.value_of("auth") {
+                        None => None,
+                        Some(a) => Some(AuthMethod::Parse(a)?),
+                    },
+                    clientSecret: value("client-secret"),
+                })
+            }
+            // clap enforces one of the subcommands
+            x => panic!("config command not recognized {:?}", x),
+        };
+
+        Ok(Self { action })
+    }
+
+    pub fn SubCommand<'a, 'b>() -> App<'a, 'b> {
+        let field = |name: &'a str, help: &'a str| -> Arg<'a, 'b> {
+            Arg::with_name(name).long(name).help(help).takes_value(true)
+        };
+
+        SubCommand::with_name("config")
+            .setting(AppSettings::ColoredHelp)
+            .setting(AppSettings::SubcommandRequired)
+            .subcommand(SubCommand::with_name("get-contexts").about("List the contexts"))
+            .subcommand(
+                SubCommand::with_name("use-context")
+                    .arg(
+                        Arg::with_name("name")
+                            .required(true)
+                            .help("context name")
+                            .takes_value(true),
+                    )
+                    .about("Set the current context"),
+            )
+            .subcommand(
+                SubCommand::with_name("set-context")
+                    .arg(
+                        Arg::with_name("name")
+                            .required(true)
+                            .help("context name")
+                            .takes_value(true),
+                    )
+                    .arg(field("server", "gateway url"))
+                    .arg(field("keycloak-url", "keycloak url"))
+                    .arg(field("realm", "keycloak realm, inferx by default"))
+                    .arg(field("tenant", "default tenant"))
+                    .arg(field("namespace", "default namespace"))
+                    .arg(
+                        field("auth", "auth method")
+                            .possible_values(&["auto", "none", "apikey", "password"]),
+                    )
+                    .arg(field("client-secret", "keycloak client secret"))
+                    .about("Create a context or update the given fields of it"),
+            )
+            .about(CONFIG_ABOUT.as_str())
+    }
+
+    pub async fn Run(&self, _gConfig: &GlobalConfig) -> Result<()> {
+        let mut config = IxConfig::Load()?;
+        match &self.action {
+            ConfigAction::GetContexts => {
+                let mut rows = vec![vec![
+                    "CURRENT".to_owned(),
+                    "NAME".to_owned(),
+                    "SERVER".to_owned(),
+                    "TENANT".to_owned(),
+                    "NAMESPACE".to_owned(),
+                    "AUTH".to_owned(),
+                ]];
+                for c in &config.contexts {
+                    let current = if c.name == config.currentContext {
+                        "*"
+                    } else {
+                        ""
+                    };
+                    rows.push(vec![
+                        current.to_owned(),
+                        c.name.clone(),
+                        c.gatewayUrl.clone(),
+                        c.tenant.clone(),
+                        c.namespace.clone(),
+                        format!("{:?}", c.auth).to_lowercase(),
+                    ]);
+                }
+                PrintRows(&rows, "");
+            }
+            ConfigAction::UseContext(name) => {
+                if config.Get(name).is_none() {
+                    return Err(Error::NotExist(format!("context {}", name)));
+                }
+                config.currentContext = name.clone();
+                config.Save()?;
+                println!("Switched to context {}", name);
+            }
+            ConfigAction::SetContext(args) => {
+                let idx = match config.contexts.iter().position(|c| c.name == args.name) {
+                    Some(idx) => idx,
+                    None => {
+                        config.contexts.push(ContextConfig {
+                            name: args.name.clone(),
+                            ..Default::default()
+                        });
+                        config.contexts.len() - 1
+                    }
+                };
+
+                let c = &mut config.contexts[idx];
+                let set = |field: &mut String, value: &Option<String>| {
+                    if let Some(v) = value {
+                        *field = v.clone();
+                    }
+                };
+                set(&mut c.gatewayUrl, &args.gatewayUrl);
+                set(&mut c.keycloakUrl, &args.keycloakUrl);
+                set(&mut c.realm, &args.realm);
+                set(&mut c.tenant, &args.tenant);
+                set(&mut c.namespace, &args.namespace);
+                set(&mut c.clientSecret, &args.clientSecret);
+                if let Some(auth) = args.auth {
+                    c.auth = auth;
+                }
+
+                if config.currentContext.is_empty() {
+                    config.currentContext = args.name.clone();
+                }
+                config.Save()?;
+                println!("Context {} saved", &args.name);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref CONFIG_ABOUT: String = format!(
+        "Manage the contexts in {} (or ${})",
+        IxConfig::Path().display(),
+        INFERX_CONFIG
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn Config() -> IxConfig {
+        let context = |name: &str, tenant: &str| ContextConfig {
+            name: name.to_owned(),
+            gatewayUrl: format!("http://{}:4000", name),
+            tenant: tenant.to_owned(),
+            ..Default::default()
+        };
+
+        IxConfig {
+            currentContext: "dev".to_owned(),
+            contexts: vec![context("dev", "t1"), context("prod", "t2")],
+        }
+    }
+
+    #[test]
+    fn ContextLookup() {
+        let mut config = Config();
+        assert_eq!(config.Context(Some("prod")).unwrap().tenant, "t2");
+        assert_eq!(config.Context(None).unwrap().name, "dev");
+        assert!(matches!(
+            config.Context(Some("staging")),
+            Err(Error::NotExist(_))
+        ));
+
+        // no current context, ixctl runs from the environment variables
+        config.currentContext.clear();
+        assert_eq!(config.Context(None).unwrap().name, "");
+        assert!(config.Context(Some("dev")).is_ok());
+
+        // a current context which was removed is an error, not the default
+        config.currentContext = "staging".to_owned();
+        assert!(matches!(config.Context(None), Err(Error::NotExist(_))));
+    }
+
+    #[test]
+    fn TokenCacheKeys() {
+        let config = Config();
+        let dev = config.Context(Some("dev")).unwrap();
+        let prod = config.Context(Some("prod")).unwrap();
+        assert_eq!(dev.CacheKey("alice"), "dev#infer_client#alice");
+        assert_ne!(dev.CacheKey("alice"), dev.CacheKey("bob"));
+        assert_ne!(dev.CacheKey("alice"), prod.CacheKey("alice"));
+
+        // without a context the token is cached per keycloak realm
+        let none = ContextConfig::default();
+        assert_eq!(
+            none.CacheKey("alice"),
+            format!("{}#inferx#infer_client#alice", none.KeycloakUrl())
+        );
+        let realm = ContextConfig {
+            realm: "other".to_owned(),
+            ..Default::default()
+        };
+        assert_ne!(realm.CacheKey("alice"), none.CacheKey("alice"));
+    }
+}
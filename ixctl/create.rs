@@ -15,65 +15,56 @@
 use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 
 use inferxlib::common::*;
-use inferxlib::data_obj::DataObject;
-use serde_json::Value;
 
 use crate::command::GlobalConfig;
+use crate::validate::LoadValidObjects;
 
 #[derive(Debug)]
 pub struct CreateCmd {
     pub filename: String,
+    pub dryRun: bool,
 }
 
 impl CreateCmd {
     pub fn Init(cmd_matches: &ArgMatches) -> Result<Self> {
-        return Ok(Self {
+        Ok(Self {
             filename: cmd_matches.value_of("filename").unwrap().to_string(),
-        });
+            dryRun: cmd_matches.is_present("dry-run"),
+        })
     }
 
     pub fn SubCommand<'a, 'b>() -> App<'a, 'b> {
-        return SubCommand::with_name("create")
+        SubCommand::with_name("create")
             .setting(AppSettings::ColoredHelp)
             .arg(
                 Arg::with_name("filename")
                     .required(true)
-                    .help("file name")
+                    .help("json or yaml file name, or - for stdin")
                     .takes_value(true),
             )
-            .about("Create a python function package");
+            .arg(
+                Arg::with_name("dry-run")
+                    .long("dry-run")
+                    .help("only validate the file, don't send it to the gateway"),
+            )
+            .about("Create objects from a file")
     }
 
     pub async fn Run(&self, gConfig: &GlobalConfig) -> Result<()> {
-        println!("CreateCmd is {:?}", self);
-
-        let content = match std::fs::read_to_string(&self.filename) {
-            Err(e) => {
-                println!("Can't open file {} with error {:?}", &self.filename, e);
-                return Ok(());
-            }
-            Ok(c) => c,
-        };
-
-        let o = match DataObject::<Value>::NewFromString(&content) {
-            Err(e) => {
-                println!(
-                    "Can't parse file {} as Json with error {:?}",
-                    &self.filename, e
-                );
-                return Ok(());
-            }
-            Ok(c) => c,
-        };
+        let objs = LoadValidObjects(&self.filename, self.dryRun)?;
+        if self.dryRun {
+            return Ok(());
+        }
 
-        println!("CreateCmd obj is {:#?}", &o);
         let client = gConfig.GetObjectClient();
-        let version = client.Create(&gConfig.accessToken, o.clone()).await?;
+        for o in objs {
+            let version = client.Create(&gConfig.accessToken, o.clone()).await?;
 
-        let obj = o.CopyWithRev(version, version);
+            let obj = o.CopyWithRev(version, version);
 
-        println!("{:#?}", obj);
+            println!("{:#?}", obj);
+        }
 
-        return Ok(());
+        Ok(())
     }
 }
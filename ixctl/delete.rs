@@ -14,7 +14,10 @@
 
 use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 
-use crate::command::GlobalConfig;
+use crate::command::{
+    GlobalConfig, NamespaceArg, ObjectNameArg, ParseObjectName, ParseRevision, RevisionArg,
+    TenantArg, SUPPORT_OBJ_TYPES,
+};
 
 use inferxlib::common::*;
 
@@ -24,51 +27,40 @@ pub struct DeleteCmd {
     pub tenant: String,
     pub namespace: String,
     pub name: String,
+    pub revision: Option<i64>,
 }
 
 impl DeleteCmd {
     pub fn Init(cmd_matches: &ArgMatches) -> Result<Self> {
-        return Ok(Self {
+        let (tenant, namespace, name) = ParseObjectName(cmd_matches);
+        Ok(Self {
             objType: cmd_matches.value_of("objectType").unwrap().to_string(),
-            tenant: cmd_matches.value_of("tenant").unwrap().to_string(),
-            namespace: cmd_matches.value_of("namespace").unwrap().to_string(),
-            name: cmd_matches.value_of("name").unwrap().to_string(),
-        });
+            tenant,
+            namespace,
+            name,
+            revision: ParseRevision(cmd_matches)?,
+        })
     }
 
     pub fn SubCommand<'a, 'b>() -> App<'a, 'b> {
-        return SubCommand::with_name("delete")
+        SubCommand::with_name("delete")
             .setting(AppSettings::ColoredHelp)
             .arg(
                 Arg::with_name("objectType")
                     .required(true)
                     .help("object type")
+                    .possible_values(&SUPPORT_OBJ_TYPES)
                     .takes_value(true),
             )
-            .arg(
-                Arg::with_name("tenant")
-                    .required(true)
-                    .help("tenant")
-                    .takes_value(true),
-            )
-            .arg(
-                Arg::with_name("namespace")
-                    .required(true)
-                    .help("namespace")
-                    .takes_value(true),
-            )
-            .arg(
-                Arg::with_name("name")
-                    .required(true)
-                    .help("object name")
-                    .takes_value(true),
-            )
-            .about("Create a python function package");
+            .arg(TenantArg())
+            .arg(NamespaceArg())
+            .arg(ObjectNameArg())
+            .arg(RevisionArg())
+            .about("Delete an object")
     }
 
     pub async fn Run(&self, gConfig: &GlobalConfig) -> Result<()> {
         let client = gConfig.GetObjectClient();
-        println!("Deletecmd is {:?}", self);
 
         let _version = client
             .Delete(
@@ -77,9 +69,10 @@ impl DeleteCmd {
                 &self.tenant,
                 &self.namespace,
                 &self.name,
+                self.revision.unwrap_or(0),
             )
             .await?;
 
-        return Ok(());
+        Ok(())
     }
 }
@@ -0,0 +1,231 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+
+use inferxlib::common::*;
+use inferxlib::obj_mgr::func_mgr::{FuncObject, Function};
+use inferxlib::obj_mgr::funcsnapshot_mgr::{ContainerSnapshot, FuncSnapshot};
+use inferxlib::obj_mgr::pod_mgr::{FuncPod, FuncPodObject};
+
+use crate::command::{GlobalConfig, NamespaceArg, ObjectNameArg, ParseObjectName, TenantArg};
+use crate::object_client::ObjectClient;
+use crate::output::{HumanSize, PrintRows};
+
+#[derive(Debug)]
+pub struct DescribeCmd {
+    pub objType: String,
+    pub tenant: String,
+    pub namespace: String,
+    pub name: String,
+}
+
+impl DescribeCmd {
+    pub fn Init(cmd_matches: &ArgMatches) -> Result<Self> {
+        let (tenant, namespace, name) = ParseObjectName(cmd_matches);
+        Ok(Self {
+            objType: cmd_matches.value_of("type").unwrap().to_string(),
+            tenant,
+            namespace,
+            name,
+        })
+    }
+
+    pub fn SubCommand<'a, 'b>() -> App<'a, 'b> {
+        SubCommand::with_name("describe")
+            .setting(AppSettings::ColoredHelp)
+            .arg(
+                Arg::with_name("type")
+                    .required(true)
+                    .help("object type")
+                    .possible_values(&[Function::KEY])
+                    .takes_value(true),
+            )
+            .arg(TenantArg())
+            .arg(NamespaceArg())
+            .arg(ObjectNameArg())
+            .about("Show a function with its status, pods and snapshots")
+    }
+
+    pub async fn Run(&self, gConfig: &GlobalConfig) -> Result<()> {
+        let client = gConfig.GetObjectClient();
+
+        let func: Function = client
+            .Get(Function::KEY, &self.tenant, &self.namespace, &self.name)
+            .await?
+            .To::<FuncObject>()?;
+
+        let (pods, snapshots) =
+            FuncPodsAndSnapshots(&client, &self.tenant, &self.namespace, &self.name).await?;
+
+        DescribeFunction(&func, &pods, &snapshots);
+        Ok(())
+    }
+}
+
+// FuncPodsAndSnapshots returns the pods and snapshots of all the revisions of a
+// function, the snapshots sorted by node.
+pub async fn FuncPodsAndSnapshots(
+    client: &ObjectClient,
+    tenant: &str,
+    namespace: &str,
+    funcname: &str,
+) -> Result<(Vec<FuncPod>, Vec<FuncSnapshot>)> {
+    let mut pods = Vec::new();
+    for o in client.List(FuncPod::KEY, tenant, namespace, "", "").await? {
+        let pod: FuncPod = o.To::<FuncPodObject>()?;
+        if pod.object.spec.funcname == funcname {
+            pods.push(pod);
+        }
+    }
+
+    // snapshot funckey is <tenant>/<namespace>/<funcname>/<revision>
+    let prefix = format!("{}/{}/{}/", tenant, namespace, funcname);
+    let mut snapshots = Vec::new();
+    for o in client
+        .List(ContainerSnapshot::KEY, tenant, namespace, "", "")
+        .await?
+    {
+        let snapshot: FuncSnapshot = o.To::<ContainerSnapshot>()?;
+        if snapshot.object.funckey.starts_with(&prefix) {
+            snapshots.push(snapshot);
+        }
+    }
+    snapshots.sort_by(|a, b| a.object.nodename.cmp(&b.object.nodename));
+
+    Ok((pods, snapshots))
+}
+
+pub fn DescribeFunction(func: &Function, pods: &[FuncPod], snapshots: &[FuncSnapshot]) {
+    let spec = &func.object.spec;
+    let status = &func.object.status;
+
+    let mut rows = vec![
+        Field("Name:", &func.Key()),
+        Field("Revision:", &format!("{}", func.Version())),
+        Field("Labels:", &func.labels.String()),
+        Field("Image:", &spec.image),
+        Field("Commands:", &spec.commands.join(" ")),
+        Field("Entrypoint:", &spec.entrypoint.join(" ")),
+    ];
+    for (i, (name, value)) in spec.envs.iter().enumerate() {
+        let label = if i == 0 { "Envs:" } else { "" };
+        rows.push(Field(label, &format!("{}={}", name, value)));
+    }
+    for (i, m) in spec.mounts.iter().enumerate() {
+        let label = if i == 0 { "Mounts:" } else { "" };
+        rows.push(Field(
+            label,
+            &format!("{} -> {}", &m.hostpath, &m.mountpath),
+        ));
+    }
+    rows.append(&mut vec![
+        Field(
+            "Endpoint:",
+            &format!(
+                "{:?} port {} probe {}",
+                spec.endpoint.schema, spec.endpoint.port, &spec.endpoint.probe
+            ),
+        ),
+        Field(
+            "Resources:",
+            &format!(
+                "CPU {}m, Mem {}MB, GPU {} x {}MB",
+                spec.resources.cpu,
+                spec.resources.memory,
+                spec.resources.gpu.gpuCount,
+                spec.resources.gpu.vRam
+            ),
+        ),
+        Field(
+            "Standby:",
+            &format!(
+                "gpu {:?}, pageable {:?}, pinned {:?}",
+                spec.standby.gpuMem, spec.standby.pageableMem, spec.standby.pinndMem
+            ),
+        ),
+        Field("State:", &format!("{:?}", status.state)),
+        Field(
+            "Snapshoting failures:",
+            &format!("{}", status.snapshotingFailureCnt),
+        ),
+        Field(
+            "Resuming failures:",
+            &format!("{}", status.resumingFailureCnt),
+        ),
+    ]);
+    PrintRows(&rows, "");
+
+    println!();
+    println!("Pods ({}):", pods.len());
+    if !pods.is_empty() {
+        let mut rows = vec![Row(&["NAME", "REVISION", "STATE", "NODE", "GPUS", "VRAM"])];
+        for pod in pods {
+            let spec = &pod.object.spec;
+            let gpus: Vec<String> = spec
+                .allocResources
+                .gpus
+                .map
+                .keys()
+                .map(|id| format!("{}", id))
+                .collect();
+            rows.push(vec![
+                pod.name.clone(),
+                format!("{}", spec.fprevision),
+                format!("{:?}", pod.object.status.state),
+                spec.nodename.clone(),
+                gpus.join(","),
+                HumanSize(spec.allocResources.gpus.FirstVRam()),
+            ]);
+        }
+        PrintRows(&rows, "  ");
+    }
+
+    println!();
+    println!("Snapshots ({}):", snapshots.len());
+    if !snapshots.is_empty() {
+        let mut rows = vec![Row(&[
+            "NODE", "FUNCKEY", "STATE", "GPU", "PAGEABLE", "PINNED",
+        ])];
+        for snapshot in snapshots {
+            let info = &snapshot.object.info;
+            let sizes = info.SnapshotStandyInfo();
+            rows.push(vec![
+                snapshot.object.nodename.clone(),
+                snapshot.object.funckey.clone(),
+                format!("{:?}", snapshot.object.state),
+                format!("{} ({:?})", HumanSize(sizes.gpu), info.standby.gpuMem),
+                format!(
+                    "{} ({:?})",
+                    HumanSize(sizes.pageable),
+                    info.standby.pageableMem
+                ),
+                format!("{} ({:?})", HumanSize(sizes.pinned), info.standby.pinndMem),
+            ]);
+        }
+        PrintRows(&rows, "  ");
+    }
+
+    println!();
+    println!("Sample call:");
+    println!("{}", func.SampleRestCall());
+}
+
+fn Field(label: &str, value: &str) -> Vec<String> {
+    vec![label.to_owned(), value.to_owned()]
+}
+
+fn Row(cells: &[&str]) -> Vec<String> {
+    cells.iter().map(|s| s.to_string()).collect()
+}
@@ -0,0 +1,139 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use serde_json::Value;
+
+use inferxlib::common::*;
+use inferxlib::data_obj::{Classify, DiffClass, ValueDiff};
+
+use crate::apply::LoadObjects;
+use crate::command::GlobalConfig;
+
+#[derive(Debug)]
+pub struct DiffCmd {
+    pub filenames: Vec<String>,
+}
+
+impl DiffCmd {
+    pub fn Init(cmd_matches: &ArgMatches) -> Result<Self> {
+        Ok(Self {
+            filenames: cmd_matches
+                .values_of("filename")
+                .unwrap()
+                .map(|s| s.to_string())
+                .collect(),
+        })
+    }
+
+    pub fn SubCommand<'a, 'b>() -> App<'a, 'b> {
+        SubCommand::with_name("diff")
+            .setting(AppSettings::ColoredHelp)
+            .arg(
+                Arg::with_name("filename")
+                    .long("filename")
+                    .short("f")
+                    .required(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .help("object file, directory of object files, or - for stdin")
+                    .takes_value(true),
+            )
+            .about("Show the differences of the deployed objects from the files, ! marks the function changes which need a new snapshot")
+    }
+
+    pub async fn Run(&self, gConfig: &GlobalConfig) -> Result<()> {
+        let objs = LoadObjects(&self.filenames)?;
+
+        let client = gConfig.GetObjectClient();
+        let (mut changedCnt, mut resnapshotCnt, mut newCnt) = (0, 0, 0);
+        for obj in &objs {
+            let id = format!("{} {}", &obj.objType, obj.Key());
+            let live = match client
+                .Get(&obj.objType, &obj.tenant, &obj.namespace, &obj.name)
+                .await
+            {
+                Err(Error::NotExist(_)) => {
+                    println!("{}: would be created", id);
+                    newCnt += 1;
+                    continue;
+                }
+                Err(e) => return Err(e),
+                Ok(o) => o,
+            };
+
+            let diffs = ClassifiedDiffs(&obj.objType, live.Diff(obj)?);
+            if diffs.is_empty() {
+                println!("{}: no changes", id);
+                continue;
+            }
+
+            let resnapshot = diffs[0].0 == DiffClass::Resnapshot;
+            changedCnt += 1;
+            if resnapshot {
+                resnapshotCnt += 1;
+            }
+            println!(
+                "{}: {} changes{}",
+                id,
+                diffs.len(),
+                if resnapshot {
+                    ", needs a new snapshot"
+                } else {
+                    ""
+                }
+            );
+            PrintDiffs(&diffs);
+        }
+
+        println!(
+            "{} changed ({} need a new snapshot), {} new, {} unchanged",
+            changedCnt,
+            resnapshotCnt,
+            newCnt,
+            objs.len() - changedCnt - newCnt
+        );
+        Ok(())
+    }
+}
+
+// ClassifiedDiffs classifies the diffs of an object of objType, the changes needing a
+// new snapshot first.
+pub fn ClassifiedDiffs(objType: &str, diffs: Vec<ValueDiff>) -> Vec<(DiffClass, ValueDiff)> {
+    let mut diffs: Vec<(DiffClass, ValueDiff)> = diffs
+        .into_iter()
+        .map(|d| (Classify(objType, &d.path), d))
+        .collect();
+    diffs.sort_by_key(|d| d.0);
+    diffs
+}
+
+pub fn PrintDiffs(diffs: &[(DiffClass, ValueDiff)]) {
+    for (class, d) in diffs {
+        println!(
+            "  {} {}: {} -> {}",
+            class.Mark(),
+            &d.path,
+            DiffValueString(&d.old),
+            DiffValueString(&d.new)
+        );
+    }
+}
+
+fn DiffValueString(v: &Option<Value>) -> String {
+    match v {
+        None => "<none>".to_owned(),
+        Some(v) => serde_json::to_string(v).unwrap_or_default(),
+    }
+}
@@ -17,7 +17,10 @@ use serde_json::Value;
 
 use inferxlib::common::*;
 
-use crate::command::GlobalConfig;
+use crate::command::{
+    GlobalConfig, NamespaceArg, ObjectNameArg, ParseObjectName, TenantArg, SUPPORT_OBJ_TYPES,
+};
+use crate::output::{OutputFormat, PrintObjects, WatchPrinter};
 
 #[derive(Debug)]
 pub struct GetCmd {
@@ -25,6 +28,8 @@ pub struct GetCmd {
     pub tenant: String,
     pub namespace: String,
     pub name: String,
+    pub output: OutputFormat,
+    pub watch: bool,
 }
 
 #[derive(Debug)]
@@ -35,50 +40,61 @@ pub struct ObjectMeta {
 
 impl GetCmd {
     pub fn Init(cmd_matches: &ArgMatches) -> Result<Self> {
-        return Ok(Self {
+        let (tenant, namespace, name) = ParseObjectName(cmd_matches);
+        Ok(Self {
             objType: cmd_matches.value_of("type").unwrap().to_string(),
-            tenant: cmd_matches.value_of("tenant").unwrap().to_string(),
-            namespace: cmd_matches.value_of("namespace").unwrap().to_string(),
-            name: cmd_matches.value_of("name").unwrap().to_string(),
-        });
+            tenant,
+            namespace,
+            name,
+            output: OutputFormat::Init(cmd_matches)?,
+            watch: cmd_matches.is_present("watch"),
+        })
     }
 
     pub fn SubCommand<'a, 'b>() -> App<'a, 'b> {
-        return SubCommand::with_name("get")
+        SubCommand::with_name("get")
             .setting(AppSettings::ColoredHelp)
             .arg(
                 Arg::with_name("type")
                     .required(true)
                     .help("object type")
+                    .possible_values(&SUPPORT_OBJ_TYPES)
                     .takes_value(true),
             )
+            .arg(TenantArg())
+            .arg(NamespaceArg())
+            .arg(ObjectNameArg())
+            .arg(OutputFormat::Arg())
             .arg(
-                Arg::with_name("tenant")
-                    .required(true)
-                    .help("object tenant")
-                    // .long("tenant")
-                    // .short("t")
-                    .takes_value(true),
+                Arg::with_name("watch")
+                    .long("watch")
+                    .short("w")
+                    .help("keep printing the changes of the object"),
             )
-            .arg(
-                Arg::with_name("namespace")
-                    .required(true)
-                    .help("object namespace")
-                    // .long("namespace")
-                    // .short("ns")
-                    .takes_value(true),
-            )
-            .arg(
-                Arg::with_name("name")
-                    .required(true)
-                    .help("object name")
-                    .takes_value(true),
-            )
-            .about("get a object");
+            .about("get a object")
     }
 
     pub async fn Run(&self, gConfig: &GlobalConfig) -> Result<()> {
         let client = gConfig.GetObjectClient();
+        if self.watch {
+            let mut stream = client
+                .Watch(
+                    &self.objType,
+                    &self.tenant,
+                    &self.namespace,
+                    "",
+                    &format!("name={}", &self.name),
+                )
+                .await?;
+            let mut printer = WatchPrinter::New(&self.output)?;
+            while let Some(event) = stream.Next().await? {
+                if event.obj.name == self.name {
+                    printer.Print(&event)?;
+                }
+            }
+            return Ok(());
+        }
+
         let obj = match client
             .Get(&self.objType, &self.tenant, &self.namespace, &self.name)
             .await
@@ -90,8 +106,6 @@ impl GetCmd {
             Ok(obj) => obj,
         };
 
-        println!("{:#?}", obj);
-
-        return Ok(());
+        PrintObjects(&[obj], &self.output, false)
     }
 }
@@ -0,0 +1,221 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use serde_json::Value;
+use std::io::Write;
+
+use inferxlib::common::*;
+use inferxlib::func_api::{FuncRequest, FuncResponse, SseDecoder, StreamChunk};
+use inferxlib::obj_mgr::func_mgr::{BodyValue, FuncObject, Function};
+
+use crate::command::{GlobalConfig, TenantArg};
+
+#[derive(Debug)]
+pub struct InvokeCmd {
+    pub tenant: String,
+    pub namespace: String,
+    pub funcname: String,
+    pub prompt: Option<String>,
+    pub path: Option<String>,
+    // body fields overriding the sample call ones
+    pub sets: Vec<(String, Value)>,
+    pub outputFile: Option<String>,
+}
+
+impl InvokeCmd {
+    pub fn Init(cmd_matches: &ArgMatches) -> Result<Self> {
+        // the function is <namespace>/<funcname>, or <funcname> in the context namespace
+        let func = cmd_matches.value_of("function").unwrap();
+        let (namespace, funcname) = match func.split_once('/') {
+            Some((ns, name)) => (ns.to_owned(), name.to_owned()),
+            None => (String::new(), func.to_owned()),
+        };
+
+        let sets = cmd_matches
+            .values_of("set")
+            .unwrap_or_default()
+            .map(ParseSet)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            tenant: cmd_matches
+                .value_of("tenant")
+                .unwrap_or_default()
+                .to_string(),
+            namespace,
+            funcname,
+            prompt: cmd_matches.value_of("prompt").map(|s| s.to_owned()),
+            path: cmd_matches.value_of("path").map(|s| s.to_owned()),
+            sets,
+            outputFile: cmd_matches.value_of("output-file").map(|s| s.to_owned()),
+        })
+    }
+
+    pub fn SubCommand<'a, 'b>() -> App<'a, 'b> {
+        SubCommand::with_name("invoke")
+            .setting(AppSettings::ColoredHelp)
+            .arg(
+                Arg::with_name("function")
+                    .required(true)
+                    .help("<namespace>/<funcname>, or <funcname> in the context namespace")
+                    .takes_value(true),
+            )
+            .arg(TenantArg())
+            .arg(
+                Arg::with_name("prompt")
+                    .long("prompt")
+                    .help("prompt instead of the sample call one")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("set")
+                    .long("set")
+                    .multiple(true)
+                    .number_of_values(1)
+                    .help("request body field, e.g. max_tokens=50 or stream=false")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("path")
+                    .long("path")
+                    .help("request path instead of the sample call one, e.g. v1/completions")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("output-file")
+                    .long("output-file")
+                    .help("file to save the text2img image, <funcname>.png by default")
+                    .takes_value(true),
+            )
+            .about("Call a deployed function with its sample call")
+    }
+
+    pub async fn Run(&self, gConfig: &GlobalConfig) -> Result<()> {
+        let client = gConfig.GetObjectClient();
+        let func: Function = client
+            .Get(Function::KEY, &self.tenant, &self.namespace, &self.funcname)
+            .await?
+            .To::<FuncObject>()?;
+
+        let sample = &func.object.spec.sampleCall;
+        let mut body = sample.RequestBody();
+        if let Some(prompt) = &self.prompt {
+            body.insert("prompt".to_owned(), Value::String(prompt.clone()));
+        }
+        for (k, v) in &self.sets {
+            body.insert(k.clone(), v.clone());
+        }
+        let path = self.path.as_ref().unwrap_or(&sample.path);
+        let req = FuncRequest::New(&sample.apiType, path, body)?;
+
+        let mut resp = client
+            .FuncCall(
+                &gConfig.accessToken,
+                &self.tenant,
+                &self.namespace,
+                &self.funcname,
+                path,
+                &req.ToValue()?,
+            )
+            .await?;
+
+        if !req.IsStream() {
+            match req.DecodeResponse(&resp.bytes().await?)? {
+                FuncResponse::Image(image) => {
+                    let filename = match &self.outputFile {
+                        Some(f) => f.clone(),
+                        None => format!("{}.png", &self.funcname),
+                    };
+                    std::fs::write(&filename, &image)?;
+                    println!("image saved to {} ({} bytes)", &filename, image.len());
+                }
+                FuncResponse::Json(v) => println!("{}", serde_json::to_string_pretty(&v)?),
+                r => println!("{}", r.Text().unwrap_or_default()),
+            }
+            return Ok(());
+        }
+
+        let mut decoder = SseDecoder::New();
+        let mut stdout = std::io::stdout();
+        loop {
+            let (events, end) = match resp.chunk().await? {
+                Some(chunk) => (decoder.Feed(&chunk), false),
+                None => (decoder.Finish().into_iter().collect(), true),
+            };
+            for data in events {
+                match req.DecodeChunk(&data)? {
+                    StreamChunk::Done => {
+                        println!();
+                        return Ok(());
+                    }
+                    StreamChunk::Text(text) => {
+                        stdout.write_all(text.as_bytes())?;
+                        stdout.flush()?;
+                    }
+                }
+            }
+            if end {
+                break;
+            }
+        }
+        println!();
+        Ok(())
+    }
+}
+
+// ParseSet parses a --set key=value into the body field, see BodyValue for the value
+// types.
+pub fn ParseSet(set: &str) -> Result<(String, Value)> {
+    match set.split_once('=') {
+        Some((k, v)) if !k.is_empty() => Ok((k.to_owned(), BodyValue(k, v))),
+        _ => {
+            Err(Error::CommonError(format!(
+                "invalid --set {:?}, expect key=value",
+                set
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn SetValues() {
+        let set = |s: &str| ParseSet(s).unwrap();
+        assert_eq!(set("max_tokens=50"), ("max_tokens".to_owned(), json!(50)));
+        assert_eq!(
+            set("temperature=0.7"),
+            ("temperature".to_owned(), json!(0.7))
+        );
+        assert_eq!(set("stream=false"), ("stream".to_owned(), json!(false)));
+        assert_eq!(set("stream=true"), ("stream".to_owned(), json!(true)));
+        // the model name stays a string even if it looks like a number
+        assert_eq!(set("model=123"), ("model".to_owned(), json!("123")));
+        assert_eq!(set("prompt=a=b"), ("prompt".to_owned(), json!("a=b")));
+        assert_eq!(set("prompt="), ("prompt".to_owned(), json!("")));
+        // a value which doesn't parse as the field's type is kept as given
+        assert_eq!(
+            set("max_tokens=many"),
+            ("max_tokens".to_owned(), json!("many"))
+        );
+        assert_eq!(set("stream=yes"), ("stream".to_owned(), json!("yes")));
+
+        assert!(ParseSet("max_tokens").is_err());
+        assert!(ParseSet("=50").is_err());
+    }
+}
@@ -16,14 +16,25 @@ use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 use serde_json::Value;
 
 use inferxlib::common::*;
+use inferxlib::obj_mgr::namespace_mgr::Namespace;
+use inferxlib::obj_mgr::tenant_mgr::SYSTEM_NAMESPACE;
+use inferxlib::selector::{FieldSelector, Selector};
 
-use crate::command::GlobalConfig;
+use crate::command::{
+    GlobalConfig, NamespaceArg, ParseScope, ScopeArg, TenantArg, SUPPORT_OBJ_TYPES,
+};
+use crate::output::{OutputFormat, PrintObjects, WatchPrinter};
 
 #[derive(Debug)]
 pub struct ListCmd {
     pub objType: String,
     pub tenant: String,
     pub namespace: String,
+    pub selector: Selector,
+    pub fieldSelector: FieldSelector,
+    pub output: OutputFormat,
+    pub watch: bool,
+    pub allNamespaces: bool,
 }
 
 #[derive(Debug)]
@@ -34,56 +45,127 @@ pub struct ObjectMeta {
 
 impl ListCmd {
     pub fn Init(cmd_matches: &ArgMatches) -> Result<Self> {
-        return Ok(Self {
+        let (tenant, namespace) = ParseScope(cmd_matches);
+        let allNamespaces = cmd_matches.is_present("all-namespaces");
+        if allNamespaces && !namespace.is_empty() {
+            return Err(Error::CommonError(
+                "a namespace can't be used with --all-namespaces".to_owned(),
+            ));
+        }
+
+        Ok(Self {
             objType: cmd_matches.value_of("type").unwrap().to_string(),
-            tenant: cmd_matches.value_of("tenant").unwrap().to_string(),
-            namespace: cmd_matches.value_of("namespace").unwrap().to_string(),
-        });
+            tenant,
+            namespace,
+            selector: Selector::Parse(cmd_matches.value_of("selector").unwrap_or_default())?,
+            fieldSelector: FieldSelector::Parse(
+                cmd_matches.value_of("field-selector").unwrap_or_default(),
+            )?,
+            output: OutputFormat::Init(cmd_matches)?,
+            watch: cmd_matches.is_present("watch"),
+            allNamespaces,
+        })
     }
 
     pub fn SubCommand<'a, 'b>() -> App<'a, 'b> {
-        return SubCommand::with_name("list")
+        SubCommand::with_name("list")
             .setting(AppSettings::ColoredHelp)
             .arg(
                 Arg::with_name("type")
                     .required(true)
                     .help("object type")
+                    .possible_values(&SUPPORT_OBJ_TYPES)
                     .takes_value(true),
             )
+            .arg(ScopeArg())
+            .arg(TenantArg())
+            .arg(NamespaceArg())
             .arg(
-                Arg::with_name("tenant")
-                    .required(true)
-                    .help("object tenant")
-                    // .long("tenant")
-                    // .short("t")
+                Arg::with_name("selector")
+                    .help("label selector, e.g. 'team=nlp,tier in (prod,staging)'")
+                    .long("selector")
+                    .short("l")
                     .takes_value(true),
             )
             .arg(
-                Arg::with_name("namespace")
-                    .required(true)
-                    .help("object namespace")
-                    // .long("namespace")
-                    // .short("ns")
+                Arg::with_name("field-selector")
+                    .help("field selector over the object json, e.g. 'object.spec.resources.GPU.Count>1'")
+                    .long("field-selector")
                     .takes_value(true),
             )
-            .about("list objects");
+            .arg(OutputFormat::Arg())
+            .arg(
+                Arg::with_name("watch")
+                    .long("watch")
+                    .short("w")
+                    .help("after listing, keep printing the changes of the objects"),
+            )
+            .arg(
+                Arg::with_name("all-namespaces")
+                    .long("all-namespaces")
+                    .short("A")
+                    .conflicts_with_all(&["namespace", "watch"])
+                    .help("list the objects of all the namespaces in the tenant"),
+            )
+            .about("list objects")
     }
 
     pub async fn Run(&self, gConfig: &GlobalConfig) -> Result<()> {
         let client = gConfig.GetObjectClient();
-        let objList = match client
-            .List(&self.objType, &self.tenant, &self.namespace)
-            .await
-        {
-            Err(e) => {
-                println!("doesn't find obj with {:#?}", e);
-                return Ok(());
+        if self.watch {
+            let mut stream = client
+                .Watch(
+                    &self.objType,
+                    &self.tenant,
+                    &self.namespace,
+                    &self.selector.String(),
+                    &self.fieldSelector.String(),
+                )
+                .await?;
+            let mut printer = WatchPrinter::New(&self.output)?;
+            while let Some(event) = stream.Next().await? {
+                // the gateway may not filter by label or field, filter again locally
+                let o = &event.obj;
+                if self.selector.Match(&o.labels) && self.fieldSelector.MatchObject(o) {
+                    printer.Print(&event)?;
+                }
             }
-            Ok(obj) => obj,
+            return Ok(());
+        }
+
+        let namespaces = if self.allNamespaces {
+            client
+                .List(Namespace::KEY, &self.tenant, SYSTEM_NAMESPACE, "", "")
+                .await?
+                .into_iter()
+                .map(|o| o.name)
+                .collect()
+        } else {
+            vec![self.namespace.clone()]
         };
 
-        println!("{:#?}", objList);
+        let mut objList = Vec::new();
+        for namespace in &namespaces {
+            match client
+                .List(
+                    &self.objType,
+                    &self.tenant,
+                    namespace,
+                    &self.selector.String(),
+                    &self.fieldSelector.String(),
+                )
+                .await
+            {
+                Err(e) => {
+                    println!("doesn't find obj in {} with {:#?}", namespace, e);
+                }
+                Ok(mut objs) => objList.append(&mut objs),
+            };
+        }
+
+        // the gateway may not filter by label or field, filter again locally
+        objList.retain(|o| self.selector.Match(&o.labels) && self.fieldSelector.MatchObject(o));
 
-        return Ok(());
+        PrintObjects(&objList, &self.output, true)
     }
 }
@@ -23,21 +23,34 @@ extern crate log;
 #[macro_use]
 extern crate clap;
 
+pub mod apply;
 pub mod command;
+pub mod config;
 pub mod create;
 // pub mod create_pypackage;
 pub mod delete;
+pub mod describe;
+pub mod diff;
 pub mod get;
+pub mod invoke;
 pub mod list;
 pub mod object_client;
+pub mod output;
+pub mod patch;
+pub mod rollout;
+pub mod simulate;
 pub mod update;
+pub mod validate;
 
 use oauth2::{
-    basic::BasicClient, reqwest::async_http_client, AuthUrl, ClientId, ClientSecret,
-    ResourceOwnerPassword, ResourceOwnerUsername, TokenResponse, TokenUrl,
+    basic::{BasicClient, BasicTokenResponse},
+    reqwest::async_http_client,
+    AuthUrl, ClientId, ClientSecret, RefreshToken, ResourceOwnerPassword, ResourceOwnerUsername,
+    TokenResponse, TokenUrl,
 };
 
 use command::{Parse, Run};
+use config::{AuthMethod, CachedToken, ContextConfig, Now, TokenCache, KEYCLOAK_CLIENT_ID};
 use inferxlib::common::*;
 
 pub struct ClientConfig {
@@ -46,16 +59,17 @@ pub struct ClientConfig {
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let accessToken = AuthN().await;
     let mut args = match Parse() {
         Ok(args) => args,
         Err(e) => {
             error!("the parse error is {:?}", e);
-            panic!("exitting...")
+            return Err(e);
         }
     };
 
-    args.gConfig.accessToken = accessToken;
+    if args.cmd.NeedGateway() {
+        args.gConfig.accessToken = AuthN(&args.gConfig.context).await?;
+    }
 
     Run(&mut args).await?;
 
@@ -63,86 +77,155 @@ async fn main() -> Result<()> {
 }
 
 pub struct Credential {
-    pub secret: String,
     pub username: String,
     pub password: String,
 }
 
-pub enum Authorization {
-    Credential(Credential),
-    Apikey(String),
-    None,
-}
-
-fn GetCred() -> Authorization {
-    match std::env::var("IFERX_APIKEY") {
-        Ok(s) => return Authorization::Apikey(s),
-        Err(_) => (),
-    };
-
-    let secret = match std::env::var("IFERX_SECRET") {
-        Ok(s) => s,
-        Err(_) => return Authorization::None,
-    };
-
+// GetCred returns the keycloak user credential from the environment variables.
+fn GetCred() -> Option<Credential> {
     let username = match std::env::var("IFERX_USERNAME") {
         Ok(s) => s,
-        Err(_) => return Authorization::None,
+        Err(_) => return None,
     };
 
     let password = match std::env::var("IFERX_PASSWORD") {
         Ok(s) => s,
-        Err(_) => return Authorization::None,
+        Err(_) => return None,
     };
 
-    let credential = Credential {
-        secret: secret,
-        username: username,
-        password: password,
-    };
+    Some(Credential {
+        username,
+        password,
+    })
+}
 
-    return Authorization::Credential(credential);
+// GetSecret returns the keycloak client secret, IFERX_SECRET overrides the context.
+fn GetSecret(context: &ContextConfig) -> Option<String> {
+    match std::env::var("IFERX_SECRET") {
+        Ok(s) => Some(s),
+        Err(_) if !context.clientSecret.is_empty() => Some(context.clientSecret.clone()),
+        Err(_) => None,
+    }
 }
 
-async fn AuthN() -> String {
-    let keycloakUrl = match std::env::var("KEYCLOAK_URL") {
-        Ok(s) => s,
-        Err(_) => "http://localhost:1260/authn".to_owned(),
+// AuthN returns the access token for the gateway. An apikey is used as is. Otherwise
+// the keycloak token is taken from the token cache, refreshed with the cached refresh
+// token once expired, and only requested with the user password as the last resort.
+async fn AuthN(context: &ContextConfig) -> Result<String> {
+    let apikey = std::env::var("IFERX_APIKEY").ok();
+    match (context.auth, apikey) {
+        (AuthMethod::None, _) => return Ok(String::new()),
+        (AuthMethod::Apikey, None) => {
+            return Err(Error::CommonError(
+                "auth method is apikey but IFERX_APIKEY is not set".to_owned(),
+            ))
+        }
+        (AuthMethod::Apikey, Some(k)) | (AuthMethod::Auto, Some(k)) => return Ok(k),
+        _ => (),
+    }
+
+    let required = context.auth == AuthMethod::Password;
+    let mut cache = TokenCache::Load();
+    // the token is cached per user, IFERX_USERNAME selects whose token is used
+    let username = std::env::var("IFERX_USERNAME").unwrap_or_default();
+    let key = context.CacheKey(&username);
+    let cached = cache.tokens.get(&key).cloned().unwrap_or_default();
+    if !cached.accessToken.is_empty() && cached.IsValid() {
+        return Ok(cached.accessToken);
+    }
+
+    let secret = match GetSecret(context) {
+        Some(s) => s,
+        None if required => return Err(Error::CommonError(
+            "auth method is password but neither IFERX_SECRET nor the context client secret is set"
+                .to_owned(),
+        )),
+        None => return Ok(String::new()),
     };
 
-    println!("keycloakUrl is {}", &keycloakUrl);
-    match GetCred() {
-        Authorization::None => return String::new(),
-        Authorization::Apikey(k) => return k,
-        Authorization::Credential(cred) => {
-            let client = BasicClient::new(
-                ClientId::new("infer_client".to_string()),
-                Some(ClientSecret::new(cred.secret)),
-                AuthUrl::new(format!(
-                    "{}/realms/inferx/protocol/openid-connect/auth",
-                    &keycloakUrl
-                ))
-                .unwrap(),
-                Some(
-                    TokenUrl::new(format!(
-                        "{}/realms/inferx/protocol/openid-connect/token",
-                        &keycloakUrl
-                    ))
-                    .unwrap(),
-                ),
-            );
-
-            let token_result = client
-                .exchange_password(
-                    &ResourceOwnerUsername::new(cred.username),
-                    &ResourceOwnerPassword::new(cred.password),
-                )
-                .request_async(async_http_client)
-                .await
-                .expect("Failed to obtain access token");
-
-            println!("Access Token: {:?}", token_result.access_token().secret());
-            return token_result.access_token().secret().clone();
+    let keycloakUrl = context.KeycloakUrl();
+    let realm = context.Realm();
+    debug!("keycloakUrl is {} realm {}", &keycloakUrl, &realm);
+    let client = BasicClient::new(
+        ClientId::new(KEYCLOAK_CLIENT_ID.to_string()),
+        Some(ClientSecret::new(secret)),
+        AuthUrl::new(format!(
+            "{}/realms/{}/protocol/openid-connect/auth",
+            &keycloakUrl, &realm
+        ))
+        .map_err(|e| Error::CommonError(format!("invalid keycloak url: {}", e)))?,
+        Some(
+            TokenUrl::new(format!(
+                "{}/realms/{}/protocol/openid-connect/token",
+                &keycloakUrl, &realm
+            ))
+            .map_err(|e| Error::CommonError(format!("invalid keycloak url: {}", e)))?,
+        ),
+    );
+
+    if !cached.refreshToken.is_empty() {
+        match client
+            .exchange_refresh_token(&RefreshToken::new(cached.refreshToken.clone()))
+            .request_async(async_http_client)
+            .await
+        {
+            Ok(token) => return CacheToken(&mut cache, &key, &token, &cached),
+            // the refresh token may be expired or revoked, login again
+            Err(e) => debug!("refresh token fail {}", e),
         }
     }
+
+    let cred =
+        match GetCred() {
+            Some(c) => c,
+            None if required || !cached.refreshToken.is_empty() => return Err(Error::CommonError(
+                "the cached token is expired, set IFERX_USERNAME and IFERX_PASSWORD to login again"
+                    .to_owned(),
+            )),
+            None => return Ok(String::new()),
+        };
+
+    let token = client
+        .exchange_password(
+            &ResourceOwnerUsername::new(cred.username),
+            &ResourceOwnerPassword::new(cred.password),
+        )
+        .request_async(async_http_client)
+        .await
+        .map_err(|e| {
+            Error::CommonError(format!(
+                "fail to obtain access token from {}: {}",
+                &keycloakUrl, e
+            ))
+        })?;
+
+    CacheToken(&mut cache, &key, &token, &cached)
+}
+
+// CacheToken saves the token, keeping the old refresh token if keycloak doesn't
+// return a new one. Failing to save only costs a new login next time.
+fn CacheToken(
+    cache: &mut TokenCache,
+    key: &str,
+    token: &BasicTokenResponse,
+    old: &CachedToken,
+) -> Result<String> {
+    let cached = CachedToken {
+        accessToken: token.access_token().secret().clone(),
+        expiresAt: match token.expires_in() {
+            Some(d) => Now() + d.as_secs(),
+            None => 0,
+        },
+        refreshToken: match token.refresh_token() {
+            Some(t) => t.secret().clone(),
+            None => old.refreshToken.clone(),
+        },
+    };
+
+    let accessToken = cached.accessToken.clone();
+    cache.tokens.insert(key.to_owned(), cached);
+    if let Err(e) = cache.Save() {
+        warn!("fail to save token cache {:?}", e);
+    }
+    Ok(accessToken)
 }
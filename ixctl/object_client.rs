@@ -1,5 +1,5 @@
 use hyper::StatusCode;
-use inferxlib::data_obj::DataObject;
+use inferxlib::data_obj::{DataObject, PatchType, WatchEvent};
 use reqwest::header::{HeaderMap, HeaderValue};
 use reqwest::Client;
 use std::time::Duration;
@@ -13,17 +13,99 @@ pub struct ObjectClient {
 
 impl ObjectClient {
     pub fn New(url: &str) -> Self {
-        return Self {
+        Self {
             url: url.to_owned(),
-        };
+        }
     }
 
     pub fn Client(&self) -> Client {
-        let client = reqwest::Client::builder()
+        reqwest::Client::builder()
             .timeout(Duration::from_secs(2))
             .build()
+            .unwrap()
+    }
+
+    // Watch opens a watch stream on the objects of a type in a namespace. The gateway sends
+    // the existing objects as Added events, then InitDone, then the changes as they happen.
+    pub async fn Watch(
+        &self,
+        objType: &str,
+        tenant: &str,
+        namespace: &str,
+        labelSelector: &str,
+        fieldSelector: &str,
+    ) -> Result<WatchStream> {
+        // no request timeout, the stream stays open until the gateway or user closes it
+        let client = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(2))
+            .build()
             .unwrap();
-        return client;
+
+        let url = format!("{}/watch/{objType}/{tenant}/{namespace}/", &self.url);
+        let mut req = client.get(&url);
+        if !labelSelector.is_empty() {
+            req = req.query(&[("labelSelector", labelSelector)]);
+        }
+        if !fieldSelector.is_empty() {
+            req = req.query(&[("fieldSelector", fieldSelector)]);
+        }
+
+        let resp = req.send().await?;
+        let code = resp.status().as_u16();
+        if code != StatusCode::OK {
+            let content = resp.text().await.ok();
+            return Err(Error::CommonError(format!(
+                "Watch fail with resp code {} content {:?}",
+                code, content
+            )));
+        }
+
+        Ok(WatchStream {
+            resp,
+            buf: Vec::new(),
+        })
+    }
+
+    // FuncCall posts the body to the path of a deployed function. There is no request
+    // timeout as the function may be resumed from a snapshot and stream for a long time.
+    pub async fn FuncCall(
+        &self,
+        token: &str,
+        tenant: &str,
+        namespace: &str,
+        funcname: &str,
+        path: &str,
+        body: &Value,
+    ) -> Result<reqwest::Response> {
+        let client = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(2))
+            .build()
+            .unwrap();
+
+        let url = format!(
+            "{}/funccall/{tenant}/{namespace}/{funcname}/{}",
+            &self.url,
+            path.trim_start_matches('/')
+        );
+        let mut headers = HeaderMap::new();
+        if !token.is_empty() {
+            headers.insert(
+                "Authorization",
+                HeaderValue::from_str(&format!("Bearer {token}")).unwrap(),
+            );
+        }
+
+        let resp = client.post(&url).headers(headers).json(body).send().await?;
+        let code = resp.status().as_u16();
+        if code != StatusCode::OK {
+            let content = resp.text().await.ok();
+            return Err(Error::CommonError(format!(
+                "FuncCall fail with resp code {} content {:?}",
+                code, content
+            )));
+        }
+
+        Ok(resp)
     }
 
     pub async fn Get(
@@ -37,9 +119,22 @@ impl ObjectClient {
             "{}/object/{objType}/{tenant}/{namespace}/{name}/",
             &self.url
         );
-        let body = self.Client().get(&url).send().await.unwrap().text().await?;
+        let resp = self.Client().get(&url).send().await?;
+        let code = resp.status().as_u16();
+        let body = resp.text().await?;
+        if code == StatusCode::NOT_FOUND {
+            return Err(Error::NotExist(format!(
+                "Get {objType} {tenant}/{namespace}/{name}"
+            )));
+        }
+        if code != StatusCode::OK {
+            return Err(Error::CommonError(format!(
+                "Get fail with resp code {} content {:?}",
+                code, body
+            )));
+        }
         let obj = serde_json::from_str(&body)?;
-        return Ok(obj);
+        Ok(obj)
     }
 
     pub async fn List(
@@ -47,14 +142,59 @@ impl ObjectClient {
         objType: &str,
         tenant: &str,
         namespace: &str,
+        labelSelector: &str,
+        fieldSelector: &str,
     ) -> Result<Vec<DataObject<Value>>> {
         let client = self.Client();
 
         let url = format!("{}/objects/{objType}/{tenant}/{namespace}/", &self.url);
-        error!("url is {:?}", &url);
-        let body = client.get(&url).send().await?.text().await?;
+        let mut req = client.get(&url);
+        if !labelSelector.is_empty() {
+            req = req.query(&[("labelSelector", labelSelector)]);
+        }
+        if !fieldSelector.is_empty() {
+            req = req.query(&[("fieldSelector", fieldSelector)]);
+        }
+        let body = req.send().await?.text().await?;
         let obj = serde_json::from_str(&body)?;
-        return Ok(obj);
+        Ok(obj)
+    }
+
+    // History returns the kept past versions of an object, oldest first, followed by
+    // the current one, as DataObjectMgr::History of the gateway. The gateway keeps them
+    // in memory only, so after a gateway restart only the current version is returned.
+    pub async fn History(
+        &self,
+        objType: &str,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+    ) -> Result<Vec<DataObject<Value>>> {
+        let client = self.Client();
+
+        let url = format!(
+            "{}/history/{objType}/{tenant}/{namespace}/{name}/",
+            &self.url
+        );
+        let resp = client.get(&url).send().await?;
+        let code = resp.status().as_u16();
+        if code == StatusCode::NOT_FOUND {
+            return Err(Error::NotExist(format!(
+                "History {} {}/{}/{}",
+                objType, tenant, namespace, name
+            )));
+        }
+        if code != StatusCode::OK {
+            let content = resp.text().await.ok();
+            return Err(Error::CommonError(format!(
+                "History fail with resp code {} content {:?}",
+                code, content
+            )));
+        }
+
+        let body = resp.text().await?;
+        let objs = serde_json::from_str(&body)?;
+        Ok(objs)
     }
 
     pub async fn Create(&self, token: &str, obj: DataObject<Value>) -> Result<i64> {
@@ -62,13 +202,18 @@ impl ObjectClient {
         let url = format!("{}/object/", &self.url);
         println!("Create url {}", &url);
         let mut headers = HeaderMap::new();
-        if token.len() > 0 {
+        if !token.is_empty() {
             headers.insert(
                 "Authorization",
                 HeaderValue::from_str(&format!("Bearer {token}")).unwrap(),
             );
         }
-        let resp = client.put(&url).headers(headers).json(&obj).send().await?;
+        let resp = client
+            .put(&url)
+            .headers(headers)
+            .json(&obj.CopyWithRev(0, 0))
+            .send()
+            .await?;
         let code = resp.status().as_u16();
         if code == StatusCode::OK {
             let res = resp.text().await?;
@@ -84,24 +229,47 @@ impl ObjectClient {
         }
 
         let content = resp.text().await?;
-        return Err(Error::CommonError(format!(
+        Err(Error::CommonError(format!(
             "Create fail with resp {}",
             content
-        )));
+        )))
     }
 
-    pub async fn Update(&self, token: &str, obj: DataObject<Value>) -> Result<i64> {
+    // expectRevision > 0 makes the gateway reject the update with 409 Conflict
+    // if the stored object has moved on from that revision. The revisions of obj are
+    // not sent, the gateway only checks the If-Match header.
+    pub async fn Update(
+        &self,
+        token: &str,
+        obj: DataObject<Value>,
+        expectRevision: i64,
+    ) -> Result<i64> {
         let client = self.Client();
         let url = format!("{}/object/", &self.url);
         let mut headers = HeaderMap::new();
-        if token.len() > 0 {
+        if !token.is_empty() {
             headers.insert(
                 "Authorization",
                 HeaderValue::from_str(&format!("Bearer {token}")).unwrap(),
             );
         }
-        let resp = client.post(&url).headers(headers).json(&obj).send().await?;
+        SetExpectRevision(&mut headers, expectRevision);
+        let resp = client
+            .post(&url)
+            .headers(headers)
+            .json(&obj.CopyWithRev(0, 0))
+            .send()
+            .await?;
         let code = resp.status().as_u16();
+        if code == StatusCode::CONFLICT {
+            let content = resp.text().await.ok();
+            return Err(Error::Conflict(format!(
+                "Update {} expect revision {} content {:?}",
+                obj.Key(),
+                expectRevision,
+                content
+            )));
+        }
         if code == StatusCode::OK {
             let res = resp.text().await?;
             match res.parse::<i64>() {
@@ -116,10 +284,39 @@ impl ObjectClient {
         }
 
         let content = resp.text().await.ok();
-        return Err(Error::CommonError(format!(
+        Err(Error::CommonError(format!(
             "Update fail with resp code {} content {:?}",
             code, content
-        )));
+        )))
+    }
+
+    // Patch applies a merge patch or json patch to the stored object and writes it back.
+    // The write is guarded by the revision the patch was applied to, so a concurrent
+    // change fails with Error::Conflict instead of being overwritten.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn Patch(
+        &self,
+        token: &str,
+        objType: &str,
+        tenant: &str,
+        namespace: &str,
+        name: &str,
+        patchType: PatchType,
+        patch: &Value,
+    ) -> Result<(DataObject<Value>, i64)> {
+        let stored = self.Get(objType, tenant, namespace, name).await?;
+        let expectRevision = stored.ExpectRevision()?;
+        let obj = stored.Patch(patchType, patch)?;
+        let errs = obj.Validate();
+        if !errs.IsEmpty() {
+            return Err(Error::CommonError(format!(
+                "patched object {} fails validation: {}",
+                obj.Key(),
+                errs
+            )));
+        }
+        let version = self.Update(token, obj.clone(), expectRevision).await?;
+        Ok((obj, version))
     }
 
     pub async fn Delete(
@@ -129,6 +326,7 @@ impl ObjectClient {
         tenant: &str,
         namespace: &str,
         name: &str,
+        expectRevision: i64,
     ) -> Result<i64> {
         let client = self.Client();
         let url = format!(
@@ -136,17 +334,24 @@ impl ObjectClient {
             &self.url
         );
         let mut headers = HeaderMap::new();
-        if token.len() > 0 {
+        if !token.is_empty() {
             headers.insert(
                 "Authorization",
                 HeaderValue::from_str(&format!("Bearer {token}")).unwrap(),
             );
         }
+        SetExpectRevision(&mut headers, expectRevision);
 
         let resp = client.delete(&url).headers(headers).send().await?;
         let code = resp.status().as_u16();
         let content = resp.text().await?;
         println!("Delete response is {:?} content {}", code, content);
+        if code == StatusCode::CONFLICT {
+            return Err(Error::Conflict(format!(
+                "Delete {tenant}/{namespace}/{name} expect revision {} content {}",
+                expectRevision, content
+            )));
+        }
         if code == StatusCode::OK {
             let res = content;
             match res.parse::<i64>() {
@@ -160,9 +365,56 @@ impl ObjectClient {
             }
         }
 
-        return Err(Error::CommonError(format!(
+        Err(Error::CommonError(format!(
             "Delete fail with resp http code {:?}",
             code
-        )));
+        )))
+    }
+}
+
+// SetExpectRevision asks the gateway for compare-and-swap semantics: the request only
+// applies if the stored object's revision is still expectRevision. 0 means no check.
+pub fn SetExpectRevision(headers: &mut HeaderMap, expectRevision: i64) {
+    if expectRevision > 0 {
+        headers.insert(
+            "If-Match",
+            HeaderValue::from_str(&format!("{}", expectRevision)).unwrap(),
+        );
+    }
+}
+
+// WatchStream reads the newline delimited json WatchEvents of a watch response.
+pub struct WatchStream {
+    resp: reqwest::Response,
+    buf: Vec<u8>,
+}
+
+impl WatchStream {
+    // Next returns the next event, or None when the gateway closes the stream.
+    pub async fn Next(&mut self) -> Result<Option<WatchEvent>> {
+        loop {
+            if let Some(pos) = self.buf.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = self.buf.drain(..=pos).collect();
+                let line = &line[..line.len() - 1];
+                if line.iter().all(|b| b.is_ascii_whitespace()) {
+                    continue;
+                }
+                let event: WatchEvent = serde_json::from_slice(line)?;
+                return Ok(Some(event));
+            }
+
+            match self.resp.chunk().await? {
+                Some(chunk) => self.buf.extend_from_slice(&chunk),
+                None => {
+                    if self.buf.iter().all(|b| b.is_ascii_whitespace()) {
+                        return Ok(None);
+                    }
+                    // the last event without the trailing newline
+                    let event: WatchEvent = serde_json::from_slice(&self.buf)?;
+                    self.buf.clear();
+                    return Ok(Some(event));
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,700 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Arg, ArgMatches};
+use serde_json::Value;
+
+use inferxlib::common::*;
+use inferxlib::data_obj::{DataObject, EventType, TypedObject, WatchEvent};
+use inferxlib::obj_mgr::func_mgr::Function;
+use inferxlib::obj_mgr::funcsnapshot_mgr::ContainerSnapshot;
+use inferxlib::obj_mgr::node_mgr::Node;
+use inferxlib::obj_mgr::pod_mgr::FuncPod;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputFormat {
+    Table,
+    // table with more columns
+    Wide,
+    Json,
+    Yaml,
+    // <type>/<tenant>/<namespace>/<name> per line
+    Name,
+    // jsonpath template evaluated for each object, e.g. {.object.spec.image}
+    JsonPath(String),
+}
+
+impl OutputFormat {
+    pub fn Parse(s: &str) -> Result<Self> {
+        if let Some(template) = s.strip_prefix("jsonpath=") {
+            return Ok(Self::JsonPath(template.to_owned()));
+        }
+
+        match s {
+            "table" => Ok(Self::Table),
+            "wide" => Ok(Self::Wide),
+            "json" => Ok(Self::Json),
+            "yaml" => Ok(Self::Yaml),
+            "name" => Ok(Self::Name),
+            _ => {
+                Err(Error::CommonError(format!(
+                    "unknown output format {:?}, expect table, wide, json, yaml, name or jsonpath=<template>",
+                    s
+                )))
+            }
+        }
+    }
+
+    pub fn Arg<'a, 'b>() -> Arg<'a, 'b> {
+        Arg::with_name("output")
+            .long("output")
+            .short("o")
+            .help("output format: table, wide, json, yaml, name or jsonpath=<template>")
+            .default_value("table")
+            .takes_value(true)
+    }
+
+    pub fn Init(cmd_matches: &ArgMatches) -> Result<Self> {
+        Self::Parse(cmd_matches.value_of("output").unwrap())
+    }
+}
+
+// ObjectValue is the json of the object as printed. It keeps the revision, so that a
+// printed object can be edited and sent back with a revision check.
+pub fn ObjectValue(obj: &DataObject<Value>) -> Value {
+    serde_json::to_value(obj).unwrap()
+}
+
+// PrintObjects prints a single object, or a list of objects of the same type.
+pub fn PrintObjects(objs: &[DataObject<Value>], format: &OutputFormat, isList: bool) -> Result<()> {
+    match format {
+        OutputFormat::Table | OutputFormat::Wide => {
+            PrintTable(objs, *format == OutputFormat::Wide);
+        }
+        OutputFormat::Json => {
+            let v = if isList {
+                Value::Array(objs.iter().map(ObjectValue).collect())
+            } else {
+                objs.first().map(ObjectValue).unwrap_or(Value::Null)
+            };
+            println!("{}", serde_json::to_string_pretty(&v)?);
+        }
+        OutputFormat::Yaml => {
+            // a yaml stream, which ixctl create/apply reads back
+            for obj in objs {
+                let s = serde_yaml::to_string(&ObjectValue(obj))
+                    .map_err(|e| Error::CommonError(format!("{}", e)))?;
+                println!("---\n{}", s.trim_end());
+            }
+        }
+        OutputFormat::Name => {
+            for obj in objs {
+                println!("{}", obj.StoreKey());
+            }
+        }
+        OutputFormat::JsonPath(template) => {
+            let template = JsonPathTemplate::Parse(template)?;
+            for obj in objs {
+                let s = template.Eval(&ObjectValue(obj))?;
+                if s.ends_with('\n') {
+                    print!("{}", s);
+                } else {
+                    println!("{}", s);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn PrintTable(objs: &[DataObject<Value>], wide: bool) {
+    if objs.is_empty() {
+        println!("No objects found");
+        return;
+    }
+
+    let header = Header(&objs[0].objType, wide);
+    let mut rows = vec![header.iter().map(|s| s.to_string()).collect::<Vec<_>>()];
+    for obj in objs {
+        let mut row = Row(obj, wide);
+        row.resize(header.len(), String::new());
+        rows.push(row);
+    }
+
+    PrintRows(&rows, "");
+}
+
+// PrintRows prints rows as aligned columns, the first row is usually the header.
+pub fn PrintRows(rows: &[Vec<String>], indent: &str) {
+    for line in FormatRows(rows, indent) {
+        println!("{}", line);
+    }
+}
+
+// FormatRows returns the lines PrintRows prints.
+pub fn FormatRows(rows: &[Vec<String>], indent: &str) -> Vec<String> {
+    let mut widths = Vec::new();
+    for row in rows {
+        for (i, cell) in row.iter().enumerate() {
+            if i == widths.len() {
+                widths.push(0);
+            }
+            widths[i] = widths[i].max(cell.len());
+        }
+    }
+
+    rows.iter()
+        .map(|row| format!("{}{}", indent, FormatRow(row, &widths)))
+        .collect()
+}
+
+fn FormatRow(row: &[String], widths: &[usize]) -> String {
+    let cells: Vec<String> = row
+        .iter()
+        .enumerate()
+        .map(|(i, cell)| format!("{:width$}", cell, width = widths[i]))
+        .collect();
+    cells.join("   ").trim_end().to_owned()
+}
+
+// WatchPrinter prints watch events as they arrive in the chosen format. Table columns
+// can only be sized by the rows seen so far, so they widen as longer values show up.
+pub struct WatchPrinter {
+    format: OutputFormat,
+    template: Option<JsonPathTemplate>,
+    widths: Vec<usize>,
+    headerPrinted: bool,
+}
+
+impl WatchPrinter {
+    pub fn New(format: &OutputFormat) -> Result<Self> {
+        let template = match format {
+            OutputFormat::JsonPath(t) => Some(JsonPathTemplate::Parse(t)?),
+            _ => None,
+        };
+
+        Ok(Self {
+            format: format.clone(),
+            template,
+            widths: Vec::new(),
+            headerPrinted: false,
+        })
+    }
+
+    // TableLines returns the table row of an event, preceded by the header for the
+    // first one.
+    pub fn TableLines(&mut self, eventType: String, obj: &DataObject<Value>) -> Vec<String> {
+        let wide = self.format == OutputFormat::Wide;
+        let mut header = vec!["EVENT".to_owned()];
+        header.extend(Header(&obj.objType, wide).iter().map(|s| s.to_string()));
+        let mut row = vec![eventType];
+        row.append(&mut Row(obj, wide));
+        row.resize(header.len(), String::new());
+
+        self.widths.resize(header.len(), 0);
+        // the widest event type, so the column doesn't move on the first Modified
+        self.widths[0] = self.widths[0].max("Modified".len());
+        for r in [&header, &row] {
+            for (i, cell) in r.iter().enumerate() {
+                self.widths[i] = self.widths[i].max(cell.len());
+            }
+        }
+
+        let mut lines = Vec::new();
+        if !self.headerPrinted {
+            lines.push(FormatRow(&header, &self.widths));
+            self.headerPrinted = true;
+        }
+        lines.push(FormatRow(&row, &self.widths));
+        lines
+    }
+
+    pub fn Print(&mut self, event: &WatchEvent) -> Result<()> {
+        let eventType = match &event.type_ {
+            EventType::Added | EventType::Modified | EventType::Deleted => {
+                format!("{:?}", &event.type_)
+            }
+            EventType::Error(e) => {
+                eprintln!("watch error: {}", e);
+                return Ok(());
+            }
+            // InitDone only marks the end of the existing objects
+            _ => return Ok(()),
+        };
+
+        let obj = &event.obj;
+        match &self.format {
+            OutputFormat::Table | OutputFormat::Wide => {
+                for line in self.TableLines(eventType, obj) {
+                    println!("{}", line);
+                }
+            }
+            OutputFormat::Json => {
+                let v = serde_json::json!({"type": eventType, "object": ObjectValue(obj)});
+                println!("{}", serde_json::to_string_pretty(&v)?);
+            }
+            OutputFormat::Yaml => {
+                let v = serde_json::json!({"type": eventType, "object": ObjectValue(obj)});
+                let s =
+                    serde_yaml::to_string(&v).map_err(|e| Error::CommonError(format!("{}", e)))?;
+                println!("---\n{}", s.trim_end());
+            }
+            OutputFormat::Name => {
+                println!("{} {}", eventType, obj.StoreKey());
+            }
+            OutputFormat::JsonPath(_) => {
+                let s = self.template.as_ref().unwrap().Eval(&ObjectValue(obj))?;
+                if s.ends_with('\n') {
+                    print!("{}", s);
+                } else {
+                    println!("{}", s);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn Header(objType: &str, wide: bool) -> Vec<&'static str> {
+    let mut header = vec!["TENANT", "NAMESPACE", "NAME"];
+    match objType {
+        Function::KEY => {
+            header.append(&mut vec!["REVISION", "IMAGE", "GPU", "VRAM(MB)", "STATE"]);
+            if wide {
+                header.append(&mut vec!["CPU(m)", "MEM(MB)", "PORT", "LABELS"]);
+            }
+        }
+        FuncPod::KEY => {
+            header.append(&mut vec!["STATE", "NODE", "GPUS"]);
+            if wide {
+                header.append(&mut vec!["FUNCTION", "REVISION", "POD IP", "CREATE TYPE"]);
+            }
+        }
+        ContainerSnapshot::KEY => {
+            header.append(&mut vec![
+                "NODE",
+                "STATE",
+                "HOST MEM",
+                "CHECKPOINT",
+                "GPU MEM",
+            ]);
+            if wide {
+                header.append(&mut vec!["FATBIN", "FUNCTION"]);
+            }
+        }
+        Node::KEY => {
+            header.append(&mut vec!["NODE IP", "GPU TYPE", "GPUS"]);
+            if wide {
+                header.append(&mut vec!["CIDR", "CPU(m)", "MEM(MB)"]);
+            }
+        }
+        _ => {
+            header.push("REVISION");
+            if wide {
+                header.push("LABELS");
+            }
+        }
+    }
+
+    header
+}
+
+fn Row(obj: &DataObject<Value>, wide: bool) -> Vec<String> {
+    let mut row = vec![obj.tenant.clone(), obj.namespace.clone(), obj.name.clone()];
+    let typed = match obj.Typed() {
+        // print what's known about objects which don't decode
+        Err(_) => return row,
+        Ok(t) => t,
+    };
+
+    match typed {
+        TypedObject::Function(f) => {
+            let spec = &f.object.spec;
+            row.push(format!("{}", f.Version()));
+            row.push(spec.image.clone());
+            row.push(format!("{}", spec.resources.gpu.gpuCount));
+            row.push(format!("{}", spec.resources.gpu.vRam));
+            row.push(format!("{:?}", f.object.status.state));
+            if wide {
+                row.push(format!("{}", spec.resources.cpu));
+                row.push(format!("{}", spec.resources.memory));
+                row.push(format!("{}", spec.endpoint.port));
+                row.push(obj.labels.String());
+            }
+        }
+        TypedObject::Pod(p) => {
+            let spec = &p.object.spec;
+            let gpus: Vec<String> = spec
+                .allocResources
+                .gpus
+                .map
+                .keys()
+                .map(|id| format!("{}", id))
+                .collect();
+            row.push(format!("{:?}", p.object.status.state));
+            row.push(spec.nodename.clone());
+            row.push(gpus.join(","));
+            if wide {
+                row.push(spec.funcname.clone());
+                row.push(format!("{}", spec.fprevision));
+                row.push(spec.pod_ip.clone());
+                row.push(format!("{:?}", spec.create_type));
+            }
+        }
+        TypedObject::Snapshot(s) => {
+            let info = &s.object.info;
+            let gpuMem: u64 = info.gpuMemSizes.values().sum();
+            row.push(s.object.nodename.clone());
+            row.push(format!("{:?}", s.object.state));
+            row.push(HumanSize(info.hostMemSize));
+            row.push(HumanSize(info.processCheckpointSize));
+            row.push(HumanSize(gpuMem));
+            if wide {
+                row.push(HumanSize(info.fatbinSize));
+                row.push(s.object.funckey.clone());
+            }
+        }
+        TypedObject::Node(n) => {
+            let spec = &n.object;
+            row.push(spec.nodeIp.clone());
+            row.push(spec.resources.GPUTypes());
+            row.push(format!("{}", spec.resources.gpus.map.len()));
+            if wide {
+                row.push(spec.cidr.clone());
+                row.push(format!("{}", spec.resources.cpu));
+                row.push(format!("{}", spec.resources.memory));
+            }
+        }
+        _ => {
+            row.push(format!("{}", obj.revision));
+            if wide {
+                row.push(obj.labels.String());
+            }
+        }
+    }
+
+    row
+}
+
+// HumanSize formats a byte count with binary units, e.g. 1.5Gi.
+pub fn HumanSize(size: u64) -> String {
+    const UNITS: [&str; 5] = ["", "Ki", "Mi", "Gi", "Ti"];
+    let mut s = size as f64;
+    let mut unit = 0;
+    while s >= 1024.0 && unit < UNITS.len() - 1 {
+        s /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        return format!("{}", size);
+    }
+
+    format!("{:.1}{}", s, UNITS[unit])
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonPathStep {
+    // .name or ['name']
+    Field(String),
+    // [n], negative counts from the end
+    Index(i64),
+    // [*] or .*, all the elements or members
+    All,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplatePart {
+    Text(String),
+    Path(Vec<JsonPathStep>),
+}
+
+// JsonPathTemplate is the kubectl style jsonpath template subset: literal text with
+// {expr} parts, where expr is a path such as .object.spec.envs[0][1] or .labels['app'].
+// A template without braces is taken as a single expression.
+#[derive(Debug, Clone)]
+pub struct JsonPathTemplate {
+    pub parts: Vec<TemplatePart>,
+}
+
+impl JsonPathTemplate {
+    pub fn Parse(template: &str) -> Result<Self> {
+        if !template.contains('{') {
+            return Ok(Self {
+                parts: vec![TemplatePart::Path(ParseJsonPath(template)?)],
+            });
+        }
+
+        let mut parts = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                parts.push(TemplatePart::Text(rest[..start].to_owned()));
+            }
+            let end = match rest[start..].find('}') {
+                None => {
+                    return Err(Error::CommonError(format!(
+                        "unclosed {{ in jsonpath template {:?}",
+                        template
+                    )))
+                }
+                Some(end) => start + end,
+            };
+            let expr = rest[start + 1..end].trim();
+            // {"\t"} style literals
+            if expr.starts_with('"') {
+                let text: String = serde_json::from_str(expr).map_err(|_| {
+                    Error::CommonError(format!("invalid string literal {} in jsonpath", expr))
+                })?;
+                parts.push(TemplatePart::Text(text));
+            } else {
+                parts.push(TemplatePart::Path(ParseJsonPath(expr)?));
+            }
+            rest = &rest[end + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(TemplatePart::Text(rest.to_owned()));
+        }
+
+        Ok(Self { parts })
+    }
+
+    pub fn Eval(&self, v: &Value) -> Result<String> {
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                TemplatePart::Text(t) => out.push_str(t),
+                TemplatePart::Path(path) => {
+                    let values = EvalJsonPath(v, path);
+                    let values: Vec<String> = values
+                        .iter()
+                        .map(|v| match v {
+                            Value::String(s) => s.clone(),
+                            v => v.to_string(),
+                        })
+                        .collect();
+                    out.push_str(&values.join(" "));
+                }
+            }
+        }
+
+        Ok(out)
+    }
+}
+
+pub fn ParseJsonPath(expr: &str) -> Result<Vec<JsonPathStep>> {
+    let invalid = || Error::CommonError(format!("invalid jsonpath expression {:?}", expr));
+
+    let mut steps = Vec::new();
+    let chars: Vec<char> = expr.trim().chars().collect();
+    let mut i = 0;
+    // the leading $ and . of the root are optional
+    if i < chars.len() && chars[i] == '$' {
+        i += 1;
+    }
+    while i < chars.len() {
+        match chars[i] {
+            '.' => {
+                i += 1;
+                let start = i;
+                while i < chars.len() && chars[i] != '.' && chars[i] != '[' {
+                    i += 1;
+                }
+                let name: String = chars[start..i].iter().collect();
+                match name.as_str() {
+                    // a lone "." is the root
+                    "" if i == chars.len() && steps.is_empty() => (),
+                    "" => return Err(invalid()),
+                    "*" => steps.push(JsonPathStep::All),
+                    _ => steps.push(JsonPathStep::Field(name)),
+                }
+            }
+            '[' => {
+                let end = match chars[i..].iter().position(|c| *c == ']') {
+                    None => return Err(invalid()),
+                    Some(end) => i + end,
+                };
+                let inner: String = chars[i + 1..end].iter().collect();
+                let inner = inner.trim();
+                if inner == "*" {
+                    steps.push(JsonPathStep::All);
+                } else if (inner.starts_with('\'') && inner.ends_with('\'') && inner.len() >= 2)
+                    || (inner.starts_with('"') && inner.ends_with('"') && inner.len() >= 2)
+                {
+                    steps.push(JsonPathStep::Field(inner[1..inner.len() - 1].to_owned()));
+                } else {
+                    match inner.parse::<i64>() {
+                        Err(_) => return Err(invalid()),
+                        Ok(idx) => steps.push(JsonPathStep::Index(idx)),
+                    }
+                }
+                i = end + 1;
+            }
+            _ if i == 0 => {
+                // a path without the leading dot, e.g. object.spec.image
+                let start = i;
+                while i < chars.len() && chars[i] != '.' && chars[i] != '[' {
+                    i += 1;
+                }
+                steps.push(JsonPathStep::Field(chars[start..i].iter().collect()));
+            }
+            _ => return Err(invalid()),
+        }
+    }
+
+    Ok(steps)
+}
+
+// EvalJsonPath returns all the values the path selects, missing fields select nothing.
+pub fn EvalJsonPath<'a>(v: &'a Value, path: &[JsonPathStep]) -> Vec<&'a Value> {
+    let mut current = vec![v];
+    for step in path {
+        let mut next = Vec::new();
+        for v in current {
+            match (step, v) {
+                (JsonPathStep::Field(name), Value::Object(map)) => {
+                    if let Some(v) = map.get(name) {
+                        next.push(v);
+                    }
+                }
+                (JsonPathStep::Index(idx), Value::Array(arr)) => {
+                    let idx = if *idx < 0 {
+                        arr.len() as i64 + *idx
+                    } else {
+                        *idx
+                    };
+                    if idx >= 0 && (idx as usize) < arr.len() {
+                        next.push(&arr[idx as usize]);
+                    }
+                }
+                (JsonPathStep::All, Value::Array(arr)) => next.extend(arr.iter()),
+                (JsonPathStep::All, Value::Object(map)) => next.extend(map.values()),
+                _ => (),
+            }
+        }
+        current = next;
+    }
+
+    current
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn HumanSizes() {
+        assert_eq!(HumanSize(0), "0");
+        assert_eq!(HumanSize(1023), "1023");
+        assert_eq!(HumanSize(1024), "1.0Ki");
+        assert_eq!(HumanSize(1536), "1.5Ki");
+        assert_eq!(HumanSize(5 * 1024 * 1024), "5.0Mi");
+        assert_eq!(HumanSize(3 << 30), "3.0Gi");
+        // Ti is the largest unit
+        assert_eq!(HumanSize(2048 << 40), "2048.0Ti");
+    }
+
+    #[test]
+    fn AlignedRows() {
+        let rows: Vec<Vec<String>> = [
+            vec!["NAME", "STATE", "GPUS"],
+            vec!["llama-3.2-3b", "Ready", "1"],
+            // a short row and an empty last cell
+            vec!["qwen"],
+            vec!["f1", "Fail", ""],
+        ]
+        .iter()
+        .map(|r| r.iter().map(|c| c.to_string()).collect())
+        .collect();
+
+        assert_eq!(
+            FormatRows(&rows, "  "),
+            vec![
+                "  NAME           STATE   GPUS",
+                "  llama-3.2-3b   Ready   1",
+                "  qwen",
+                "  f1             Fail",
+            ]
+        );
+        assert!(FormatRows(&[], "").is_empty());
+    }
+
+    #[test]
+    fn WatchTableWidens() {
+        let obj = |name: &str, revision: i64| -> DataObject<Value> {
+            serde_json::from_value(json!({
+                "objType": "namespace",
+                "tenant": "t1",
+                "namespace": "system",
+                "name": name,
+                "revision": revision,
+                "object": { "spec": {}, "status": { "disable": false } },
+            }))
+            .unwrap()
+        };
+
+        let mut printer = WatchPrinter::New(&OutputFormat::Table).unwrap();
+        // the header only comes with the first event
+        assert_eq!(
+            printer.TableLines("Added".to_owned(), &obj("ns1", 7)),
+            vec![
+                "EVENT      TENANT   NAMESPACE   NAME   REVISION",
+                "Added      t1       system      ns1    7",
+            ]
+        );
+        // a longer name widens its column for the later rows
+        assert_eq!(
+            printer.TableLines("Modified".to_owned(), &obj("namespace-two", 12)),
+            vec!["Modified   t1       system      namespace-two   12"]
+        );
+        assert_eq!(
+            printer.TableLines("Deleted".to_owned(), &obj("ns1", 13)),
+            vec!["Deleted    t1       system      ns1             13"]
+        );
+    }
+
+    #[test]
+    fn ParseOutputFormat() {
+        assert_eq!(OutputFormat::Parse("wide").unwrap(), OutputFormat::Wide);
+        assert_eq!(
+            OutputFormat::Parse("jsonpath={.name}").unwrap(),
+            OutputFormat::JsonPath("{.name}".to_owned())
+        );
+        assert!(OutputFormat::Parse("xml").is_err());
+    }
+
+    #[test]
+    fn JsonPathTemplates() {
+        let v = json!({
+            "name": "f1",
+            "labels": {"app.kubernetes.io/name": "llama"},
+            "object": {"spec": {"envs": [["A", "1"], ["B", "2"]], "port": 8000}}
+        });
+        let Eval = |template: &str| JsonPathTemplate::Parse(template).unwrap().Eval(&v).unwrap();
+
+        assert_eq!(Eval("object.spec.port"), "8000");
+        assert_eq!(Eval("{.name}:{.object.spec.port}"), "f1:8000");
+        assert_eq!(Eval("{.object.spec.envs[-1][0]}"), "B");
+        assert_eq!(Eval("{.object.spec.envs[*][1]}"), "1 2");
+        assert_eq!(Eval("{.labels['app.kubernetes.io/name']}"), "llama");
+        assert_eq!(Eval("{.name}{\"\\t\"}{.missing}"), "f1\t");
+
+        for template in ["{.name", "{.object..spec}", "{.object[x]}", "{.envs[0}"] {
+            assert!(JsonPathTemplate::Parse(template).is_err(), "{}", template);
+        }
+    }
+}
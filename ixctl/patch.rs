@@ -0,0 +1,119 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use serde_json::Value;
+
+use inferxlib::common::*;
+use inferxlib::data_obj::PatchType;
+
+use crate::command::{
+    GlobalConfig, NamespaceArg, ObjectNameArg, ParseObjectName, TenantArg, SUPPORT_OBJ_TYPES,
+};
+
+#[derive(Debug)]
+pub struct PatchCmd {
+    pub objType: String,
+    pub tenant: String,
+    pub namespace: String,
+    pub name: String,
+    pub patchType: PatchType,
+    pub patch: Value,
+}
+
+impl PatchCmd {
+    pub fn Init(cmd_matches: &ArgMatches) -> Result<Self> {
+        let content = match (
+            cmd_matches.value_of("patch"),
+            cmd_matches.value_of("patch-file"),
+        ) {
+            (Some(p), None) => p.to_owned(),
+            (None, Some(f)) => std::fs::read_to_string(f)?,
+            _ => {
+                return Err(Error::CommonError(
+                    "one of --patch or --patch-file is required".to_owned(),
+                ))
+            }
+        };
+
+        let (tenant, namespace, name) = ParseObjectName(cmd_matches);
+        Ok(Self {
+            objType: cmd_matches.value_of("type").unwrap().to_string(),
+            tenant,
+            namespace,
+            name,
+            patchType: PatchType::Parse(cmd_matches.value_of("patch-type").unwrap())?,
+            patch: serde_json::from_str(&content)?,
+        })
+    }
+
+    pub fn SubCommand<'a, 'b>() -> App<'a, 'b> {
+        SubCommand::with_name("patch")
+            .setting(AppSettings::ColoredHelp)
+            .arg(
+                Arg::with_name("type")
+                    .required(true)
+                    .help("object type")
+                    .possible_values(&SUPPORT_OBJ_TYPES)
+                    .takes_value(true),
+            )
+            .arg(TenantArg())
+            .arg(NamespaceArg())
+            .arg(ObjectNameArg())
+            .arg(
+                Arg::with_name("patch-type")
+                    .long("type")
+                    .help("merge: RFC 7386 merge patch, json: RFC 6902 json patch")
+                    .possible_values(&["merge", "json"])
+                    .default_value("merge")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("patch")
+                    .long("patch")
+                    .short("p")
+                    .help("patch document, paths start at the object root, e.g. /object/spec")
+                    .conflicts_with("patch-file")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("patch-file")
+                    .long("patch-file")
+                    .help("file containing the patch document")
+                    .takes_value(true),
+            )
+            .about("Patch a stored object with a merge patch or json patch")
+    }
+
+    pub async fn Run(&self, gConfig: &GlobalConfig) -> Result<()> {
+        let client = gConfig.GetObjectClient();
+
+        let (obj, version) = client
+            .Patch(
+                &gConfig.accessToken,
+                &self.objType,
+                &self.tenant,
+                &self.namespace,
+                &self.name,
+                self.patchType,
+                &self.patch,
+            )
+            .await?;
+
+        let obj = obj.CopyWithRev(version, version);
+        println!("{:#?}", obj);
+
+        Ok(())
+    }
+}
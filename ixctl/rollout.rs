@@ -0,0 +1,352 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use serde_json::Value;
+
+use inferxlib::common::*;
+use inferxlib::data_obj::DataObject;
+use inferxlib::obj_mgr::func_mgr::{
+    ChangedSpecFields, FuncObject, Function, Rollback, SpecVersion,
+};
+
+use crate::command::{GlobalConfig, NamespaceArg, TenantArg};
+use crate::describe::FuncPodsAndSnapshots;
+use crate::diff::{ClassifiedDiffs, PrintDiffs};
+use crate::output::{OutputFormat, PrintObjects, PrintRows};
+
+#[derive(Debug)]
+pub enum RolloutAction {
+    History,
+    Undo,
+}
+
+#[derive(Debug)]
+pub struct RolloutCmd {
+    pub action: RolloutAction,
+    pub tenant: String,
+    pub namespace: String,
+    pub name: String,
+    pub toRevision: Option<i64>,
+    // the revision whose changes history shows
+    pub revision: Option<i64>,
+    pub dryRun: bool,
+    pub output: OutputFormat,
+}
+
+impl RolloutCmd {
+    pub fn Init(cmd_matches: &ArgMatches) -> Result<Self> {
+        let (action, m) = match cmd_matches.subcommand() {
+            ("history", Some(m)) => (RolloutAction::History, m),
+            ("undo", Some(m)) => (RolloutAction::Undo, m),
+            // clap enforces one of the subcommands
+            x => panic!("rollout command not recognized {:?}", x),
+        };
+
+        let parseRevision = |arg: &str| -> Result<Option<i64>> {
+            match m.value_of(arg) {
+                None => Ok(None),
+                Some(s) => match s.parse::<i64>() {
+                    Ok(rev) => Ok(Some(rev)),
+                    Err(_) => {
+                        Err(Error::CommonError(format!(
+                            "invalid --{} {:?}, expect an integer",
+                            arg, s
+                        )))
+                    }
+                },
+            }
+        };
+        let toRevision = parseRevision("to-revision")?;
+        let revision = parseRevision("revision")?;
+
+        let output = match action {
+            RolloutAction::History => OutputFormat::Init(m)?,
+            RolloutAction::Undo => OutputFormat::Table,
+        };
+
+        Ok(Self {
+            action,
+            tenant: m.value_of("tenant").unwrap_or_default().to_string(),
+            namespace: m.value_of("namespace").unwrap_or_default().to_string(),
+            name: m.value_of("name").unwrap().to_string(),
+            toRevision,
+            revision,
+            dryRun: m.is_present("dry-run"),
+            output,
+        })
+    }
+
+    pub fn SubCommand<'a, 'b>() -> App<'a, 'b> {
+        let name = || {
+            Arg::with_name("name")
+                .required(true)
+                .help("function name")
+                .takes_value(true)
+        };
+
+        SubCommand::with_name("rollout")
+            .setting(AppSettings::ColoredHelp)
+            .setting(AppSettings::SubcommandRequired)
+            .subcommand(
+                SubCommand::with_name("history")
+                    .arg(name())
+                    .arg(TenantArg())
+                    .arg(NamespaceArg())
+                    .arg(OutputFormat::Arg())
+                    .arg(
+                        Arg::with_name("revision")
+                            .long("revision")
+                            .help("show the changes of this revision from the one before it")
+                            .takes_value(true),
+                    )
+                    .about(
+                        "List the kept revisions of a function and the pods and snapshots of each",
+                    ),
+            )
+            .subcommand(
+                SubCommand::with_name("undo")
+                    .arg(name())
+                    .arg(TenantArg())
+                    .arg(NamespaceArg())
+                    .arg(
+                        Arg::with_name("to-revision")
+                            .long("to-revision")
+                            .help("revision to roll back to, the previous one by default")
+                            .takes_value(true),
+                    )
+                    .arg(
+                        Arg::with_name("dry-run")
+                            .long("dry-run")
+                            .help("only print the revision which would be restored"),
+                    )
+                    .about("Roll a function back to the spec of an earlier revision"),
+            )
+            .about("Manage the revisions of a function")
+    }
+
+    pub async fn Run(&self, gConfig: &GlobalConfig) -> Result<()> {
+        let client = gConfig.GetObjectClient();
+        let history = client
+            .History(Function::KEY, &self.tenant, &self.namespace, &self.name)
+            .await?;
+        if history.is_empty() {
+            return Err(Error::NotExist(format!(
+                "history of function {}/{}/{}",
+                &self.tenant, &self.namespace, &self.name
+            )));
+        }
+
+        match self.action {
+            RolloutAction::History => {
+                if let Some(rev) = self.revision {
+                    return PrintRevisionChanges(&history, rev);
+                }
+                if self.output != OutputFormat::Table && self.output != OutputFormat::Wide {
+                    return PrintObjects(&history, &self.output, true);
+                }
+                let (pods, snapshots) =
+                    FuncPodsAndSnapshots(&client, &self.tenant, &self.namespace, &self.name)
+                        .await?;
+                let funcs = history
+                    .iter()
+                    .map(|o| o.To::<FuncObject>())
+                    .collect::<Result<Vec<Function>>>()?;
+
+                let mut rows = vec![Row(&[
+                    "CURRENT",
+                    "REVISION",
+                    "IMAGE",
+                    "GPU",
+                    "VRAM(MB)",
+                    "CHANGED",
+                    "PODS",
+                    "SNAPSHOTS",
+                ])];
+                for (i, func) in funcs.iter().enumerate() {
+                    let changed = match i {
+                        0 => Vec::new(),
+                        _ => ChangedSpecFields(&history[i - 1], &history[i]),
+                    };
+                    let version = func.Version();
+                    let spec = &func.object.spec;
+                    rows.push(vec![
+                        if i == funcs.len() - 1 { "*" } else { "" }.to_owned(),
+                        format!("{}", version),
+                        spec.image.clone(),
+                        format!("{}", spec.resources.gpu.gpuCount),
+                        format!("{}", spec.resources.gpu.vRam),
+                        changed.join(","),
+                        format!(
+                            "{}",
+                            pods.iter()
+                                .filter(|p| p.object.spec.fprevision == version)
+                                .count()
+                        ),
+                        format!(
+                            "{}",
+                            snapshots
+                                .iter()
+                                .filter(|s| SnapshotRevision(&s.object.funckey) == Some(version))
+                                .count()
+                        ),
+                    ]);
+                }
+                PrintRows(&rows, "");
+
+                // the pods and snapshots of the revisions before the current one keep
+                // their resources until they are cleaned up
+                let current = funcs.last().unwrap().Version();
+                let mut stale = Vec::new();
+                for pod in &pods {
+                    if pod.object.spec.fprevision != current {
+                        stale.push(vec![
+                            format!("pod {}", &pod.name),
+                            format!("revision {}", pod.object.spec.fprevision),
+                            format!("{:?}", pod.object.status.state),
+                            pod.object.spec.nodename.clone(),
+                        ]);
+                    }
+                }
+                for snapshot in &snapshots {
+                    let revision = SnapshotRevision(&snapshot.object.funckey);
+                    if revision != Some(current) {
+                        stale.push(vec![
+                            format!("snapshot {}", &snapshot.object.funckey),
+                            format!("revision {}", revision.unwrap_or_default()),
+                            format!("{:?}", snapshot.object.state),
+                            snapshot.object.nodename.clone(),
+                        ]);
+                    }
+                }
+                if !stale.is_empty() {
+                    println!();
+                    println!("Referencing old revisions ({}):", stale.len());
+                    PrintRows(&stale, "  ");
+                }
+            }
+            RolloutAction::Undo => {
+                let current = history.last().unwrap();
+                let currentVersion = SpecVersion(current);
+                let target = match self.toRevision {
+                    Some(rev) if rev == currentVersion => {
+                        return Err(Error::CommonError(format!(
+                            "function {} is already at revision {}",
+                            current.Key(),
+                            rev
+                        )))
+                    }
+                    Some(rev) => history.iter().rev().find(|o| SpecVersion(o) == rev),
+                    None => history
+                        .iter()
+                        .rev()
+                        .find(|o| SpecVersion(o) != currentVersion),
+                };
+                let target = match target {
+                    Some(t) => t,
+                    None => {
+                        return Err(Error::NotExist(format!(
+                            "revision {} of function {}, see ixctl rollout history",
+                            self.toRevision
+                                .map(|r| format!("{}", r))
+                                .unwrap_or("before current".to_owned()),
+                            current.Key()
+                        )))
+                    }
+                };
+
+                let changed = ChangedSpecFields(current, target);
+                if self.dryRun {
+                    println!(
+                        "function {} would roll back from revision {} to {}, changed: {}",
+                        current.Key(),
+                        currentVersion,
+                        SpecVersion(target),
+                        changed.join(",")
+                    );
+                    return Ok(());
+                }
+
+                let obj = Rollback(current, target)?;
+                let errs = obj.Validate();
+                if !errs.IsEmpty() {
+                    return Err(Error::CommonError(format!(
+                        "rolled back object {} fails validation: {}",
+                        obj.Key(),
+                        errs
+                    )));
+                }
+
+                let expectRevision = current.ExpectRevision()?;
+                let revision = client
+                    .Update(&gConfig.accessToken, obj, expectRevision)
+                    .await?;
+                println!(
+                    "function {} rolled back to the spec of revision {} (revision {}), changed: {}",
+                    current.Key(),
+                    SpecVersion(target),
+                    revision,
+                    changed.join(",")
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// PrintRevisionChanges prints the changes of revision rev of a function from the kept
+// revision before it.
+fn PrintRevisionChanges(history: &[DataObject<Value>], rev: i64) -> Result<()> {
+    let current = history.last().unwrap();
+    let idx = match history.iter().position(|o| SpecVersion(o) == rev) {
+        Some(idx) => idx,
+        None => {
+            return Err(Error::NotExist(format!(
+                "revision {} of function {}, see ixctl rollout history",
+                rev,
+                current.Key()
+            )))
+        }
+    };
+    if idx == 0 {
+        println!(
+            "revision {} is the oldest kept revision of function {}, there is none to compare with",
+            rev,
+            current.Key()
+        );
+        return Ok(());
+    }
+
+    let prev = &history[idx - 1];
+    let diffs = ClassifiedDiffs(Function::KEY, prev.Diff(&history[idx])?);
+    println!(
+        "function {} revision {} from revision {}: {} changes",
+        current.Key(),
+        rev,
+        SpecVersion(prev),
+        diffs.len()
+    );
+    PrintDiffs(&diffs);
+    Ok(())
+}
+
+// snapshot funckey is <tenant>/<namespace>/<funcname>/<revision>
+fn SnapshotRevision(funckey: &str) -> Option<i64> {
+    funckey.rsplit('/').next()?.parse().ok()
+}
+
+fn Row(cells: &[&str]) -> Vec<String> {
+    cells.iter().map(|s| s.to_string()).collect()
+}
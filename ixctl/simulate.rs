@@ -0,0 +1,164 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use std::path::Path;
+
+use inferxlib::common::*;
+use inferxlib::sched_sim::{SchedSim, SimConfig, SimReport};
+
+use crate::command::GlobalConfig;
+use crate::output::{OutputFormat, PrintRows};
+
+#[derive(Debug)]
+pub struct SimulateCmd {
+    pub filename: String,
+    pub output: OutputFormat,
+}
+
+impl SimulateCmd {
+    pub fn Init(cmd_matches: &ArgMatches) -> Result<Self> {
+        let output = OutputFormat::Init(cmd_matches)?;
+        match output {
+            OutputFormat::Table | OutputFormat::Json | OutputFormat::Yaml => (),
+            _ => {
+                return Err(Error::CommonError(
+                    "simulate supports the table, json and yaml output formats".to_owned(),
+                ))
+            }
+        }
+
+        Ok(Self {
+            filename: cmd_matches.value_of("filename").unwrap().to_string(),
+            output,
+        })
+    }
+
+    pub fn SubCommand<'a, 'b>() -> App<'a, 'b> {
+        SubCommand::with_name("simulate")
+            .setting(AppSettings::ColoredHelp)
+            .arg(
+                Arg::with_name("filename")
+                    .long("filename")
+                    .short("f")
+                    .required(true)
+                    .help("simulation file with the nodes, functions and events")
+                    .takes_value(true),
+            )
+            .arg(OutputFormat::Arg())
+            .about("Simulate the scheduling of functions on a set of nodes without a gateway")
+    }
+
+    pub async fn Run(&self, _gConfig: &GlobalConfig) -> Result<()> {
+        let config = SimConfig::Load(&self.filename)?;
+        let dir = Path::new(&self.filename)
+            .parent()
+            .unwrap_or(Path::new("."))
+            .to_path_buf();
+        let report = SchedSim::Run(&config, &dir)?;
+
+        match &self.output {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+            OutputFormat::Yaml => {
+                let s = serde_yaml::to_string(&report)
+                    .map_err(|e| Error::CommonError(format!("{}", e)))?;
+                print!("{}", s);
+            }
+            _ => PrintReport(&report),
+        }
+
+        if !report.failures.is_empty() {
+            return Err(Error::CommonError(format!(
+                "{} scheduling failures",
+                report.failures.len()
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn PrintReport(report: &SimReport) {
+    println!("Pods:");
+    let mut rows = vec![vec![
+        "ID".to_owned(),
+        "FUNCTION".to_owned(),
+        "NODE".to_owned(),
+        "STATE".to_owned(),
+        "GPUS".to_owned(),
+        "TOPOLOGY".to_owned(),
+    ]];
+    for pod in &report.pods {
+        rows.push(vec![
+            pod.id.clone(),
+            pod.funcname.clone(),
+            pod.node.clone(),
+            format!("{:?}", pod.state),
+            pod.gpus
+                .iter()
+                .map(|g| g.to_string())
+                .collect::<Vec<_>>()
+                .join(","),
+            pod.topologyScore.map(|s| s.to_string()).unwrap_or_default(),
+        ]);
+    }
+    PrintRows(&rows, "  ");
+
+    println!("Nodes:");
+    let mut rows = vec![vec![
+        "NAME".to_owned(),
+        "GPUS".to_owned(),
+        "FREE GPUS".to_owned(),
+        "FREE VRAM".to_owned(),
+        "MAX FREE VRAM".to_owned(),
+        "FRAGMENTATION".to_owned(),
+        "FREE MEM".to_owned(),
+        "SNAPSHOTS".to_owned(),
+        "STANDBY".to_owned(),
+        "READY".to_owned(),
+    ]];
+    for node in &report.nodes {
+        rows.push(vec![
+            node.name.clone(),
+            node.gpuCount.to_string(),
+            node.freeGpus.to_string(),
+            format!("{}MB", node.freeVRam),
+            format!("{}MB", node.maxFreeVRam),
+            format!("{:.0}%", node.fragmentation * 100.0),
+            format!("{}MB", node.freeMemory),
+            node.snapshots.to_string(),
+            node.standbyPods.to_string(),
+            node.readyPods.to_string(),
+        ]);
+    }
+    PrintRows(&rows, "  ");
+
+    if !report.failures.is_empty() {
+        println!("Failures:");
+        let mut rows = vec![vec![
+            "STEP".to_owned(),
+            "ACTION".to_owned(),
+            "FUNCTION".to_owned(),
+            "REASON".to_owned(),
+        ]];
+        for f in &report.failures {
+            rows.push(vec![
+                f.step.to_string(),
+                format!("{:?}", f.action).to_lowercase(),
+                f.funcname.clone(),
+                f.reason.clone(),
+            ]);
+        }
+        PrintRows(&rows, "  ");
+    }
+}
@@ -14,64 +14,99 @@
 
 use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 
-use inferxlib::{common::*, data_obj::DataObject};
-use serde_json::Value;
+use inferxlib::common::*;
 
-use crate::command::GlobalConfig;
+use crate::command::{GlobalConfig, ParseRevision, RevisionArg};
+use crate::validate::LoadValidObjects;
 
 #[derive(Debug)]
 pub struct UpdateCmd {
     pub filename: String,
+    pub dryRun: bool,
+    // expected revision of the stored object, overrides the revision in the file
+    pub revision: Option<i64>,
+    // update the objects without revision at the one they are stored at
+    pub force: bool,
 }
 
 impl UpdateCmd {
     pub fn Init(cmd_matches: &ArgMatches) -> Result<Self> {
-        return Ok(Self {
+        Ok(Self {
             filename: cmd_matches.value_of("filename").unwrap().to_string(),
-        });
+            dryRun: cmd_matches.is_present("dry-run"),
+            revision: ParseRevision(cmd_matches)?,
+            force: cmd_matches.is_present("force"),
+        })
     }
 
     pub fn SubCommand<'a, 'b>() -> App<'a, 'b> {
-        return SubCommand::with_name("update")
+        SubCommand::with_name("update")
             .setting(AppSettings::ColoredHelp)
             .arg(
                 Arg::with_name("filename")
                     .required(true)
-                    .help("file name")
+                    .help("json or yaml file name, or - for stdin")
                     .takes_value(true),
             )
-            .about("Create a python function package");
+            .arg(
+                Arg::with_name("dry-run")
+                    .long("dry-run")
+                    .help("only validate the file, don't send it to the gateway"),
+            )
+            .arg(RevisionArg())
+            .arg(
+                Arg::with_name("force")
+                    .long("force")
+                    .conflicts_with("revision")
+                    .help("overwrite the objects which have no revision whatever their stored revision"),
+            )
+            .about("Update objects from a file")
     }
 
     pub async fn Run(&self, gConfig: &GlobalConfig) -> Result<()> {
         let client = gConfig.GetObjectClient();
-        println!("UpdateCmd is {:?} server is {:?}", self, gConfig.gatewayUrl);
 
-        let content = match std::fs::read_to_string(&self.filename) {
-            Err(e) => {
-                println!("Can't open file {} with error {:?}", &self.filename, e);
-                return Ok(());
-            }
-            Ok(c) => c,
-        };
+        let objs = LoadValidObjects(&self.filename, self.dryRun)?;
+        if self.revision.is_some() && objs.len() > 1 {
+            return Err(Error::CommonError(format!(
+                "--revision needs a single object but {} has {}",
+                &self.filename,
+                objs.len()
+            )));
+        }
+
+        if self.dryRun {
+            return Ok(());
+        }
 
-        let o = match DataObject::<Value>::NewFromString(&content) {
-            Err(e) => {
-                println!(
-                    "Can't parse file {} as Json with error {:?}",
-                    &self.filename, e
-                );
-                return Ok(());
+        for o in &objs {
+            if self.revision.is_none() && o.revision == 0 && !self.force {
+                return Err(Error::CommonError(format!(
+                    "{} has no revision, update the output of ixctl get, pass --revision or --force to overwrite it",
+                    o.Key()
+                )));
             }
-            Ok(c) => c,
-        };
+        }
 
-        let version = client.Update(&gConfig.accessToken, o.clone()).await?;
+        for o in objs {
+            // an object printed by get carries its revision, which guards the update
+            let expectRevision = match self.revision {
+                Some(rev) => rev,
+                None if o.revision == 0 => client
+                    .Get(&o.objType, &o.tenant, &o.namespace, &o.name)
+                    .await?
+                    .ExpectRevision()?,
+                None => o.revision,
+            };
+            let version = client
+                .Update(&gConfig.accessToken, o.clone(), expectRevision)
+                .await?;
 
-        let obj = o.CopyWithRev(version, version);
+            let obj = o.CopyWithRev(version, version);
 
-        println!("{:#?}", obj);
+            println!("{:#?}", obj);
+        }
 
-        return Ok(());
+        Ok(())
     }
 }
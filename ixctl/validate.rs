@@ -0,0 +1,226 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use serde_json::Value;
+use std::io::Read;
+use std::path::Path;
+
+use inferxlib::common::*;
+use inferxlib::data_obj::{DataObject, ObjectFormat};
+use inferxlib::validation::ErrorList;
+
+use crate::command::GlobalConfig;
+
+#[derive(Debug)]
+pub struct ValidateCmd {
+    pub filenames: Vec<String>,
+}
+
+impl ValidateCmd {
+    pub fn Init(cmd_matches: &ArgMatches) -> Result<Self> {
+        Ok(Self {
+            filenames: cmd_matches
+                .values_of("filename")
+                .unwrap()
+                .map(|s| s.to_string())
+                .collect(),
+        })
+    }
+
+    pub fn SubCommand<'a, 'b>() -> App<'a, 'b> {
+        SubCommand::with_name("validate")
+            .setting(AppSettings::ColoredHelp)
+            .arg(
+                Arg::with_name("filename")
+                    .required(true)
+                    .multiple(true)
+                    .help("object files, directories of object files, or - for stdin")
+                    .takes_value(true),
+            )
+            .about("Validate object files locally without contacting the gateway")
+    }
+
+    pub async fn Run(&self, _gConfig: &GlobalConfig) -> Result<()> {
+        let mut filenames = Vec::new();
+        for filename in &self.filenames {
+            filenames.append(&mut ObjectFiles(filename)?);
+        }
+
+        let mut objCnt = 0;
+        let mut failCnt = 0;
+        for filename in &filenames {
+            for report in ValidateFile(filename) {
+                report.Print();
+                objCnt += 1;
+                if !report.IsValid() {
+                    failCnt += 1;
+                }
+            }
+        }
+
+        println!(
+            "{} objects in {} files checked, {} valid, {} invalid",
+            objCnt,
+            filenames.len(),
+            objCnt - failCnt,
+            failCnt
+        );
+
+        if failCnt > 0 {
+            return Err(Error::CommonError(format!(
+                "{} of {} objects fail validation",
+                failCnt, objCnt
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+// ObjectFiles expands a directory into the object files in it, sorted by name.
+pub fn ObjectFiles(filename: &str) -> Result<Vec<String>> {
+    let path = Path::new(filename);
+    if filename == "-" || !path.is_dir() {
+        return Ok(vec![filename.to_owned()]);
+    }
+
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(path)? {
+        let path = entry?.path();
+        let name = path.to_string_lossy().to_string();
+        if path.is_file() && ObjectFormat::FromFilename(&name).is_some() {
+            files.push(name);
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+#[derive(Debug)]
+pub struct ValidateReport {
+    pub filename: String,
+    pub obj: Option<DataObject<Value>>,
+    pub errs: ErrorList,
+}
+
+impl ValidateReport {
+    pub fn IsValid(&self) -> bool {
+        self.errs.IsEmpty()
+    }
+
+    pub fn Print(&self) {
+        let id = match &self.obj {
+            None => "".to_owned(),
+            Some(o) => format!(" {} {}", &o.objType, o.Key()),
+        };
+
+        if self.IsValid() {
+            println!("{}{}: OK", &self.filename, id);
+            return;
+        }
+
+        println!(
+            "{}{}: FAIL ({} errors)",
+            &self.filename,
+            id,
+            self.errs.Len()
+        );
+        for e in &self.errs.errs {
+            println!("    {}", e);
+        }
+    }
+}
+
+// ValidateFile reads all the objects in a file, or stdin for "-", and validates each
+// of them. The format is picked by file extension, json or yaml.
+pub fn ValidateFile(filename: &str) -> Vec<ValidateReport> {
+    let newReport = || ValidateReport {
+        filename: filename.to_owned(),
+        obj: None,
+        errs: ErrorList::default(),
+    };
+
+    let mut content = String::new();
+    let res = if filename == "-" {
+        std::io::stdin().read_to_string(&mut content).map(|_| ())
+    } else {
+        std::fs::read_to_string(filename).map(|c| content = c)
+    };
+
+    if let Err(e) = res {
+        let mut report = newReport();
+        report.errs.Add("", &format!("can't open file: {}", e));
+        return vec![report];
+    }
+
+    let format = ObjectFormat::FromFilename(filename).unwrap_or(ObjectFormat::Detect(&content));
+    let objs = match DataObject::<Value>::NewListFromString(&content, format) {
+        Err(e) => {
+            let mut report = newReport();
+            match e {
+                Error::CommonError(e) => report
+                    .errs
+                    .Add("", &format!("can't parse as {:?}: {}", format, e)),
+                e => report
+                    .errs
+                    .Add("", &format!("can't parse as {:?}: {:?}", format, e)),
+            }
+            return vec![report];
+        }
+        Ok(objs) => objs,
+    };
+
+    if objs.is_empty() {
+        let mut report = newReport();
+        report.errs.Add("", "no object found");
+        return vec![report];
+    }
+
+    let mut reports = Vec::new();
+    for obj in objs {
+        let mut report = newReport();
+        report.errs = obj.Validate();
+        report.obj = Some(obj);
+        reports.push(report);
+    }
+
+    reports
+}
+
+// LoadValidObjects returns all the objects in a file if they are all valid. Reports of
+// invalid objects are printed, and those of the valid ones too if verbose.
+pub fn LoadValidObjects(filename: &str, verbose: bool) -> Result<Vec<DataObject<Value>>> {
+    let mut objs = Vec::new();
+    let mut failCnt = 0;
+    for report in ValidateFile(filename) {
+        if verbose || !report.IsValid() {
+            report.Print();
+        }
+
+        if !report.IsValid() {
+            failCnt += 1;
+            continue;
+        }
+
+        objs.push(report.obj.unwrap());
+    }
+
+    if failCnt > 0 {
+        return Err(Error::CommonError(format!("{} fails validation", filename)));
+    }
+
+    Ok(objs)
+}